@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+
+/// A token that has expired at the given day of its `ExpiredTokens`
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExpiredToken {
+    pub token: String,
+    pub day: u32,
+}
+
+/// Stored expired tokens, either a plain list of tokens (older market files) or the tokens with
+/// the day they have expired at
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum StoredExpiredTokens {
+    Tokens(Vec<String>),
+    WithDays { day: u32, tokens: Vec<ExpiredToken> },
+}
+
+/**
+ * `ExpiredTokens` contains the tokens of all locks that have expired, were settled or were
+ * cancelled. This is needed to return `ExpiredToken` instead of `UnrecognizedToken`.
+ * A lookup is O(1). Tokens are evicted after a retention window, so that the memory does not
+ * grow in long simulations. Afterwards, a token is unrecognized.
+ */
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "StoredExpiredTokens")]
+pub struct ExpiredTokens {
+    /// Number of days that have passed
+    day: u32,
+    /// All tokens, the oldest first
+    tokens: VecDeque<ExpiredToken>,
+    /// All tokens, to look them up
+    #[serde(skip)]
+    index: HashSet<String>,
+}
+
+impl From<StoredExpiredTokens> for ExpiredTokens {
+    fn from(stored: StoredExpiredTokens) -> Self {
+        let (day, tokens) = match stored {
+            StoredExpiredTokens::Tokens(tokens) => (
+                0,
+                tokens
+                    .into_iter()
+                    .map(|token| ExpiredToken { token, day: 0 })
+                    .collect(),
+            ),
+            StoredExpiredTokens::WithDays { day, tokens } => (day, tokens),
+        };
+        let mut expired_tokens = Self {
+            day,
+            ..Self::default()
+        };
+        for expired_token in tokens {
+            if expired_tokens.index.insert(expired_token.token.clone()) {
+                expired_tokens.tokens.push_back(expired_token);
+            }
+        }
+        expired_tokens
+    }
+}
+
+impl ExpiredTokens {
+    /// Adds the given token, it expires today
+    pub fn insert(&mut self, token: String) {
+        if self.index.insert(token.clone()) {
+            self.tokens.push_back(ExpiredToken {
+                token,
+                day: self.day,
+            });
+        }
+    }
+
+    /// Checks if the given token is contained
+    pub fn contains(&self, token: &str) -> bool {
+        self.index.contains(token)
+    }
+
+    /// Returns the number of tokens
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Checks if there are no tokens
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_empty()
+    }
+
+    /// Starts the next day. Tokens that have expired at least the given number of days ago are
+    /// evicted.
+    pub fn next_day(&mut self, retention_in_days: u32) {
+        self.day = self.day.saturating_add(1);
+        while let Some(oldest) = self.tokens.front() {
+            if self.day.saturating_sub(oldest.day) < retention_in_days {
+                break;
+            }
+            if let Some(oldest) = self.tokens.pop_front() {
+                self.index.remove(&oldest.token);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::goods::expired_tokens::ExpiredTokens;
+
+    #[test]
+    fn test_expired_tokens() {
+        let mut tokens = ExpiredTokens::default();
+        tokens.insert("first".to_string());
+        tokens.insert("first".to_string());
+        assert_eq!(1, tokens.len(), "Tokens must not be added twice");
+
+        tokens.next_day(2);
+        tokens.insert("second".to_string());
+        assert!(tokens.contains("first") && tokens.contains("second"));
+
+        tokens.next_day(2);
+        assert!(!tokens.contains("first"), "First token must be evicted");
+        assert!(tokens.contains("second"));
+        tokens.next_day(2);
+        assert!(tokens.is_empty());
+    }
+
+    #[test]
+    fn test_read_expired_tokens() {
+        let mut tokens = ExpiredTokens::default();
+        tokens.insert("first".to_string());
+        tokens.next_day(10);
+        let json = serde_json::to_string(&tokens).unwrap();
+        let read_tokens: ExpiredTokens = serde_json::from_str(&json).unwrap();
+        assert_eq!(tokens, read_tokens);
+
+        // older files only contain the tokens
+        let read_tokens: ExpiredTokens = serde_json::from_str(r#"["first","second"]"#).unwrap();
+        assert_eq!(2, read_tokens.len());
+        assert!(read_tokens.contains("second"));
+    }
+}
@@ -1,23 +1,78 @@
-use crate::goods::good_status::{GoodLock, GoodStatus};
+use crate::goods::expired_tokens::ExpiredTokens;
+use crate::goods::good_status::GoodLock;
+use crate::market::lock_listener::LockSide;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use unitn_market_2022::good::good_kind::GoodKind;
 
 /**
  * The `GoodMetadata` struct represents meta information about the `Good`.
+ * A good can be locked multiple times for sell and for buy. Each lock is identified by its
+ * transaction token.
  */
-#[derive(Debug, PartialEq, Clone)]
+#[derive(Debug, PartialEq, Clone, Serialize, Deserialize)]
+#[serde(from = "StoredGoodMetadata")]
 pub struct GoodMetadata {
     /// Base sell price of the good, in EUR
     pub base_sell_price: f32,
     /// Base buy price of the good, in EUR
     pub base_buy_price: f32,
-    /// Is the good locked for sell
-    pub sell_status: GoodStatus,
-    /// Is the good locked for buy
-    pub buy_status: GoodStatus,
-    /// All expired sell tokens
-    pub expired_sell_tokens: Vec<String>,
-    /// All expired buy tokens
-    pub expired_buy_tokens: Vec<String>,
+    /// All sell locks of the good, by token (ordered, so that they are iterated in the same order
+    /// on every run)
+    pub sell_locks: BTreeMap<String, GoodLock>,
+    /// All buy locks of the good, by token
+    pub buy_locks: BTreeMap<String, GoodLock>,
+    /// Expired sell tokens (including settled and cancelled ones) of the retention window
+    pub expired_sell_tokens: ExpiredTokens,
+    /// Expired buy tokens (including settled and cancelled ones) of the retention window
+    pub expired_buy_tokens: ExpiredTokens,
+}
+
+/// Lock status of a good, as it was stored by older market files that allowed one lock per side
+#[derive(Deserialize)]
+enum StoredGoodStatus {
+    Locked(GoodLock),
+    Available,
+}
+
+/// Stored metadata, either with all locks by token or with the single lock status of older
+/// market files
+#[derive(Deserialize)]
+struct StoredGoodMetadata {
+    base_sell_price: f32,
+    base_buy_price: f32,
+    #[serde(default)]
+    sell_locks: BTreeMap<String, GoodLock>,
+    #[serde(default)]
+    buy_locks: BTreeMap<String, GoodLock>,
+    #[serde(default)]
+    sell_status: Option<StoredGoodStatus>,
+    #[serde(default)]
+    buy_status: Option<StoredGoodStatus>,
+    #[serde(default)]
+    expired_sell_tokens: ExpiredTokens,
+    #[serde(default)]
+    expired_buy_tokens: ExpiredTokens,
+}
+
+impl From<StoredGoodMetadata> for GoodMetadata {
+    fn from(stored: StoredGoodMetadata) -> Self {
+        let with_status = |mut locks: BTreeMap<String, GoodLock>,
+                           status: Option<StoredGoodStatus>| {
+            if let Some(StoredGoodStatus::Locked(lock)) = status {
+                locks.insert(lock.transaction_token.clone(), lock);
+            }
+            locks
+        };
+        Self {
+            base_sell_price: stored.base_sell_price,
+            base_buy_price: stored.base_buy_price,
+            sell_locks: with_status(stored.sell_locks, stored.sell_status),
+            buy_locks: with_status(stored.buy_locks, stored.buy_status),
+            expired_sell_tokens: stored.expired_sell_tokens,
+            expired_buy_tokens: stored.expired_buy_tokens,
+        }
+    }
 }
 
 impl GoodMetadata {
@@ -26,134 +81,181 @@ impl GoodMetadata {
         Self {
             base_sell_price: 1.0 / exchange_rate,
             base_buy_price: exchange_rate,
-            sell_status: GoodStatus::Available,
-            buy_status: GoodStatus::Available,
-            expired_sell_tokens: Vec::new(),
-            expired_buy_tokens: Vec::new(),
+            sell_locks: BTreeMap::new(),
+            buy_locks: BTreeMap::new(),
+            expired_sell_tokens: ExpiredTokens::default(),
+            expired_buy_tokens: ExpiredTokens::default(),
         }
     }
 }
 
 impl GoodMetadata {
-    /// Locks the Good (adds a `GoodStatus::Locked` to the metadata) for sell
+    /// Adds a new sell lock with the given token to the metadata
     pub fn lock_for_sell(
         &mut self,
         locked_qty: f32,
         kind: GoodKind,
         offer: f32,
+        token: String,
         trader_name: String,
-    ) -> String {
-        let lock = GoodLock::new(locked_qty, kind, offer, trader_name);
-        let token = lock.transaction_token.clone();
-        self.sell_status = GoodStatus::Locked(lock);
-        token
+    ) {
+        let lock = GoodLock::new(locked_qty, kind, offer, token.clone(), trader_name);
+        self.sell_locks.insert(token, lock);
     }
 
-    /// Locks the Good (adds a `GoodStatus::Locked` to the metadata) for buy
+    /// Adds a new buy lock with the given token to the metadata
     pub fn lock_for_buy(
         &mut self,
         locked_qty: f32,
         kind: GoodKind,
         bid: f32,
+        token: String,
         trader_name: String,
-    ) -> String {
-        let lock = GoodLock::new(locked_qty, kind, bid, trader_name);
-        let token = lock.transaction_token.clone();
-        self.buy_status = GoodStatus::Locked(lock);
-        token
+    ) {
+        let lock = GoodLock::new(locked_qty, kind, bid, token.clone(), trader_name);
+        self.buy_locks.insert(token, lock);
     }
 
-    /// Unlocks the good for sell
-    pub fn unlock_for_sell(&mut self) {
-        if let GoodStatus::Locked(lock) = &self.sell_status {
-            self.expired_sell_tokens
-                .push(lock.transaction_token.clone());
-        } else {
-            panic!("Cannot unlock sell lock because there is no lock");
-        }
-        self.sell_status = GoodStatus::Available;
+    /// Removes the sell lock for the given token and marks the token as expired.
+    /// Returns the removed lock, if available.
+    pub fn unlock_for_sell(&mut self, token: &str) -> Option<GoodLock> {
+        let lock = self.sell_locks.remove(token)?;
+        self.expired_sell_tokens.insert(token.to_string());
+        Some(lock)
     }
 
-    /// Unlocks the good for buy
-    pub fn unlock_for_buy(&mut self) {
-        if let GoodStatus::Locked(lock) = &self.buy_status {
-            self.expired_buy_tokens.push(lock.transaction_token.clone());
-        } else {
-            panic!("Cannot unlock buy lock because there is no lock");
-        }
-        self.buy_status = GoodStatus::Available;
+    /// Removes the buy lock for the given token and marks the token as expired.
+    /// Returns the removed lock, if available.
+    pub fn unlock_for_buy(&mut self, token: &str) -> Option<GoodLock> {
+        let lock = self.buy_locks.remove(token)?;
+        self.expired_buy_tokens.insert(token.to_string());
+        Some(lock)
     }
 
-    /// Check if this Good is locked for sell
+    /// Check if this Good has at least one sell lock
     pub fn is_locked_for_sell(&self) -> bool {
-        match self.sell_status {
-            GoodStatus::Available => false,
-            GoodStatus::Locked(_) => true,
-        }
+        !self.sell_locks.is_empty()
     }
 
-    /// Check if this Good is locked for buy
+    /// Check if this Good has at least one buy lock
     pub fn is_locked_for_buy(&self) -> bool {
-        match self.buy_status {
-            GoodStatus::Available => false,
-            GoodStatus::Locked(_) => true,
-        }
+        !self.buy_locks.is_empty()
     }
 
-    /// Returns a reference of the sell lock, if available
-    pub fn get_sell_lock(&self) -> Option<&GoodLock> {
-        match &self.sell_status {
-            GoodStatus::Available => None,
-            GoodStatus::Locked(lock) => Some(lock),
-        }
+    /// Check if this Good has at least one sell lock of the given trader
+    pub fn is_locked_for_sell_by(&self, trader_name: &str) -> bool {
+        self.is_locked_for_sell() && self.sell_locks.values().any(|l| l.is_owned_by(trader_name))
     }
 
-    /// Returns a mut reference of the sell lock, if available
-    pub fn get_mut_sell_lock(&mut self) -> Option<&mut GoodLock> {
-        match &mut self.sell_status {
-            GoodStatus::Available => None,
-            GoodStatus::Locked(lock) => Some(lock),
-        }
+    /// Check if this Good has at least one buy lock of the given trader
+    pub fn is_locked_for_buy_by(&self, trader_name: &str) -> bool {
+        self.is_locked_for_buy() && self.buy_locks.values().any(|l| l.is_owned_by(trader_name))
     }
 
-    /// Returns a reference of the buy lock, if available
-    pub fn get_buy_lock(&self) -> Option<&GoodLock> {
-        match &self.buy_status {
-            GoodStatus::Available => None,
-            GoodStatus::Locked(lock) => Some(lock),
-        }
+    /// Returns a reference of the sell lock for the given token, if available
+    pub fn get_sell_lock(&self, token: &str) -> Option<&GoodLock> {
+        self.sell_locks.get(token)
+    }
+
+    /// Returns a reference of the buy lock for the given token, if available
+    pub fn get_buy_lock(&self, token: &str) -> Option<&GoodLock> {
+        self.buy_locks.get(token)
+    }
+
+    /// Returns the quantity of the good that is locked for buy
+    pub fn get_locked_buy_quantity(&self) -> f32 {
+        self.buy_locks
+            .values()
+            .map(|lock| lock.locked_original_qty)
+            .sum()
     }
 
-    /// Returns a mut reference of the buy lock, if available
-    pub fn get_mut_buy_lock(&mut self) -> Option<&mut GoodLock> {
-        match &mut self.buy_status {
-            GoodStatus::Available => None,
-            GoodStatus::Locked(lock) => Some(lock),
+    /// Returns the EUR quantity that is promised by all sell locks of this good
+    pub fn get_locked_sell_eur_quantity(&self) -> f32 {
+        self.sell_locks.values().map(|lock| lock.eur_quantity).sum()
+    }
+
+    /// Increases the age of all locks by one day. Locks that have reached the given max. age
+    /// are unlocked instead, they are returned with their side.
+    pub fn age_locks(&mut self, max_age_in_days: u8) -> Vec<(LockSide, GoodLock)> {
+        let is_expired = |lock: &&GoodLock| lock.age_in_days >= max_age_in_days;
+        let expired_sell_tokens = self
+            .sell_locks
+            .values()
+            .filter(is_expired)
+            .map(|lock| lock.transaction_token.clone())
+            .collect::<Vec<String>>();
+        let expired_buy_tokens = self
+            .buy_locks
+            .values()
+            .filter(is_expired)
+            .map(|lock| lock.transaction_token.clone())
+            .collect::<Vec<String>>();
+
+        let mut expired_locks = Vec::new();
+        for token in expired_sell_tokens.iter() {
+            if let Some(lock) = self.unlock_for_sell(token) {
+                expired_locks.push((LockSide::Sell, lock));
+            }
+        }
+        for token in expired_buy_tokens.iter() {
+            if let Some(lock) = self.unlock_for_buy(token) {
+                expired_locks.push((LockSide::Buy, lock));
+            }
         }
+
+        self.sell_locks
+            .values_mut()
+            .chain(self.buy_locks.values_mut())
+            .for_each(|lock| lock.increase_age_by_one());
+        expired_locks
+    }
+
+    /// Starts the next day of the expired tokens, tokens that have expired at least the given
+    /// number of days ago are evicted
+    pub fn age_expired_tokens(&mut self, retention_in_days: u32) {
+        self.expired_sell_tokens.next_day(retention_in_days);
+        self.expired_buy_tokens.next_day(retention_in_days);
     }
 
     /// Checks if it contains an expired sell token
-    pub fn has_expired_sell_token(&self, token: &String) -> bool {
+    pub fn has_expired_sell_token(&self, token: &str) -> bool {
         self.expired_sell_tokens.contains(token)
     }
 
     /// Checks if it contains an expired buy token
-    pub fn has_expired_buy_token(&self, token: &String) -> bool {
+    pub fn has_expired_buy_token(&self, token: &str) -> bool {
         self.expired_buy_tokens.contains(token)
     }
 
+    /// Multiplies the base buy price with the given factor. The price is kept, if the new
+    /// price would not be a positive number (e.g. after a factor of NaN or infinity).
     pub fn fluctuate_buy_price_with_factor(&mut self, factor: f32) {
-        self.base_buy_price *= factor;
+        let price = self.base_buy_price * factor;
+        if is_valid_price(price) {
+            self.base_buy_price = price;
+        }
     }
 
+    /// Multiplies the base sell price with the given factor, see
+    /// `fluctuate_buy_price_with_factor`
     pub fn fluctuate_sell_price_with_factor(&mut self, factor: f32) {
-        self.base_sell_price *= factor;
+        let price = self.base_sell_price * factor;
+        if is_valid_price(price) {
+            self.base_sell_price = price;
+        }
     }
 }
 
+/// Checks if the given price is finite and positive
+fn is_valid_price(price: f32) -> bool {
+    price.is_finite() && price > 0.0
+}
+
 #[cfg(test)]
 mod tests {
     use crate::goods::good_metadata::GoodMetadata;
+    use crate::market::lock_listener::LockSide;
     use unitn_market_2022::good::consts::DEFAULT_GOOD_KIND;
     use unitn_market_2022::good::good_kind::GoodKind;
 
@@ -190,20 +292,28 @@ mod tests {
             "Metadata is not suppose to be buy-locked"
         );
 
-        let invalid_buy_lock = meta.get_buy_lock();
+        let invalid_token = "INVALID_TOKEN";
+        let invalid_buy_lock = meta.get_buy_lock(invalid_token);
         assert_eq!(None, invalid_buy_lock, "Buy lock must be None");
-        let invalid_sell_lock = meta.get_sell_lock();
+        let invalid_sell_lock = meta.get_sell_lock(invalid_token);
         assert_eq!(None, invalid_sell_lock, "Sell lock must be None");
 
-        let _ = meta.lock_for_buy(100.0, GoodKind::EUR, 120.0, trader_name);
+        let token = "TOKEN".to_string();
+        meta.lock_for_buy(100.0, GoodKind::EUR, 120.0, token.clone(), trader_name);
         assert!(meta.is_locked_for_buy(), "Metadata must be locked for buy");
         assert!(
             !meta.is_locked_for_sell(),
             "Metadata should not be locked for sell"
         );
 
-        let lock = meta.get_buy_lock();
+        let lock = meta.get_buy_lock(&token);
         assert!(lock.is_some(), "Buy lock can't be None");
+        assert_eq!(
+            100.0,
+            meta.get_locked_buy_quantity(),
+            "Locked buy quantity must be {}",
+            100.0
+        );
     }
 
     #[test]
@@ -219,20 +329,28 @@ mod tests {
             "Metadata is not suppose to be buy-locked"
         );
 
-        let invalid_buy_lock = meta.get_buy_lock();
+        let invalid_token = "INVALID_TOKEN";
+        let invalid_buy_lock = meta.get_buy_lock(invalid_token);
         assert_eq!(None, invalid_buy_lock, "Buy lock must be None");
-        let invalid_sell_lock = meta.get_sell_lock();
+        let invalid_sell_lock = meta.get_sell_lock(invalid_token);
         assert_eq!(None, invalid_sell_lock, "Sell lock must be None");
 
-        let _ = meta.lock_for_sell(100.0, GoodKind::EUR, 120.0, trader_name);
+        let token = "TOKEN".to_string();
+        meta.lock_for_sell(100.0, GoodKind::EUR, 120.0, token.clone(), trader_name);
         assert!(meta.is_locked_for_sell(), "Metadata must be sell-locked");
         assert!(
             !meta.is_locked_for_buy(),
             "Metadata is not suppose to be buy-locked"
         );
 
-        let lock = meta.get_sell_lock();
+        let lock = meta.get_sell_lock(&token);
         assert!(lock.is_some(), "Sell lock can't be None");
+        assert_eq!(
+            120.0,
+            meta.get_locked_sell_eur_quantity(),
+            "Locked EUR quantity must be {}",
+            120.0
+        );
     }
 
     #[test]
@@ -262,6 +380,21 @@ mod tests {
             old_sell_price,
             meta.base_sell_price
         );
+
+        let old_buy_price = meta.base_buy_price;
+        let old_sell_price = meta.base_sell_price;
+        meta.fluctuate_buy_price_with_factor(f32::NAN);
+        meta.fluctuate_buy_price_with_factor(1000.0 / 0.0);
+        meta.fluctuate_sell_price_with_factor(0.0);
+        meta.fluctuate_sell_price_with_factor(-1.0);
+        assert_eq!(
+            old_buy_price, meta.base_buy_price,
+            "Bad factors must be ignored"
+        );
+        assert_eq!(
+            old_sell_price, meta.base_sell_price,
+            "Bad factors must be ignored"
+        );
     }
 
     #[test]
@@ -290,7 +423,14 @@ mod tests {
         );
 
         // test sell
-        let token = meta.lock_for_sell(100.0, DEFAULT_GOOD_KIND, 100.0, trader_name.clone());
+        let token = "SELL_TOKEN".to_string();
+        meta.lock_for_sell(
+            100.0,
+            DEFAULT_GOOD_KIND,
+            100.0,
+            token.clone(),
+            trader_name.clone(),
+        );
         assert!(
             meta.expired_sell_tokens.is_empty(),
             "There can't be any expired sell-token (None has expired yet)"
@@ -300,7 +440,7 @@ mod tests {
             "Sell-Token '{}' can't have expired yet",
             token
         );
-        meta.unlock_for_sell();
+        meta.unlock_for_sell(&token);
         assert!(
             !meta.expired_sell_tokens.is_empty(),
             "There must be at least one expired sell-token"
@@ -312,7 +452,8 @@ mod tests {
         );
 
         // test buy
-        let token = meta.lock_for_buy(100.0, DEFAULT_GOOD_KIND, 100.0, trader_name);
+        let token = "BUY_TOKEN".to_string();
+        meta.lock_for_buy(100.0, DEFAULT_GOOD_KIND, 100.0, token.clone(), trader_name);
         assert!(
             meta.expired_buy_tokens.is_empty(),
             "There can't be any expired buy-token (None has expired yet)"
@@ -322,7 +463,7 @@ mod tests {
             "Buy-Token '{}' can't be expired yet",
             token
         );
-        meta.unlock_for_buy();
+        meta.unlock_for_buy(&token);
         assert!(
             !meta.expired_buy_tokens.is_empty(),
             "There must be at least one expired buy-token"
@@ -333,4 +474,102 @@ mod tests {
             token
         );
     }
+
+    #[test]
+    fn test_multiple_locks() {
+        let trader_name = "TEST_TRADER".to_string();
+        let mut meta = GoodMetadata::new(1.0);
+
+        let first_token = "FIRST_TOKEN".to_string();
+        meta.lock_for_buy(
+            100.0,
+            GoodKind::USD,
+            120.0,
+            first_token.clone(),
+            trader_name.clone(),
+        );
+        let second_token = "SECOND_TOKEN".to_string();
+        meta.lock_for_buy(50.0, GoodKind::USD, 60.0, second_token.clone(), trader_name);
+        assert_eq!(2, meta.buy_locks.len(), "There must be two buy locks");
+        assert_eq!(
+            150.0,
+            meta.get_locked_buy_quantity(),
+            "Locked buy quantity must be the sum of all locks"
+        );
+
+        // settle only the first lock
+        let lock = meta.unlock_for_buy(&first_token);
+        assert!(lock.is_some(), "First lock must have been removed");
+        assert!(
+            meta.is_locked_for_buy(),
+            "Second lock '{}' must still exist",
+            second_token
+        );
+        assert_eq!(
+            50.0,
+            meta.get_locked_buy_quantity(),
+            "Only the second lock is still locked"
+        );
+        assert!(
+            meta.unlock_for_buy(&first_token).is_none(),
+            "A lock can't be removed twice"
+        );
+    }
+
+    #[test]
+    fn test_age_locks() {
+        let trader_name = "TEST_TRADER".to_string();
+        let max_age = 3;
+        let mut meta = GoodMetadata::new(1.0);
+        let buy_token = "BUY_TOKEN".to_string();
+        meta.lock_for_buy(
+            100.0,
+            GoodKind::USD,
+            120.0,
+            buy_token.clone(),
+            trader_name.clone(),
+        );
+        let sell_token = "SELL_TOKEN".to_string();
+        meta.lock_for_sell(100.0, GoodKind::USD, 80.0, sell_token.clone(), trader_name);
+
+        // locks start at the age of 1 day
+        for _ in 1..max_age {
+            meta.age_locks(max_age);
+        }
+        assert_eq!(
+            max_age,
+            meta.get_buy_lock(&buy_token).unwrap().age_in_days,
+            "Buy lock must be {} days old",
+            max_age
+        );
+        assert!(
+            meta.is_locked_for_sell(),
+            "Sell lock can't have expired yet"
+        );
+
+        assert_eq!(
+            1,
+            meta.get_buy_lock(&buy_token)
+                .unwrap()
+                .get_remaining_days(max_age),
+            "Buy lock must expire at the next day"
+        );
+        let expired_locks = meta.age_locks(max_age);
+        assert_eq!(2, expired_locks.len(), "Both locks must be returned");
+        assert!(expired_locks
+            .iter()
+            .any(|(side, lock)| *side == LockSide::Buy && lock.transaction_token == buy_token));
+        assert!(!meta.is_locked_for_buy(), "Buy lock must have expired");
+        assert!(!meta.is_locked_for_sell(), "Sell lock must have expired");
+        assert!(
+            meta.has_expired_buy_token(&buy_token),
+            "Buy-Token '{}' must be expired",
+            buy_token
+        );
+        assert!(
+            meta.has_expired_sell_token(&sell_token),
+            "Sell-Token '{}' must be expired",
+            sell_token
+        );
+    }
 }
@@ -1,7 +1,14 @@
 use crate::goods::good_metadata::GoodMetadata;
-use crate::goods::good_status::GoodStatus;
-use crate::goods::goods_factory::{GoodWithMeta, GoodsFactory};
-use std::slice::IterMut;
+use crate::goods::good_status::GoodLock;
+use crate::goods::goods_factory::{ExchangeRates, GoodEntry, GoodWithMeta, GoodsFactory};
+use crate::goods::serde_good_kind::ALL_GOOD_KINDS;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::File;
+use std::io;
+use std::io::{BufReader, BufWriter, Write};
+use std::slice::{Iter, IterMut};
 use unitn_market_2022::good::consts::DEFAULT_GOOD_KIND;
 use unitn_market_2022::good::good::Good;
 use unitn_market_2022::good::good_kind::GoodKind;
@@ -15,6 +22,21 @@ use unitn_market_2022::market::good_label::GoodLabel;
  */
 pub struct GoodStorage(Vec<GoodWithMeta>);
 
+/**
+ * A `GoodSnapshot` is the representation of a single good and its metadata, as it is
+ * written to and read from a file or a market snapshot.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoodSnapshot {
+    /// Kind of the good
+    #[serde(with = "crate::goods::serde_good_kind")]
+    pub kind: GoodKind,
+    /// Quantity of the good
+    pub quantity: f32,
+    /// Metadata of the good (prices, locks, and expired tokens)
+    pub metadata: GoodMetadata,
+}
+
 // Struct functions
 impl GoodStorage {
     /// Generates goods with random quantities up to the given available quantity
@@ -22,10 +44,114 @@ impl GoodStorage {
         Self(GoodsFactory::random_goods(available_quantity))
     }
 
+    /// Generates goods with random quantities, generated by the given rng, and the given
+    /// exchange rates
+    pub fn new_random_with_rates<R: Rng>(
+        available_quantity: f32,
+        exchange_rates: &ExchangeRates,
+        rng: &mut R,
+    ) -> Self {
+        Self(GoodsFactory::random_goods_with_rates(
+            available_quantity,
+            exchange_rates,
+            rng,
+        ))
+    }
+
     /// Generates goods with the given quantities
     pub fn with_quantities(eur: f32, yen: f32, usd: f32, yuan: f32) -> Self {
         Self(GoodsFactory::all_with_quantities(eur, yen, usd, yuan))
     }
+
+    /// Generates goods with the given quantities and exchange rates
+    pub fn with_quantities_and_rates(
+        eur: f32,
+        yen: f32,
+        usd: f32,
+        yuan: f32,
+        exchange_rates: &ExchangeRates,
+    ) -> Self {
+        Self(GoodsFactory::all_with_quantities_and_rates(
+            eur,
+            yen,
+            usd,
+            yuan,
+            exchange_rates,
+        ))
+    }
+
+    /// Generates the goods of the given entries, e.g. only YEN, if all values are valid (see
+    /// `GoodStorage::from_snapshots`). The default good is always added.
+    pub fn from_entries(entries: &[GoodEntry]) -> Option<Self> {
+        let snapshots = GoodsFactory::with_entries(entries)
+            .into_iter()
+            .map(|(good, metadata)| GoodSnapshot {
+                kind: good.get_kind(),
+                quantity: good.get_qty(),
+                metadata,
+            })
+            .collect();
+        GoodStorage::from_snapshots(snapshots)
+    }
+
+    /// Reads the goods from the given file.
+    /// Returns `None` if the file can't be read or if it contains bad values.
+    pub fn from_file(path: &str) -> Option<Self> {
+        let file = File::open(path).ok()?;
+        let stored_goods: Vec<GoodSnapshot> = serde_json::from_reader(BufReader::new(file)).ok()?;
+        GoodStorage::from_snapshots(stored_goods)
+    }
+
+    /// Builds a storage from the given snapshots, if all values are valid.
+    /// The default good must be present, no good kind may be present more than once, quantities
+    /// must not be negative and prices must be positive. Every lock must be stored with the good
+    /// of its kind by its token, with positive quantities and a token that is used only once.
+    pub fn from_snapshots(snapshots: Vec<GoodSnapshot>) -> Option<Self> {
+        let has_default_good = snapshots.iter().any(|g| g.kind == DEFAULT_GOOD_KIND);
+        let has_unique_kinds = ALL_GOOD_KINDS
+            .iter()
+            .all(|kind| snapshots.iter().filter(|g| g.kind == *kind).count() <= 1);
+        let has_valid_values = snapshots.iter().all(|g| {
+            g.quantity.is_finite()
+                && g.quantity >= 0.0
+                && g.metadata.base_buy_price.is_finite()
+                && g.metadata.base_buy_price > 0.0
+                && g.metadata.base_sell_price.is_finite()
+                && g.metadata.base_sell_price > 0.0
+        });
+        if !has_default_good || !has_unique_kinds || !has_valid_values {
+            return None;
+        }
+        if !GoodStorage::has_valid_locks(&snapshots) {
+            return None;
+        }
+
+        let goods = snapshots
+            .into_iter()
+            .map(|g| (Good::new(g.kind, g.quantity), g.metadata))
+            .collect();
+        Some(Self(goods))
+    }
+
+    /// Checks if all locks of the given snapshots are valid, see `GoodStorage::from_snapshots`
+    fn has_valid_locks(snapshots: &[GoodSnapshot]) -> bool {
+        let mut tokens = HashSet::new();
+        snapshots.iter().all(|g| {
+            g.metadata
+                .sell_locks
+                .iter()
+                .chain(g.metadata.buy_locks.iter())
+                .all(|(token, lock)| {
+                    lock.kind == g.kind
+                        && *token == lock.transaction_token
+                        && lock.locked_original_qty.is_finite()
+                        && lock.locked_original_qty > 0.0
+                        && lock.eur_quantity.is_finite()
+                        && lock.eur_quantity > 0.0
+                        && tokens.insert(token.clone())
+                })
+        })
+    }
 }
 
 impl GoodStorage {
@@ -52,19 +178,30 @@ impl GoodStorage {
             .collect::<Vec<GoodLabel>>()
     }
 
-    /// Returns the numbers of goods that are locked for buy
-    pub fn get_buy_locks_len(&self) -> usize {
-        self.0.iter().filter(|(_, m)| m.is_locked_for_buy()).count()
+    /// Returns the numbers of goods that are locked for buy by the given trader
+    pub fn get_buy_locks_len(&self, trader_name: &str) -> usize {
+        self.0
+            .iter()
+            .filter(|(_, m)| m.is_locked_for_buy_by(trader_name))
+            .count()
     }
 
-    /// Returns the numbers of goods that are locked for sell
-    pub fn get_sell_locks_len(&self) -> usize {
+    /// Returns the numbers of goods that are locked for sell by the given trader
+    pub fn get_sell_locks_len(&self, trader_name: &str) -> usize {
         self.0
             .iter()
-            .filter(|(_, m)| m.is_locked_for_sell())
+            .filter(|(_, m)| m.is_locked_for_sell_by(trader_name))
             .count()
     }
 
+    /// Returns all buy and sell locks of all goods
+    pub fn get_locks(&self) -> Vec<&GoodLock> {
+        self.0
+            .iter()
+            .flat_map(|(_, m)| m.buy_locks.values().chain(m.sell_locks.values()))
+            .collect()
+    }
+
     /// Returns a mutable good reference for the given kind
     pub fn get_mut_good_for_kind(&mut self, kind: &GoodKind) -> Option<&mut GoodWithMeta> {
         self.0.iter_mut().find(|(g, _)| g.get_kind() == *kind)
@@ -76,53 +213,102 @@ impl GoodStorage {
     }
 
     /// Returns a mutable reference to the good for the given token, if available
-    pub fn get_mut_good_for_sell_token(&mut self, token: &String) -> Option<&mut GoodWithMeta> {
-        self.0.iter_mut().find(|(_, m)| match &m.sell_status {
-            GoodStatus::Available => false,
-            GoodStatus::Locked(lock) => lock.transaction_token == *token,
-        })
+    pub fn get_mut_good_for_sell_token(&mut self, token: &str) -> Option<&mut GoodWithMeta> {
+        self.0
+            .iter_mut()
+            .find(|(_, m)| m.sell_locks.contains_key(token))
     }
 
     /// Checks if any good contains the expired sell token
-    pub fn has_good_expired_sell_token(&self, token: &String) -> bool {
+    pub fn has_good_expired_sell_token(&self, token: &str) -> bool {
         self.0
             .iter()
             .any(|(_, meta)| meta.has_expired_sell_token(token))
     }
 
     /// Returns a mutable reference to the good for the given token, if available
-    pub fn get_mut_good_for_buy_token(&mut self, token: &String) -> Option<&mut GoodWithMeta> {
-        self.0.iter_mut().find(|(_, m)| match &m.buy_status {
-            GoodStatus::Available => false,
-            GoodStatus::Locked(lock) => lock.transaction_token == *token,
-        })
+    pub fn get_mut_good_for_buy_token(&mut self, token: &str) -> Option<&mut GoodWithMeta> {
+        self.0
+            .iter_mut()
+            .find(|(_, m)| m.buy_locks.contains_key(token))
     }
 
     /// Checks if any good contains the expired buy token
-    pub fn has_good_expired_buy_token(&self, token: &String) -> bool {
+    pub fn has_good_expired_buy_token(&self, token: &str) -> bool {
         self.0
             .iter()
             .any(|(_, meta)| meta.has_expired_buy_token(token))
     }
 
-    /// Returns the default good
-    pub fn get_default_good(&self) -> &GoodWithMeta {
+    /// Returns the buy or sell lock for the given token, if available
+    pub fn get_lock_for_token(&self, token: &str) -> Option<&GoodLock> {
         self.0
             .iter()
-            .find(|(g, _)| g.get_kind() == DEFAULT_GOOD_KIND)
-            .expect("Not able to get default good ") // we can expect, default good should always be present
+            .find_map(|(_, m)| m.get_buy_lock(token).or_else(|| m.get_sell_lock(token)))
+    }
+
+    /// Returns the quantity of the given kind that is neither locked for buy, nor promised to
+    /// a trader by a sell lock (only the default good is promised by sell locks).
+    pub fn get_free_quantity_for_kind(&self, kind: &GoodKind) -> f32 {
+        let (good, meta) = match self.get_good_for_kind(kind) {
+            Some((good, meta)) => (good, meta),
+            _ => return 0.0,
+        };
+
+        let mut locked_quantity = meta.get_locked_buy_quantity();
+        if *kind == DEFAULT_GOOD_KIND {
+            locked_quantity += self
+                .0
+                .iter()
+                .map(|(_, m)| m.get_locked_sell_eur_quantity())
+                .sum::<f32>();
+        }
+        (good.get_qty() - locked_quantity).max(0.0)
+    }
+
+    /// Returns the default good, if available
+    pub fn get_default_good(&self) -> Option<&GoodWithMeta> {
+        self.get_good_for_kind(&DEFAULT_GOOD_KIND)
+    }
+
+    /// Returns an iterator of the goods
+    pub fn iter(&self) -> Iter<GoodWithMeta> {
+        self.0.iter()
     }
 
     /// Returns a mut iterator of the goods
     pub fn iter_mut(&mut self) -> IterMut<GoodWithMeta> {
         self.0.iter_mut()
     }
+
+    /// Returns a snapshot of every good, including its metadata
+    pub fn to_snapshots(&self) -> Vec<GoodSnapshot> {
+        self.0
+            .iter()
+            .map(|(good, meta)| GoodSnapshot {
+                kind: good.get_kind(),
+                quantity: good.get_qty(),
+                metadata: meta.clone(),
+            })
+            .collect()
+    }
+
+    /// Writes all goods, including their metadata, to the given file.
+    /// The file can be read again using [`GoodStorage::from_file`].
+    pub fn write_to_file(&self, path: &str) -> io::Result<()> {
+        let stored_goods = self.to_snapshots();
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer_pretty(&mut writer, &stored_goods)?;
+        writer.flush()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::goods::good_storage::GoodStorage;
+    use crate::goods::good_storage::{GoodSnapshot, GoodStorage};
     use crate::goods::goods_factory::GoodsFactory;
+    use std::env::temp_dir;
+    use std::fs;
     use unitn_market_2022::good::consts::DEFAULT_GOOD_KIND;
     use unitn_market_2022::good::good_kind::GoodKind;
 
@@ -194,7 +380,7 @@ mod tests {
     #[test]
     fn test_get_default_good() {
         let storage = GoodStorage::with_quantities(5.0, 5.0, 5.0, 5.0);
-        let (default, _) = storage.get_default_good();
+        let (default, _) = storage.get_default_good().unwrap();
         assert_eq!(
             DEFAULT_GOOD_KIND,
             default.get_kind(),
@@ -207,9 +393,10 @@ mod tests {
     #[test]
     fn test_buy_locks() {
         let mut storage = GoodStorage::with_quantities(5.0, 5.0, 5.0, 5.0);
+        let trader_name = "Test_Trader".to_string();
         assert_eq!(
             0,
-            storage.get_buy_locks_len(),
+            storage.get_buy_locks_len(&trader_name),
             "Lock len must be 0, no Good locked yet"
         );
 
@@ -228,11 +415,27 @@ mod tests {
         );
 
         let (_, first_meta) = storage.0.get_mut(0).unwrap();
-        let trader_name = "Test_Trader".to_string();
 
         // lock the EUR good
-        let token = first_meta.lock_for_buy(2.0, GoodKind::EUR, 200.0, trader_name);
-        assert_eq!(1, storage.get_buy_locks_len(), "One good is locked for buy");
+        let token = "TOKEN".to_string();
+        first_meta.lock_for_buy(
+            2.0,
+            GoodKind::EUR,
+            200.0,
+            token.clone(),
+            trader_name.clone(),
+        );
+        assert_eq!(
+            1,
+            storage.get_buy_locks_len(&trader_name),
+            "One good is locked for buy"
+        );
+        assert_eq!(
+            0,
+            storage.get_buy_locks_len("Other_Trader"),
+            "Locks are counted per trader"
+        );
+        assert_eq!(1, storage.get_locks().len());
         assert!(
             !storage.has_good_expired_buy_token(&token),
             "Valid token '{}' token can't be expired",
@@ -251,7 +454,7 @@ mod tests {
         );
 
         // unlock
-        locked_meta.unlock_for_buy();
+        locked_meta.unlock_for_buy(&token);
         assert!(
             !locked_meta.is_locked_for_buy(),
             "Good should not locked for buy anymore"
@@ -266,9 +469,10 @@ mod tests {
     #[test]
     fn test_sell_locks() {
         let mut storage = GoodStorage::with_quantities(5.0, 5.0, 5.0, 5.0);
+        let trader_name = "Test_Trader".to_string();
         assert_eq!(
             0,
-            storage.get_sell_locks_len(),
+            storage.get_sell_locks_len(&trader_name),
             "Lock len must be 0, no Good locked yet"
         );
 
@@ -288,13 +492,19 @@ mod tests {
         );
 
         let (_, first_meta) = storage.0.get_mut(0).unwrap();
-        let trader_name = "Test_Trader".to_string();
 
         // lock the EUR good
-        let token = first_meta.lock_for_sell(2.0, GoodKind::EUR, 200.0, trader_name);
+        let token = "TOKEN".to_string();
+        first_meta.lock_for_sell(
+            2.0,
+            GoodKind::EUR,
+            200.0,
+            token.clone(),
+            trader_name.clone(),
+        );
         assert_eq!(
             1,
-            storage.get_sell_locks_len(),
+            storage.get_sell_locks_len(&trader_name),
             "One good is locked for sell"
         );
         assert!(
@@ -315,7 +525,7 @@ mod tests {
         );
 
         // unlock
-        locked_meta.unlock_for_sell();
+        locked_meta.unlock_for_sell(&token);
         assert!(
             !locked_meta.is_locked_for_sell(),
             "Good should not be locked for sell anymore"
@@ -326,4 +536,211 @@ mod tests {
             token
         );
     }
+
+    #[test]
+    fn test_get_free_quantity_for_kind() {
+        let mut storage = GoodStorage::with_quantities(100.0, 100.0, 100.0, 100.0);
+        let trader_name = "Test_Trader".to_string();
+        assert_eq!(
+            100.0,
+            storage.get_free_quantity_for_kind(&GoodKind::USD),
+            "Nothing is locked yet"
+        );
+
+        // buy locks reduce the free quantity of the locked good
+        let (_, usd_meta) = storage.get_mut_good_for_kind(&GoodKind::USD).unwrap();
+        usd_meta.lock_for_buy(
+            30.0,
+            GoodKind::USD,
+            40.0,
+            "TOKEN-1".to_string(),
+            trader_name.clone(),
+        );
+        usd_meta.lock_for_buy(
+            20.0,
+            GoodKind::USD,
+            25.0,
+            "TOKEN-2".to_string(),
+            trader_name.clone(),
+        );
+        assert_eq!(
+            50.0,
+            storage.get_free_quantity_for_kind(&GoodKind::USD),
+            "Half of the USD is locked"
+        );
+
+        // sell locks reduce the free quantity of the default good
+        let (_, yen_meta) = storage.get_mut_good_for_kind(&GoodKind::YEN).unwrap();
+        yen_meta.lock_for_sell(
+            500.0,
+            GoodKind::YEN,
+            60.0,
+            "TOKEN-3".to_string(),
+            trader_name,
+        );
+        assert_eq!(
+            40.0,
+            storage.get_free_quantity_for_kind(&DEFAULT_GOOD_KIND),
+            "60 EUR are promised to a trader"
+        );
+        assert_eq!(
+            100.0,
+            storage.get_free_quantity_for_kind(&GoodKind::YEN),
+            "A sell lock does not lock the sold good"
+        );
+    }
+
+    #[test]
+    fn test_write_and_read_file() {
+        let path = temp_dir().join("sgx_test_write_and_read_file.json");
+        let path = path.to_str().unwrap();
+        let mut storage = GoodStorage::with_quantities(50.0, 60.0, 70.0, 80.0);
+        let trader_name = "Test_Trader".to_string();
+
+        // lock a good, the lock must be written as well
+        let (_, usd_meta) = storage.get_mut_good_for_kind(&GoodKind::USD).unwrap();
+        let token = "TOKEN".to_string();
+        usd_meta.lock_for_buy(2.0, GoodKind::USD, 200.0, token.clone(), trader_name);
+
+        storage
+            .write_to_file(path)
+            .expect("Not able to write storage to file");
+        let read_storage = GoodStorage::from_file(path);
+        let _ = fs::remove_file(path);
+
+        assert!(read_storage.is_some(), "Storage must be read from file");
+        let mut read_storage = read_storage.unwrap();
+        assert_eq!(
+            storage.0, read_storage.0,
+            "Read storage must be equal to the written one"
+        );
+        assert!(
+            read_storage.get_mut_good_for_buy_token(&token).is_some(),
+            "Lock for token '{}' must still exist",
+            token
+        );
+    }
+
+    #[test]
+    fn test_read_invalid_file() {
+        let missing_path = temp_dir().join("sgx_test_read_missing_file.json");
+        let _ = fs::remove_file(&missing_path);
+        assert!(
+            GoodStorage::from_file(missing_path.to_str().unwrap()).is_none(),
+            "No storage can be read from a missing file"
+        );
+
+        let malformed_path = temp_dir().join("sgx_test_read_malformed_file.json");
+        let malformed_path = malformed_path.to_str().unwrap();
+        fs::write(malformed_path, "not a storage").unwrap();
+        let malformed_storage = GoodStorage::from_file(malformed_path);
+        let _ = fs::remove_file(malformed_path);
+        assert!(
+            malformed_storage.is_none(),
+            "No storage can be read from a malformed file"
+        );
+
+        // negative quantities are bad values
+        let stored_goods = GoodsFactory::all_with_quantities(5.0, 5.0, 5.0, 5.0)
+            .into_iter()
+            .map(|(good, metadata)| GoodSnapshot {
+                kind: good.get_kind(),
+                quantity: -good.get_qty(),
+                metadata,
+            })
+            .collect();
+        assert!(
+            GoodStorage::from_snapshots(stored_goods).is_none(),
+            "No storage can be built from negative quantities"
+        );
+
+        // the default good is required
+        let stored_goods = GoodsFactory::all_with_quantities(5.0, 5.0, 5.0, 5.0)
+            .into_iter()
+            .skip(1)
+            .map(|(good, metadata)| GoodSnapshot {
+                kind: good.get_kind(),
+                quantity: good.get_qty(),
+                metadata,
+            })
+            .collect();
+        assert!(
+            GoodStorage::from_snapshots(stored_goods).is_none(),
+            "No storage can be built without the default good"
+        );
+    }
+
+    #[test]
+    fn test_read_file_with_single_locks() {
+        // market files of older versions contain one lock status per side instead of all locks
+        let path = temp_dir().join("sgx_test_read_file_with_single_locks.json");
+        let path = path.to_str().unwrap();
+        let content = r#"[
+            {
+                "kind": "EUR",
+                "quantity": 100.0,
+                "metadata": {
+                    "base_sell_price": 1.0,
+                    "base_buy_price": 1.0,
+                    "sell_status": "Available",
+                    "buy_status": "Available",
+                    "expired_sell_tokens": [],
+                    "expired_buy_tokens": []
+                }
+            },
+            {
+                "kind": "USD",
+                "quantity": 50.0,
+                "metadata": {
+                    "base_sell_price": 0.95,
+                    "base_buy_price": 1.05,
+                    "sell_status": "Available",
+                    "buy_status": {
+                        "Locked": {
+                            "locked_original_qty": 10.0,
+                            "kind": "USD",
+                            "eur_quantity": 10.5,
+                            "transaction_token": "TRADER-USD-10",
+                            "age_in_days": 2
+                        }
+                    },
+                    "expired_sell_tokens": ["TRADER-USD-5"],
+                    "expired_buy_tokens": []
+                }
+            }
+        ]"#;
+        fs::write(path, content).unwrap();
+        let storage = GoodStorage::from_file(path);
+        let _ = fs::remove_file(path);
+
+        assert!(storage.is_some(), "Older market files must be readable");
+        let mut storage = storage.unwrap();
+        let (_, usd_meta) = storage.get_good_for_kind(&GoodKind::USD).unwrap();
+        let lock = usd_meta.get_buy_lock("TRADER-USD-10");
+        assert_eq!(Some(2), lock.map(|l| l.age_in_days), "Lock must be kept");
+        assert!(!usd_meta.is_locked_for_sell());
+        assert!(usd_meta.has_expired_sell_token("TRADER-USD-5"));
+        assert!(storage
+            .get_mut_good_for_buy_token("TRADER-USD-10")
+            .is_some());
+    }
+
+    #[test]
+    fn test_good_storage_from_entries() {
+        let storage = GoodStorage::from_entries(&[(GoodKind::YEN, 100.0, 150.0)]).unwrap();
+        assert_eq!(2, storage.len(), "Only YEN and the default good are listed");
+        assert!(storage.get_default_good().is_some());
+        assert!(storage.get_good_for_kind(&GoodKind::USD).is_none());
+        assert_eq!(0.0, storage.get_free_quantity_for_kind(&GoodKind::USD));
+
+        assert!(
+            GoodStorage::from_entries(&[(GoodKind::YEN, 1.0, 150.0), (GoodKind::YEN, 1.0, 150.0)])
+                .is_none(),
+            "Kinds must be unique"
+        );
+        assert!(
+            GoodStorage::from_entries(&[(GoodKind::YEN, 1.0, 0.0)]).is_none(),
+            "Exchange rate must be positive"
+        );
+    }
 }
@@ -2,12 +2,35 @@ use crate::goods::good_metadata::GoodMetadata;
 use rand::Rng;
 use unitn_market_2022::good::consts::{
     DEFAULT_EUR_USD_EXCHANGE_RATE, DEFAULT_EUR_YEN_EXCHANGE_RATE, DEFAULT_EUR_YUAN_EXCHANGE_RATE,
+    DEFAULT_GOOD_KIND,
 };
 use unitn_market_2022::good::good::Good;
 use unitn_market_2022::good::good_kind::GoodKind;
 
 pub type GoodWithMeta = (Good, GoodMetadata);
 
+/// A good of the market, given as kind, quantity and exchange rate from EUR
+pub type GoodEntry = (GoodKind, f32, f32);
+
+/// The exchange rates from EUR to the other goods, the goods are initialized with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExchangeRates {
+    pub yen: f32,
+    pub usd: f32,
+    pub yuan: f32,
+}
+
+impl Default for ExchangeRates {
+    /// The default exchange rates of the market common
+    fn default() -> Self {
+        Self {
+            yen: DEFAULT_EUR_YEN_EXCHANGE_RATE,
+            usd: DEFAULT_EUR_USD_EXCHANGE_RATE,
+            yuan: DEFAULT_EUR_YUAN_EXCHANGE_RATE,
+        }
+    }
+}
+
 /**
  * The `GoodsFactory` is a helper class that intents to generate
  * some `Good`s. The purpose of this class is, that it only cares
@@ -17,34 +40,54 @@ pub type GoodWithMeta = (Good, GoodMetadata);
 pub struct GoodsFactory();
 
 impl GoodsFactory {
-    /// Generate a vec of random quantities that sum up to the available quantity
-    fn random_quantities(num: u32, mut available_quantity: f32) -> Vec<f32> {
+    /// Generate a vec of `num` random quantities that sum up to the available quantity.
+    /// Once the available quantity is used up, the remaining quantities are 0.
+    fn random_quantities<R: Rng>(num: u32, mut available_quantity: f32, rng: &mut R) -> Vec<f32> {
         let mut quantities = Vec::new();
-        let mut steps = 0;
-        let mut rng = rand::thread_rng();
 
         // remove random values from quantity and add it to the vec
-        while available_quantity > 0.0 && steps < (num - 1) {
-            let quantity = available_quantity - rng.gen_range(0.0..available_quantity);
+        for _ in 1..num {
+            let quantity = if available_quantity > 0.0 {
+                available_quantity - rng.gen_range(0.0..available_quantity)
+            } else {
+                0.0
+            };
             available_quantity -= quantity;
             quantities.push(quantity);
-            steps += 1;
         }
 
         // add the remaining quantity
-        quantities.push(available_quantity);
+        if num > 0 {
+            quantities.push(available_quantity.max(0.0));
+        }
 
         quantities
     }
 
-    /// Returns Goods containing only an EUR good
+    /// Returns all goods with random quantities, that sum up to the available quantity
     pub fn random_goods(available_quantity: f32) -> Vec<GoodWithMeta> {
-        let random_quantities = GoodsFactory::random_quantities(4, available_quantity);
-        GoodsFactory::all_with_quantities(
+        GoodsFactory::random_goods_with_rates(
+            available_quantity,
+            &ExchangeRates::default(),
+            &mut rand::thread_rng(),
+        )
+    }
+
+    /// Returns all goods with random quantities and the given exchange rates.
+    /// The quantities are generated by the given rng, so a seeded rng always returns the same
+    /// goods.
+    pub fn random_goods_with_rates<R: Rng>(
+        available_quantity: f32,
+        exchange_rates: &ExchangeRates,
+        rng: &mut R,
+    ) -> Vec<GoodWithMeta> {
+        let random_quantities = GoodsFactory::random_quantities(4, available_quantity, rng);
+        GoodsFactory::all_with_quantities_and_rates(
             random_quantities[0],
             random_quantities[1],
             random_quantities[2],
             random_quantities[3],
+            exchange_rates,
         )
     }
 
@@ -54,27 +97,58 @@ impl GoodsFactory {
         usd: f32,
         yuan: f32,
     ) -> Vec<(Good, GoodMetadata)> {
+        GoodsFactory::all_with_quantities_and_rates(eur, yen, usd, yuan, &ExchangeRates::default())
+    }
+
+    /// Returns all goods with the given quantities and exchange rates
+    pub fn all_with_quantities_and_rates(
+        eur: f32,
+        yen: f32,
+        usd: f32,
+        yuan: f32,
+        exchange_rates: &ExchangeRates,
+    ) -> Vec<GoodWithMeta> {
         Vec::from([
             (Good::new(GoodKind::EUR, eur), GoodMetadata::new(1.0)),
             (
                 Good::new(GoodKind::YEN, yen),
-                GoodMetadata::new(DEFAULT_EUR_YEN_EXCHANGE_RATE),
+                GoodMetadata::new(exchange_rates.yen),
             ),
             (
                 Good::new(GoodKind::USD, usd),
-                GoodMetadata::new(DEFAULT_EUR_USD_EXCHANGE_RATE),
+                GoodMetadata::new(exchange_rates.usd),
             ),
             (
                 Good::new(GoodKind::YUAN, yuan),
-                GoodMetadata::new(DEFAULT_EUR_YUAN_EXCHANGE_RATE),
+                GoodMetadata::new(exchange_rates.yuan),
             ),
         ])
     }
+
+    /// Returns the goods of the given entries, in the same order. The default good is always
+    /// needed to pay and to get paid, therefore it is added first (without quantity), if it is
+    /// not part of the entries.
+    pub fn with_entries(entries: &[GoodEntry]) -> Vec<GoodWithMeta> {
+        let mut goods = Vec::new();
+        if !entries
+            .iter()
+            .any(|(kind, _, _)| *kind == DEFAULT_GOOD_KIND)
+        {
+            goods.push((Good::new(DEFAULT_GOOD_KIND, 0.0), GoodMetadata::new(1.0)));
+        }
+        goods.extend(
+            entries.iter().map(|(kind, quantity, rate)| {
+                (Good::new(*kind, *quantity), GoodMetadata::new(*rate))
+            }),
+        );
+        goods
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::goods::goods_factory::GoodsFactory;
+    use crate::goods::goods_factory::{ExchangeRates, GoodsFactory};
+    use unitn_market_2022::good::good_kind::GoodKind;
 
     #[test]
     fn test_random_goods() {
@@ -123,10 +197,30 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_with_quantities_and_rates() {
+        let rates = ExchangeRates {
+            yen: 100.0,
+            usd: 2.0,
+            yuan: 5.0,
+        };
+        let goods = GoodsFactory::all_with_quantities_and_rates(50.0, 60.0, 70.0, 80.0, &rates);
+        let buy_prices = goods
+            .iter()
+            .map(|(_, meta)| meta.base_buy_price)
+            .collect::<Vec<f32>>();
+        assert_eq!(
+            vec![1.0, 100.0, 2.0, 5.0],
+            buy_prices,
+            "Goods must be initialized with the given rates"
+        );
+    }
+
     #[test]
     fn test_random_quantities() {
         let available_quantity: f32 = 1000.0;
-        let random_quantities = GoodsFactory::random_quantities(4, available_quantity);
+        let random_quantities =
+            GoodsFactory::random_quantities(4, available_quantity, &mut rand::thread_rng());
         assert_eq!(
             4,
             random_quantities.len(),
@@ -143,5 +237,26 @@ mod tests {
             "The sum of all quantities must be equal to {}",
             available_quantity
         );
+
+        let random_quantities = GoodsFactory::random_quantities(4, 0.0, &mut rand::thread_rng());
+        assert_eq!(
+            vec![0.0; 4],
+            random_quantities,
+            "Without available quantity, all quantities must be 0"
+        );
+    }
+
+    #[test]
+    fn test_with_entries() {
+        let goods = GoodsFactory::with_entries(&[(GoodKind::YEN, 100.0, 150.0)]);
+        assert_eq!(2, goods.len(), "Default good must be added");
+        assert_eq!(GoodKind::EUR, goods[0].0.get_kind());
+        assert_eq!(0.0, goods[0].0.get_qty());
+        assert_eq!(GoodKind::YEN, goods[1].0.get_kind());
+        assert_eq!(100.0, goods[1].0.get_qty());
+        assert_eq!(150.0, goods[1].1.base_buy_price);
+
+        let goods = GoodsFactory::with_entries(&[(GoodKind::EUR, 10.0, 1.0)]);
+        assert_eq!(1, goods.len(), "Default good must not be added twice");
     }
 }
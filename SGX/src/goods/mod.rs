@@ -1,4 +1,7 @@
+pub mod expired_tokens;
 pub mod good_metadata;
 pub mod good_status;
 pub mod good_storage;
 pub mod goods_factory;
+pub mod serde_good_kind;
+pub mod token_generator;
@@ -0,0 +1,59 @@
+//! `GoodKind` does not implement `Serialize` and `Deserialize`, therefore this module can be used
+//! as `#[serde(with = "crate::goods::serde_good_kind")]` to (de-)serialize it by its name.
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serializer};
+use unitn_market_2022::good::good_kind::GoodKind;
+
+/// All available good kinds
+pub const ALL_GOOD_KINDS: [GoodKind; 4] =
+    [GoodKind::EUR, GoodKind::USD, GoodKind::YEN, GoodKind::YUAN];
+
+/// Returns the `GoodKind` for the given name (e.g. `"EUR"`), if available
+pub fn kind_from_name(name: &str) -> Option<GoodKind> {
+    ALL_GOOD_KINDS
+        .iter()
+        .copied()
+        .find(|kind| kind.to_string() == name)
+}
+
+/// Serializes the given kind as its name
+pub fn serialize<S>(kind: &GoodKind, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&kind.to_string())
+}
+
+/// Deserializes a kind from its name
+pub fn deserialize<'de, D>(deserializer: D) -> Result<GoodKind, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let name = String::deserialize(deserializer)?;
+    kind_from_name(&name).ok_or_else(|| D::Error::custom(format!("Unknown good kind '{}'", name)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::goods::serde_good_kind::{kind_from_name, ALL_GOOD_KINDS};
+
+    #[test]
+    fn test_kind_from_name() {
+        for kind in ALL_GOOD_KINDS {
+            assert_eq!(
+                Some(kind),
+                kind_from_name(&kind.to_string()),
+                "Name of {} must be parsed back to its kind",
+                kind
+            );
+        }
+
+        let invalid_name = "NOT_A_KIND";
+        assert_eq!(
+            None,
+            kind_from_name(invalid_name),
+            "There is no kind for name '{}'",
+            invalid_name
+        );
+    }
+}
@@ -0,0 +1,78 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/**
+ * The `TokenGenerator` generates the tokens of the locks of a market.
+ * A token consists of a counter, that makes it unique, and a random nonce, that makes it
+ * impossible to guess the token of another lock. With a seed, the same tokens are generated
+ * in the same order.
+ */
+#[derive(Debug, Clone)]
+pub struct TokenGenerator {
+    /// Number of generated tokens
+    counter: u64,
+    /// Generator of the nonces
+    rng: StdRng,
+}
+
+impl TokenGenerator {
+    /// Constructs a new generator, the nonces are drawn with the given seed if available,
+    /// otherwise with entropy of the OS
+    pub fn new(seed: Option<u64>) -> Self {
+        let rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        Self { counter: 0, rng }
+    }
+
+    /// Returns the next token
+    pub fn next_token(&mut self) -> String {
+        let count = self.counter;
+        self.counter += 1;
+        let nonce: u64 = self.rng.gen();
+        format!("{:08x}-{:016x}", count, nonce)
+    }
+}
+
+impl Default for TokenGenerator {
+    fn default() -> Self {
+        TokenGenerator::new(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::goods::token_generator::TokenGenerator;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_token_generation() {
+        let mut generator = TokenGenerator::default();
+        let tokens = (0..1000)
+            .map(|_| generator.next_token())
+            .collect::<HashSet<String>>();
+        assert_eq!(1000, tokens.len(), "All generated tokens must be unique");
+    }
+
+    #[test]
+    fn test_seeded_tokens() {
+        let gen_tokens = |seed: Option<u64>| {
+            let mut generator = TokenGenerator::new(seed);
+            (0..10)
+                .map(|_| generator.next_token())
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(
+            gen_tokens(Some(42)),
+            gen_tokens(Some(42)),
+            "The same seed must result in the same tokens"
+        );
+        assert_ne!(gen_tokens(Some(42)), gen_tokens(Some(43)));
+        assert_ne!(
+            gen_tokens(None),
+            gen_tokens(None),
+            "Tokens without a seed must not be reproducible"
+        );
+    }
+}
@@ -1,4 +1,5 @@
 mod goods;
 pub mod market;
+mod property_tests;
 mod tests;
 extern crate chrono;
@@ -0,0 +1,442 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::rc::Rc;
+use unitn_market_2022::good::consts::STARTING_CAPITAL;
+use unitn_market_2022::market::Market;
+
+use crate::goods::good_storage::GoodStorage;
+use crate::goods::goods_factory::{ExchangeRates, GoodEntry};
+use crate::market::consensus::ConsensusConfig;
+use crate::market::consts::{MAX_LOCK_EXPIRY_IN_DAYS, MIN_LOCK_EXPIRY_IN_DAYS};
+use crate::market::errors::BuildError;
+use crate::market::log::Logger;
+use crate::market::pricing::{DemandPricing, PricingModel};
+use crate::market::sgx::SGX;
+
+/**
+ * The `SGXConfig` contains all parameters of the market, that don't depend on its goods.
+ * The default config is the behaviour of a market created with `SGX::new_random`.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SGXConfig {
+    /// Margins and fluctuation factors of the default pricing model
+    pub pricing: DemandPricing,
+    /// Number of days after which a lock expires, between 3 and 15 days. Values out of this
+    /// range are clamped, see `get_lock_expiry_in_days`.
+    pub lock_expiry_in_days: u8,
+    /// Number of days an expired token is remembered, to tell it apart from an unrecognized
+    /// token. Afterwards, the token is unrecognized.
+    #[serde(default = "default_expired_token_retention_in_days")]
+    pub expired_token_retention_in_days: u32,
+    /// Max. number of goods that a single trader can lock for buy (and for sell) at the same
+    /// time. If `None`, all goods except two (but at least one) can be locked.
+    pub max_locked_goods: Option<usize>,
+    /// Max. number of buy and sell locks a single trader can hold at the same time.
+    /// If `None`, there is no limit.
+    #[serde(default)]
+    pub max_locks_per_trader: Option<usize>,
+    /// Max. number of buy and sell locks of all traders at the same time.
+    /// If `None`, there is no limit.
+    #[serde(default)]
+    pub max_locks: Option<usize>,
+    /// If limit orders can be placed, see `SGX::place_order`
+    #[serde(default)]
+    pub order_book_enabled: bool,
+    /// If the price fluctuation of a lock is reverted, when the lock is cancelled
+    #[serde(default)]
+    pub revert_price_on_cancel: bool,
+    /// How the consensus prices of other markets are estimated and followed
+    #[serde(default)]
+    pub consensus: ConsensusConfig,
+}
+
+impl Default for SGXConfig {
+    fn default() -> Self {
+        Self {
+            pricing: DemandPricing::default(),
+            lock_expiry_in_days: MAX_LOCK_EXPIRY_IN_DAYS,
+            expired_token_retention_in_days: default_expired_token_retention_in_days(),
+            max_locked_goods: None,
+            max_locks_per_trader: None,
+            max_locks: None,
+            order_book_enabled: false,
+            revert_price_on_cancel: false,
+            consensus: ConsensusConfig::default(),
+        }
+    }
+}
+
+/// Configs that were stored without a retention window, remember expired tokens for 30 days
+fn default_expired_token_retention_in_days() -> u32 {
+    30
+}
+
+impl SGXConfig {
+    /// Returns the max. number of goods that a trader can lock at the same time, if the market
+    /// has the given number of goods
+    pub fn get_max_locked_goods(&self, goods_len: usize) -> usize {
+        self.max_locked_goods
+            .unwrap_or(goods_len.saturating_sub(2).max(1))
+    }
+
+    /// Returns the number of days after which a lock expires, clamped to 3 to 15 days
+    pub fn get_lock_expiry_in_days(&self) -> u8 {
+        self.lock_expiry_in_days
+            .clamp(MIN_LOCK_EXPIRY_IN_DAYS, MAX_LOCK_EXPIRY_IN_DAYS)
+    }
+}
+
+/**
+ * The `SGXBuilder` builds a market with the given config, quantities and exchange rates.
+ * Everything that is not set, is the same as for `SGX::new_random`.
+ *
+ * ```ignore
+ * let market = SGX::builder()
+ *     .quantities(10_000.0, 10_000.0, 10_000.0, 10_000.0)
+ *     .buy_margin(0.1)
+ *     .lock_expiry_in_days(5)
+ *     .build();
+ * ```
+ */
+pub struct SGXBuilder {
+    config: SGXConfig,
+    quantities: Option<(f32, f32, f32, f32)>,
+    goods: Option<Vec<GoodEntry>>,
+    exchange_rates: ExchangeRates,
+    pricing_model: Option<Box<dyn PricingModel>>,
+    logger: Option<Logger>,
+    seed: Option<u64>,
+}
+
+impl SGXBuilder {
+    /// Constructs a new builder with the default config
+    pub fn new() -> Self {
+        Self {
+            config: SGXConfig::default(),
+            quantities: None,
+            goods: None,
+            exchange_rates: ExchangeRates::default(),
+            pricing_model: None,
+            logger: None,
+            seed: None,
+        }
+    }
+
+    /// Replaces the whole config
+    pub fn config(mut self, config: SGXConfig) -> Self {
+        self.config = config;
+        self
+    }
+
+    /// Sets the initial quantities, otherwise they are random
+    pub fn quantities(mut self, eur: f32, yen: f32, usd: f32, yuan: f32) -> Self {
+        self.quantities = Some((eur, yen, usd, yuan));
+        self
+    }
+
+    /// Sets the goods of the market as kind, quantity and exchange rate from EUR, e.g. only
+    /// `(GoodKind::YEN, 10_000.0, 150.0)` for a market that only trades YEN. The default good is
+    /// always added. Quantities and exchange rates of the other setters are ignored then.
+    pub fn goods(mut self, goods: Vec<GoodEntry>) -> Self {
+        self.goods = Some(goods);
+        self
+    }
+
+    /// Sets the initial exchange rates from EUR to the other goods
+    pub fn exchange_rates(mut self, yen: f32, usd: f32, yuan: f32) -> Self {
+        self.exchange_rates = ExchangeRates { yen, usd, yuan };
+        self
+    }
+
+    /// Sets the margin that is added to the buy price (e.g. 0.05 for 5%)
+    pub fn buy_margin(mut self, buy_margin: f32) -> Self {
+        self.config.pricing.buy_margin = buy_margin;
+        self
+    }
+
+    /// Sets the margin that is added to the sell price (e.g. 0.15 for 15%)
+    pub fn sell_margin(mut self, sell_margin: f32) -> Self {
+        self.config.pricing.sell_margin = sell_margin;
+        self
+    }
+
+    /// Sets the factor the price of the other side is increased with, after an external event
+    pub fn external_event_factor(mut self, factor: f32) -> Self {
+        self.config.pricing.external_event_factor = factor;
+        self
+    }
+
+    /// Sets the factors the buy and sell prices are multiplied with, after a day has passed
+    pub fn wait_factors(mut self, buy_factor: f32, sell_factor: f32) -> Self {
+        self.config.pricing.wait_buy_factor = buy_factor;
+        self.config.pricing.wait_sell_factor = sell_factor;
+        self
+    }
+
+    /// Sets the number of days after which a lock expires, it is clamped to 3 to 15 days
+    pub fn lock_expiry_in_days(mut self, days: u8) -> Self {
+        self.config.lock_expiry_in_days =
+            days.clamp(MIN_LOCK_EXPIRY_IN_DAYS, MAX_LOCK_EXPIRY_IN_DAYS);
+        self
+    }
+
+    /// Sets the number of days an expired token is remembered
+    pub fn expired_token_retention_in_days(mut self, days: u32) -> Self {
+        self.config.expired_token_retention_in_days = days;
+        self
+    }
+
+    /// Sets the weight of the latest event in the consensus prices and how far our quotes are
+    /// pulled towards them, see `ConsensusConfig`
+    pub fn consensus(mut self, smoothing: f32, strength: f32) -> Self {
+        self.config.consensus = ConsensusConfig {
+            smoothing,
+            strength,
+        };
+        self
+    }
+
+    /// Sets the max. number of goods that a trader can lock at the same time
+    pub fn max_locked_goods(mut self, max_locked_goods: usize) -> Self {
+        self.config.max_locked_goods = Some(max_locked_goods);
+        self
+    }
+
+    /// Sets the max. number of locks a single trader can hold at the same time
+    pub fn max_locks_per_trader(mut self, max_locks: usize) -> Self {
+        self.config.max_locks_per_trader = Some(max_locks);
+        self
+    }
+
+    /// Sets the max. number of locks of all traders at the same time
+    pub fn max_locks(mut self, max_locks: usize) -> Self {
+        self.config.max_locks = Some(max_locks);
+        self
+    }
+
+    /// Enables or disables limit orders
+    pub fn order_book(mut self, enabled: bool) -> Self {
+        self.config.order_book_enabled = enabled;
+        self
+    }
+
+    /// Sets if the price fluctuation of a lock is reverted, when the lock is cancelled
+    pub fn revert_price_on_cancel(mut self, revert: bool) -> Self {
+        self.config.revert_price_on_cancel = revert;
+        self
+    }
+
+    /// Sets another pricing model. The margins and factors of the config are ignored then.
+    pub fn pricing_model(mut self, pricing_model: Box<dyn PricingModel>) -> Self {
+        self.pricing_model = Some(pricing_model);
+        self
+    }
+
+    /// Sets the logger, otherwise the text log is written to log_SGX.txt
+    pub fn logger(mut self, logger: Logger) -> Self {
+        self.logger = Some(logger);
+        self
+    }
+
+    /// Sets the seed the random quantities and the lock tokens are generated with. The same seed
+    /// always results in the same quantities and tokens.
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Generates the goods of the market. Returns an error if the given goods are invalid.
+    fn build_good_storage(&self) -> Result<GoodStorage, BuildError> {
+        if let Some(goods) = &self.goods {
+            return GoodStorage::from_entries(goods).ok_or_else(|| BuildError::InvalidGoods {
+                goods: goods.clone(),
+            });
+        }
+
+        let good_storage = match self.quantities {
+            Some((eur, yen, usd, yuan)) => {
+                GoodStorage::with_quantities_and_rates(eur, yen, usd, yuan, &self.exchange_rates)
+            }
+            None => match self.seed {
+                Some(seed) => GoodStorage::new_random_with_rates(
+                    STARTING_CAPITAL,
+                    &self.exchange_rates,
+                    &mut StdRng::seed_from_u64(seed),
+                ),
+                None => GoodStorage::new_random_with_rates(
+                    STARTING_CAPITAL,
+                    &self.exchange_rates,
+                    &mut rand::thread_rng(),
+                ),
+            },
+        };
+        Ok(good_storage)
+    }
+
+    /// Builds the market. Returns an error if the given goods are invalid (e.g. a negative
+    /// quantity).
+    pub fn try_build_sgx(self) -> Result<SGX, BuildError> {
+        let good_storage = self.build_good_storage()?;
+        let logger = self.logger.unwrap_or_default();
+        let mut market = SGX::from_config(good_storage, None, logger, self.config);
+        if let Some(pricing_model) = self.pricing_model {
+            market.set_pricing_model(pricing_model);
+        }
+        if let Some(seed) = self.seed {
+            market.set_token_seed(seed);
+        }
+        Ok(market)
+    }
+
+    /// Builds the market, see `SGXBuilder::try_build_sgx`.
+    /// Panics if the given goods are invalid.
+    pub fn build_sgx(self) -> SGX {
+        self.try_build_sgx().unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Builds the market, like the constructors of `Market`.
+    /// Panics if the given goods are invalid.
+    pub fn build(self) -> Rc<RefCell<dyn Market>> {
+        Rc::new(RefCell::new(self.build_sgx()))
+    }
+}
+
+impl Default for SGXBuilder {
+    fn default() -> Self {
+        SGXBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::market::config::{SGXBuilder, SGXConfig};
+    use crate::market::errors::BuildError;
+    use crate::market::log::Logger;
+    use unitn_market_2022::good::good_kind::GoodKind;
+    use unitn_market_2022::market::Market;
+
+    #[test]
+    fn test_default_config() {
+        let config = SGXConfig::default();
+        assert_eq!(15, config.lock_expiry_in_days);
+        assert_eq!(2, config.get_max_locked_goods(4), "All goods except two");
+        assert_eq!(
+            1,
+            config.get_max_locked_goods(2),
+            "A market with a single good besides EUR can lock it"
+        );
+    }
+
+    #[test]
+    fn test_lock_expiry_is_clamped() {
+        let config = SGXConfig {
+            lock_expiry_in_days: 30,
+            ..SGXConfig::default()
+        };
+        assert_eq!(15, config.get_lock_expiry_in_days());
+        let config = SGXConfig {
+            lock_expiry_in_days: 0,
+            ..SGXConfig::default()
+        };
+        assert_eq!(3, config.get_lock_expiry_in_days());
+
+        let market = SGXBuilder::new()
+            .lock_expiry_in_days(1)
+            .logger(Logger::disabled())
+            .build_sgx();
+        assert_eq!(3, market.get_config().lock_expiry_in_days);
+    }
+
+    #[test]
+    fn test_builder() {
+        let market = SGXBuilder::new()
+            .quantities(1000.0, 2000.0, 3000.0, 4000.0)
+            .exchange_rates(100.0, 2.0, 5.0)
+            .logger(Logger::disabled())
+            .build();
+        let market = market.as_ref().borrow();
+
+        for (kind, quantity, rate) in [
+            (GoodKind::EUR, 1000.0, 1.0),
+            (GoodKind::YEN, 2000.0, 100.0),
+            (GoodKind::USD, 3000.0, 2.0),
+            (GoodKind::YUAN, 4000.0, 5.0),
+        ] {
+            let label = market
+                .get_goods()
+                .into_iter()
+                .find(|l| l.good_kind == kind)
+                .unwrap();
+            assert_eq!(quantity, label.quantity, "Wrong quantity of {}", kind);
+            assert_eq!(rate, label.exchange_rate_buy, "Wrong rate of {}", kind);
+        }
+    }
+
+    #[test]
+    fn test_builder_seed() {
+        let get_quantities = |seed: u64| {
+            SGXBuilder::new()
+                .seed(seed)
+                .logger(Logger::disabled())
+                .build_sgx()
+                .get_goods()
+                .iter()
+                .map(|l| l.quantity)
+                .collect::<Vec<f32>>()
+        };
+        assert_eq!(
+            get_quantities(42),
+            get_quantities(42),
+            "The same seed must result in the same quantities"
+        );
+        assert_ne!(
+            get_quantities(42),
+            get_quantities(43),
+            "Different seeds should result in different quantities"
+        );
+    }
+
+    #[test]
+    fn test_builder_margin() {
+        let build_market = |buy_margin: f32| {
+            SGXBuilder::new()
+                .quantities(1000.0, 1000.0, 1000.0, 1000.0)
+                .buy_margin(buy_margin)
+                .logger(Logger::disabled())
+                .build_sgx()
+        };
+        let cheap_price = build_market(0.0)
+            .get_buy_price(GoodKind::USD, 10.0)
+            .unwrap();
+        let expensive_price = build_market(0.5)
+            .get_buy_price(GoodKind::USD, 10.0)
+            .unwrap();
+        assert!(
+            cheap_price * 1.4 < expensive_price,
+            "Buy margin must increase the price"
+        );
+    }
+    #[test]
+    fn test_builder_invalid_goods() {
+        let goods = vec![(GoodKind::EUR, 1000.0, 1.0), (GoodKind::YEN, -10.0, 150.0)];
+        let res = SGXBuilder::new()
+            .goods(goods.clone())
+            .logger(Logger::disabled())
+            .try_build_sgx();
+        assert!(
+            matches!(&res, Err(BuildError::InvalidGoods { goods: invalid }) if *invalid == goods),
+            "Negative quantity must be rejected"
+        );
+
+        let res = SGXBuilder::new()
+            .goods(vec![
+                (GoodKind::YEN, 10.0, 150.0),
+                (GoodKind::YEN, 10.0, 150.0),
+            ])
+            .logger(Logger::disabled())
+            .try_build_sgx();
+        assert!(res.is_err(), "A kind can't be listed twice");
+    }
+}
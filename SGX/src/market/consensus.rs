@@ -0,0 +1,177 @@
+//! Consensus prices of the goods. From the events of other markets, the SGX keeps a rolling
+//! estimate of the "fair" price per unit of every good, separately for buying and selling. The
+//! estimate is an exponential moving average of the prices per unit of the events.
+//!
+//! If enabled in the config, the quotes of the SGX are pulled towards the consensus after each
+//! event, in addition to the reaction of the pricing model (see `ConsensusConfig`).
+use serde::{Deserialize, Serialize};
+use unitn_market_2022::good::good_kind::GoodKind;
+
+/// Parameters of the consensus prices
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ConsensusConfig {
+    /// Weight of the latest event in the moving average, between 0 and 1
+    pub smoothing: f32,
+    /// How far our quote is pulled towards the consensus after each event, between 0 (not at
+    /// all) and 1 (our quote of the same quantity becomes the consensus price)
+    pub strength: f32,
+}
+
+impl Default for ConsensusConfig {
+    /// The consensus is estimated, but the quotes are not pulled towards it
+    fn default() -> Self {
+        Self {
+            smoothing: 0.2,
+            strength: 0.0,
+        }
+    }
+}
+
+/// The estimated price per unit of one side of a good
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PriceEstimate {
+    /// Moving average of the price per unit, in EUR
+    pub unit_price: f32,
+    /// Moving average of the traded quantity
+    pub quantity: f32,
+    /// Number of events the estimate is based on
+    pub samples: u32,
+}
+
+impl PriceEstimate {
+    /// Adds an event with the given quantity and price to the moving averages
+    fn add(&mut self, quantity: f32, price: f32, smoothing: f32) {
+        let smoothing = smoothing.clamp(0.0, 1.0);
+        self.unit_price += smoothing * (price / quantity - self.unit_price);
+        self.quantity += smoothing * (quantity - self.quantity);
+        self.samples = self.samples.saturating_add(1);
+    }
+}
+
+/// The consensus prices of a single good
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoodConsensus {
+    /// Kind of the good
+    #[serde(with = "crate::goods::serde_good_kind")]
+    pub kind: GoodKind,
+    /// Price a trader pays to other markets, from their `Bought` and `LockedBuy` events
+    pub buy: Option<PriceEstimate>,
+    /// Price other markets pay to a trader, from their `Sold` and `LockedSell` events
+    pub sell: Option<PriceEstimate>,
+}
+
+/// The consensus prices of all goods, that other markets have traded so far
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ConsensusPrices {
+    goods: Vec<GoodConsensus>,
+}
+
+impl ConsensusPrices {
+    /// Returns the consensus prices of the given kind
+    pub fn get(&self, kind: &GoodKind) -> Option<&GoodConsensus> {
+        self.goods.iter().find(|g| g.kind == *kind)
+    }
+
+    /// Returns the consensus prices of all goods
+    pub fn iter(&self) -> impl Iterator<Item = &GoodConsensus> {
+        self.goods.iter()
+    }
+
+    /// Returns the mutable consensus prices of the given kind, they are added if missing
+    fn get_or_insert(&mut self, kind: GoodKind) -> &mut GoodConsensus {
+        let index = match self.goods.iter().position(|g| g.kind == kind) {
+            Some(index) => index,
+            None => {
+                self.goods.push(GoodConsensus {
+                    kind,
+                    buy: None,
+                    sell: None,
+                });
+                self.goods.len() - 1
+            }
+        };
+        &mut self.goods[index]
+    }
+
+    /// Adds an event of another market and returns the updated estimate. Events with a
+    /// quantity or price that is not positive are ignored.
+    pub(crate) fn add_event(
+        &mut self,
+        kind: GoodKind,
+        is_buy: bool,
+        quantity: f32,
+        price: f32,
+        smoothing: f32,
+    ) -> Option<PriceEstimate> {
+        let is_valid = |value: f32| value.is_finite() && value > 0.0;
+        if !is_valid(quantity) || !is_valid(price) || !is_valid(price / quantity) {
+            return None;
+        }
+
+        let consensus = self.get_or_insert(kind);
+        let estimate = if is_buy {
+            &mut consensus.buy
+        } else {
+            &mut consensus.sell
+        };
+        match estimate {
+            Some(estimate) => estimate.add(quantity, price, smoothing),
+            None => {
+                // the first event is the estimate
+                *estimate = Some(PriceEstimate {
+                    unit_price: price / quantity,
+                    quantity,
+                    samples: 1,
+                })
+            }
+        }
+        *estimate
+    }
+}
+
+/// How far the quotes of the market deviate from the consensus of a good
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ConsensusDeviation {
+    /// Kind of the good
+    #[serde(with = "crate::goods::serde_good_kind")]
+    pub kind: GoodKind,
+    /// Relative deviation of our buy quote from the consensus (e.g. 0.1 if we are 10% more
+    /// expensive), for the average quantity of the events
+    pub buy_deviation: Option<f32>,
+    /// Relative deviation of our sell quote from the consensus (e.g. -0.1 if we pay 10% less),
+    /// for the average quantity of the events
+    pub sell_deviation: Option<f32>,
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::market::consensus::ConsensusPrices;
+    use unitn_market_2022::good::good_kind::GoodKind;
+
+    #[test]
+    fn test_consensus_prices() {
+        let mut consensus = ConsensusPrices::default();
+        let estimate = consensus
+            .add_event(GoodKind::USD, true, 10.0, 20.0, 0.5)
+            .unwrap();
+        assert_eq!(2.0, estimate.unit_price, "First event is the estimate");
+
+        let estimate = consensus
+            .add_event(GoodKind::USD, true, 10.0, 40.0, 0.5)
+            .unwrap();
+        assert_eq!(3.0, estimate.unit_price);
+        assert_eq!(2, estimate.samples);
+        assert!(
+            consensus.get(&GoodKind::USD).unwrap().sell.is_none(),
+            "Sell side must not be touched"
+        );
+
+        assert!(consensus
+            .add_event(GoodKind::YEN, false, 0.0, 40.0, 0.5)
+            .is_none());
+        assert!(consensus
+            .add_event(GoodKind::YEN, false, 10.0, f32::NAN, 0.5)
+            .is_none());
+        assert!(consensus.get(&GoodKind::YEN).is_none());
+    }
+}
@@ -1,2 +1,6 @@
 pub const NAME: &str = "SGX";
 pub const LOG_PATH: &str = "log_SGX.txt";
+/// Min. number of days after which a lock expires (see specifications)
+pub const MIN_LOCK_EXPIRY_IN_DAYS: u8 = 3;
+/// Max. number of days after which a lock expires (see specifications)
+pub const MAX_LOCK_EXPIRY_IN_DAYS: u8 = 15;
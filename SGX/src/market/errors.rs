@@ -0,0 +1,252 @@
+use std::fmt::{Debug, Display, Formatter};
+use unitn_market_2022::good::good_kind::GoodKind;
+use unitn_market_2022::market::{LockBuyError, LockSellError};
+
+use crate::goods::goods_factory::GoodEntry;
+
+/**
+ * An `OwnedTokenError` is returned by the trading methods of `SGX` that check the owner of a
+ * token (e.g. `SGX::buy_as`). Either the token belongs to another trader, or the wrapped
+ * market operation itself failed.
+ */
+#[derive(Debug, PartialEq)]
+pub enum OwnedTokenError<E> {
+    /// The lock of the token is owned by another trader
+    NotTokenOwner {
+        token: String,
+        owner: String,
+        trader_name: String,
+    },
+    /// The market operation failed
+    Market(E),
+}
+
+impl<E: Debug> Display for OwnedTokenError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OwnedTokenError::NotTokenOwner {
+                token,
+                owner,
+                trader_name,
+            } => write!(
+                f,
+                "Token '{}' is owned by trader '{}' and can't be used by trader '{}'",
+                token, owner, trader_name
+            ),
+            OwnedTokenError::Market(e) => write!(f, "{:?}", e),
+        }
+    }
+}
+
+/**
+ * A `LockLimitError` explains, why a lock was rejected with `MaxAllowedLocksReached`
+ * (see `SGX::check_lock_limit`).
+ */
+#[derive(Debug, PartialEq)]
+pub enum LockLimitError {
+    /// The trader has already locked the max. number of goods
+    MaxLockedGoodsReached {
+        trader_name: String,
+        locked_goods: usize,
+    },
+    /// The trader holds the max. number of locks
+    TraderQuotaReached { trader_name: String, locks: usize },
+    /// All traders together hold the max. number of locks. The blocking traders are listed with
+    /// their number of locks, the most locks first.
+    GlobalCapReached {
+        blocking_traders: Vec<(String, usize)>,
+    },
+}
+
+impl Display for LockLimitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockLimitError::MaxLockedGoodsReached {
+                trader_name,
+                locked_goods,
+            } => write!(
+                f,
+                "Trader '{}' has already locked {} goods",
+                trader_name, locked_goods
+            ),
+            LockLimitError::TraderQuotaReached { trader_name, locks } => {
+                write!(f, "Trader '{}' already holds {} locks", trader_name, locks)
+            }
+            LockLimitError::GlobalCapReached { blocking_traders } => {
+                let traders = blocking_traders
+                    .iter()
+                    .map(|(name, locks)| format!("'{}' ({})", name, locks))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                write!(f, "All locks are held by {}", traders)
+            }
+        }
+    }
+}
+
+/**
+ * A `CheckedLockError` is returned by the lock methods of `SGX` that explain a rejected lock
+ * (e.g. `SGX::lock_buy_checked`). Either a lock limit is reached, or the wrapped market operation
+ * itself failed.
+ */
+#[derive(Debug, PartialEq)]
+pub enum CheckedLockError<E> {
+    /// A lock limit is reached, instead of `MaxAllowedLocksReached`
+    LockLimit(LockLimitError),
+    /// The market operation failed
+    Market(E),
+}
+
+impl<E: Debug> Display for CheckedLockError<E> {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CheckedLockError::LockLimit(e) => write!(f, "{}", e),
+            CheckedLockError::Market(e) => write!(f, "{:?}", e),
+        }
+    }
+}
+
+impl From<CheckedLockError<LockBuyError>> for LockBuyError {
+    /// Returns the error of `Market::lock_buy`, a reached lock limit is `MaxAllowedLocksReached`
+    fn from(error: CheckedLockError<LockBuyError>) -> Self {
+        match error {
+            CheckedLockError::LockLimit(_) => LockBuyError::MaxAllowedLocksReached,
+            CheckedLockError::Market(e) => e,
+        }
+    }
+}
+
+impl From<CheckedLockError<LockSellError>> for LockSellError {
+    /// Returns the error of `Market::lock_sell`, a reached lock limit is `MaxAllowedLocksReached`
+    fn from(error: CheckedLockError<LockSellError>) -> Self {
+        match error {
+            CheckedLockError::LockLimit(_) => LockSellError::MaxAllowedLocksReached,
+            CheckedLockError::Market(e) => e,
+        }
+    }
+}
+
+/// A `CancelLockError` is returned by `SGX::cancel_lock`
+#[derive(Debug, PartialEq)]
+pub enum CancelLockError {
+    UnrecognizedToken {
+        unrecognized_token: String,
+    },
+    /// The lock was already settled, cancelled or it has expired
+    ExpiredToken {
+        expired_token: String,
+    },
+}
+
+impl Display for CancelLockError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CancelLockError::UnrecognizedToken { unrecognized_token } => {
+                write!(f, "Token '{}' is unknown", unrecognized_token)
+            }
+            CancelLockError::ExpiredToken { expired_token } => {
+                write!(f, "Token '{}' has expired", expired_token)
+            }
+        }
+    }
+}
+
+/// An `OrderError` is returned by the limit order methods of `SGX` (e.g. `SGX::place_order`)
+#[derive(Debug, PartialEq)]
+pub enum OrderError {
+    /// Limit orders are not enabled in the config of the market
+    OrderBookDisabled,
+    /// Only goods that are not the default good can be ordered
+    DefaultGoodKind,
+    NonPositiveQuantity {
+        quantity: f32,
+    },
+    NonPositivePrice {
+        price_per_unit: f32,
+    },
+    /// The escrow is not of the required kind
+    WrongEscrowKind {
+        required_kind: GoodKind,
+        escrow_kind: GoodKind,
+    },
+    /// The escrow does not contain the required quantity
+    InsufficientEscrow {
+        required_quantity: f32,
+        contained_quantity: f32,
+    },
+    UnknownOrder {
+        id: u64,
+    },
+    /// The order is owned by another trader
+    NotOrderOwner {
+        id: u64,
+        owner: String,
+        trader_name: String,
+    },
+    /// The order can't be cancelled, because it was already filled
+    AlreadyFilled {
+        id: u64,
+    },
+    /// The order can't be claimed, because it was not filled yet
+    NotFilled {
+        id: u64,
+    },
+}
+
+impl Display for OrderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            OrderError::OrderBookDisabled => write!(f, "Limit orders are disabled"),
+            OrderError::DefaultGoodKind => write!(f, "The default good can't be ordered"),
+            OrderError::NonPositiveQuantity { quantity } => {
+                write!(f, "Quantity {} must be positive", quantity)
+            }
+            OrderError::NonPositivePrice { price_per_unit } => {
+                write!(f, "Price per unit {} must be positive", price_per_unit)
+            }
+            OrderError::WrongEscrowKind {
+                required_kind,
+                escrow_kind,
+            } => write!(
+                f,
+                "Escrow must be {}, but is {}",
+                required_kind, escrow_kind
+            ),
+            OrderError::InsufficientEscrow {
+                required_quantity,
+                contained_quantity,
+            } => write!(
+                f,
+                "Escrow must contain {}, but contains {}",
+                required_quantity, contained_quantity
+            ),
+            OrderError::UnknownOrder { id } => write!(f, "Order {} is unknown", id),
+            OrderError::NotOrderOwner {
+                id,
+                owner,
+                trader_name,
+            } => write!(
+                f,
+                "Order {} is owned by trader '{}' and can't be used by trader '{}'",
+                id, owner, trader_name
+            ),
+            OrderError::AlreadyFilled { id } => write!(f, "Order {} is already filled", id),
+            OrderError::NotFilled { id } => write!(f, "Order {} is not filled yet", id),
+        }
+    }
+}
+
+/// A `BuildError` is returned by `SGXBuilder::try_build_sgx`
+#[derive(Debug, PartialEq)]
+pub enum BuildError {
+    /// The goods given with `SGXBuilder::goods` are invalid, see `GoodStorage::from_snapshots`
+    InvalidGoods { goods: Vec<GoodEntry> },
+}
+
+impl Display for BuildError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            BuildError::InvalidGoods { goods } => write!(f, "Invalid goods {:?}", goods),
+        }
+    }
+}
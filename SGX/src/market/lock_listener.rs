@@ -0,0 +1,24 @@
+use crate::goods::good_status::GoodLock;
+
+/// The side of a lock
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockSide {
+    /// Lock of `Market::lock_buy`
+    Buy,
+    /// Lock of `Market::lock_sell`
+    Sell,
+}
+
+/**
+ * A `LockListener` is notified about changes of locks, that are not covered by the events of
+ * the market common (there is no `EventKind` for a cancelled or an expired lock).
+ * Listeners are added with `SGX::add_lock_listener`. The owning trader of a lock is
+ * `GoodLock::trader_name`.
+ */
+pub trait LockListener {
+    /// Called after the given lock was cancelled with `SGX::cancel_lock`
+    fn on_lock_cancelled(&mut self, _side: LockSide, _lock: &GoodLock) {}
+
+    /// Called after the given lock has expired and was evicted by the market
+    fn on_lock_expired(&mut self, _side: LockSide, _lock: &GoodLock) {}
+}
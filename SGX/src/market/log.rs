@@ -1,65 +1,248 @@
 use chrono::prelude::*;
-use std::fs::{File, OpenOptions};
-use std::io::Write;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io;
+use std::io::{BufWriter, Write};
 use unitn_market_2022::good::good_kind::GoodKind;
 
 use crate::market::consts::{LOG_PATH, NAME};
 
-// create a log file named log_SGX.txt
-fn create_log() {
-    match File::create(LOG_PATH) {
-        Ok(_) => println!("File created"),
-        Err(e) => println!("Unable to create the file {LOG_PATH}: {e}"),
-    };
+/// The format of the lines, that are written by a `Logger`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Text lines as specified by the market common, e.g. `SGX|2022::11::20::...|BUY-TOKEN:...-OK`
+    #[default]
+    Text,
+    /// One JSON object per line (JSON-lines), including the time and the market name
+    JsonLines,
 }
 
-// open the file log_SGX.txt in read/write/append mode (so it will add a line)
-fn open_log() -> File {
-    let log = OpenOptions::new()
-        .write(true)
-        .append(true)
-        .open(LOG_PATH)
-        .unwrap();
-
-    log
+/**
+ * A `LogEvent` is a single event of the market, that is written to the log.
+ * Errors contain the debug representation of the returned error (e.g. `MaxAllowedLocksReached`).
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum LogEvent {
+    MarketInit {
+        eur: f32,
+        usd: f32,
+        yen: f32,
+        yuan: f32,
+    },
+    LockBuy {
+        trader_name: String,
+        #[serde(with = "crate::goods::serde_good_kind")]
+        kind_to_buy: GoodKind,
+        quantity_to_buy: f32,
+        bid: f32,
+        token: String,
+    },
+    LockBuyError {
+        trader_name: String,
+        #[serde(with = "crate::goods::serde_good_kind")]
+        kind_to_buy: GoodKind,
+        quantity_to_buy: f32,
+        bid: f32,
+        error: String,
+    },
+    LockSell {
+        trader_name: String,
+        #[serde(with = "crate::goods::serde_good_kind")]
+        kind_to_sell: GoodKind,
+        quantity_to_sell: f32,
+        offer: f32,
+        token: String,
+    },
+    LockSellError {
+        trader_name: String,
+        #[serde(with = "crate::goods::serde_good_kind")]
+        kind_to_sell: GoodKind,
+        quantity_to_sell: f32,
+        offer: f32,
+        error: String,
+    },
+    Buy {
+        token: String,
+    },
+    BuyError {
+        token: String,
+        error: String,
+    },
+    Sell {
+        token: String,
+    },
+    SellError {
+        token: String,
+        error: String,
+    },
+    /// A lock was cancelled by its trader
+    CancelLock {
+        token: String,
+    },
+    CancelLockError {
+        token: String,
+        error: String,
+    },
+    /// A day has passed, `day` is the number of days since the market initialization
+    Wait {
+        day: u32,
+    },
 }
 
-// create the log file, than open the file in read/write mode. It will delete the content and override the file.
-fn init_log() -> File {
-    create_log();
+impl LogEvent {
+    /// Returns the log code of the event in the text format of the market common.
+    /// The text format has no code for waiting and cancelling, therefore `None` is returned
+    /// for these events.
+    pub fn to_text(&self) -> Option<String> {
+        let log_code = match self {
+            LogEvent::MarketInit {
+                eur,
+                usd,
+                yen,
+                yuan,
+            } => format!(
+                "\nMARKET_INITIALIZATION\nEUR{:+e}\nUSD:{:+e}\nYEN:{:+e}\nYUAN:{:+e}\nEND_MARKET_INITIALIZATION",
+                eur, usd, yen, yuan
+            ),
+            LogEvent::LockBuy {
+                trader_name,
+                kind_to_buy,
+                quantity_to_buy,
+                bid,
+                token,
+            } => format!(
+                "LOCK_BUY-{}-KIND_TO_BUY:{}-QUANTITY_TO_BUY:{}-BID:{}-TOKEN:{}",
+                trader_name, kind_to_buy, quantity_to_buy, bid, token
+            ),
+            LogEvent::LockBuyError {
+                trader_name,
+                kind_to_buy,
+                quantity_to_buy,
+                bid,
+                ..
+            } => format!(
+                "LOCK_BUY-{}-KIND_TO_BUY:{}-QUANTITY_TO_BUY:{}-BID:{}-ERROR",
+                trader_name, kind_to_buy, quantity_to_buy, bid
+            ),
+            LogEvent::LockSell {
+                trader_name,
+                kind_to_sell,
+                quantity_to_sell,
+                offer,
+                token,
+            } => format!(
+                "LOCK-SELL-{}-KIND_TO_SELL:{}-QUANTITY_TO_SELL:{}-OFFER:{}-TOKEN:{}",
+                trader_name, kind_to_sell, quantity_to_sell, offer, token
+            ),
+            LogEvent::LockSellError {
+                trader_name,
+                kind_to_sell,
+                quantity_to_sell,
+                offer,
+                ..
+            } => format!(
+                "LOCK-SELL-{}-KIND_TO_SELL:{}-QUANTITY_TO_SELL:{}-OFFER:{}-ERROR",
+                trader_name, kind_to_sell, quantity_to_sell, offer
+            ),
+            LogEvent::Buy { token } => format!("BUY-TOKEN:{}-OK", token),
+            LogEvent::BuyError { token, .. } => format!("BUY-TOKEN:{}-ERROR", token),
+            LogEvent::Sell { token } => format!("SELL-TOKEN:{}-OK", token),
+            LogEvent::SellError { token, .. } => format!("SELL-TOKEN:{}-ERROR", token),
+            LogEvent::CancelLock { .. }
+            | LogEvent::CancelLockError { .. }
+            | LogEvent::Wait { .. } => return None,
+        };
+        Some(log_code)
+    }
+}
 
-    let log = OpenOptions::new()
-        .write(true)
-        .truncate(true)
-        .open(LOG_PATH)
-        .unwrap();
+/// A single line of the JSON-lines format
+#[derive(Serialize)]
+struct JsonRecord<'a> {
+    market: &'a str,
+    time: String,
+    #[serde(flatten)]
+    event: &'a LogEvent,
+}
 
-    log
+/**
+ * The `Logger` writes the events of a market to a writer (usually the log file), either as
+ * text or as JSON-lines. The writer is kept open and flushed after every event.
+ */
+pub struct Logger {
+    format: LogFormat,
+    writer: Option<Box<dyn Write>>,
 }
 
-// write the line to the log file, including the market name and the date like the specification requires
-fn write_to_log(log_code: String) {
-    let date = get_date();
+impl Logger {
+    /// Constructs a new logger that writes to the given writer
+    pub fn new(format: LogFormat, writer: Box<dyn Write>) -> Self {
+        Self {
+            format,
+            writer: Some(writer),
+        }
+    }
 
-    let line = format!("{}{}\n", date, log_code);
+    /// Constructs a new logger that writes to the file at the given path.
+    /// The file is created, or truncated if it already exists.
+    pub fn with_file(format: LogFormat, path: &str) -> io::Result<Self> {
+        let file = File::create(path)?;
+        Ok(Logger::new(format, Box::new(BufWriter::new(file))))
+    }
 
-    let mut log = open_log();
-    match write!(log, "{}", line) {
-        Ok(_) => (),
-        Err(e) => println!("Error while logging: {e}"),
-    };
-}
+    /// Constructs a logger that does not write anything
+    pub fn disabled() -> Self {
+        Self {
+            format: LogFormat::default(),
+            writer: None,
+        }
+    }
+
+    /// Returns the format of this logger
+    pub fn get_format(&self) -> LogFormat {
+        self.format
+    }
 
-fn write_market_init(log_code: String) {
-    let date = get_date();
+    /// Writes the given event
+    pub fn log(&mut self, event: LogEvent) {
+        let line = match self.format {
+            LogFormat::Text => match event.to_text() {
+                Some(log_code) => format!("{}{}", get_date(), log_code),
+                None => return,
+            },
+            LogFormat::JsonLines => {
+                let record = JsonRecord {
+                    market: NAME,
+                    time: Local::now().to_rfc3339(),
+                    event: &event,
+                };
+                match serde_json::to_string(&record) {
+                    Ok(line) => line,
+                    Err(e) => {
+                        println!("Error while logging: {e}");
+                        return;
+                    }
+                }
+            }
+        };
 
-    let line = format!("{}{}\n", date, log_code);
+        if let Some(writer) = self.writer.as_mut() {
+            if let Err(e) = writeln!(writer, "{}", line).and_then(|_| writer.flush()) {
+                println!("Error while logging: {e}");
+            }
+        }
+    }
+}
 
-    let mut log = init_log();
-    match write! {log, "{}", line} {
-        Ok(_) => (),
-        Err(e) => println!("Error while logging market init: {e}"),
-    };
+impl Default for Logger {
+    /// Writes text to the file log_SGX.txt
+    fn default() -> Self {
+        Logger::with_file(LogFormat::Text, LOG_PATH).unwrap_or_else(|e| {
+            println!("Unable to create the file {LOG_PATH}: {e}");
+            Logger::disabled()
+        })
+    }
 }
 
 // return a string containing the date formatted like how specified in the market common plus the name of the market
@@ -81,99 +264,91 @@ pub fn get_date() -> String {
     date
 }
 
-// push to log file the initialization of the market
-pub fn log_for_market_init(eur: f32, yen: f32, usd: f32, yuan: f32) {
-    let log_code = format!("\nMARKET_INITIALIZATION\nEUR{:+e}\nUSD:{:+e}\nYEN:{:+e}\nYUAN:{:+e}\nEND_MARKET_INITIALIZATION",
-                           eur,
-                           usd,
-                           yen,
-                           yuan);
+#[cfg(test)]
+mod tests {
+    use crate::market::log::{LogEvent, LogFormat, Logger};
+    use std::cell::RefCell;
+    use std::io;
+    use std::io::Write;
+    use std::rc::Rc;
+    use unitn_market_2022::good::good_kind::GoodKind;
 
-    write_market_init(log_code);
-}
+    /// A writer that can be inspected after it was moved into a logger
+    #[derive(Clone, Default)]
+    struct SharedBuffer(Rc<RefCell<Vec<u8>>>);
 
-// push to log file that the lock buy function returned an error
-pub fn log_for_lock_buy_err(
-    trader_name: String,
-    kind_to_buy: GoodKind,
-    quantity_to_buy: f32,
-    bid: f32,
-) {
-    let log_code = format!(
-        "LOCK_BUY-{}-KIND_TO_BUY:{}-QUANTITY_TO_BUY:{}-BID:{}-ERROR",
-        trader_name, kind_to_buy, quantity_to_buy, bid
-    );
+    impl Write for SharedBuffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
 
-    write_to_log(log_code);
-}
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
 
-// push to log file that the lock buy function returned an ok
-pub fn log_for_lock_buy(
-    trader_name: String,
-    kind_to_buy: GoodKind,
-    quantity_to_buy: f32,
-    bid: f32,
-    token: String,
-) {
-    let log_code = format!(
-        "LOCK_BUY-{}-KIND_TO_BUY:{}-QUANTITY_TO_BUY:{}-BID:{}-TOKEN:{}",
-        trader_name, kind_to_buy, quantity_to_buy, bid, token
-    );
+    impl SharedBuffer {
+        fn lines(&self) -> Vec<String> {
+            String::from_utf8(self.0.borrow().clone())
+                .unwrap()
+                .lines()
+                .map(String::from)
+                .collect()
+        }
+    }
 
-    write_to_log(log_code);
-}
+    fn lock_buy_error() -> LogEvent {
+        LogEvent::LockBuyError {
+            trader_name: "TEST_TRADER".to_string(),
+            kind_to_buy: GoodKind::USD,
+            quantity_to_buy: 10.0,
+            bid: 5.0,
+            error: "MaxAllowedLocksReached".to_string(),
+        }
+    }
 
-// push to log file that the lock sell function returned an error
-pub fn log_for_lock_sell_err(
-    trader_name: String,
-    kind_to_sell: GoodKind,
-    quantity_to_sell: f32,
-    offer: f32,
-) {
-    let log_code = format!(
-        "LOCK-SELL-{}-KIND_TO_SELL:{}-QUANTITY_TO_SELL:{}-OFFER:{}-ERROR",
-        trader_name, kind_to_sell, quantity_to_sell, offer
-    );
+    #[test]
+    fn test_text_format() {
+        let buffer = SharedBuffer::default();
+        let mut logger = Logger::new(LogFormat::Text, Box::new(buffer.clone()));
+        logger.log(lock_buy_error());
+        logger.log(LogEvent::Wait { day: 1 });
 
-    write_to_log(log_code);
-}
+        let lines = buffer.lines();
+        assert_eq!(1, lines.len(), "Waiting is not part of the text format");
+        assert!(
+            lines[0].starts_with("SGX|"),
+            "Line must start with the name"
+        );
+        assert!(
+            lines[0]
+                .ends_with("|LOCK_BUY-TEST_TRADER-KIND_TO_BUY:USD-QUANTITY_TO_BUY:10-BID:5-ERROR"),
+            "Unexpected line '{}'",
+            lines[0]
+        );
+    }
 
-// push to log file that the lock sell function returned an ok
-pub fn log_for_lock_sell(
-    trader_name: String,
-    kind_to_sell: GoodKind,
-    quantity_to_sell: f32,
-    offer: f32,
-    token: String,
-) {
-    let log_code = format!(
-        "LOCK-SELL-{}-KIND_TO_SELL:{}-QUANTITY_TO_SELL:{}-OFFER:{}-TOKEN:{}",
-        trader_name, kind_to_sell, quantity_to_sell, offer, token
-    );
+    #[test]
+    fn test_json_lines_format() {
+        let buffer = SharedBuffer::default();
+        let mut logger = Logger::new(LogFormat::JsonLines, Box::new(buffer.clone()));
+        logger.log(lock_buy_error());
+        logger.log(LogEvent::Wait { day: 1 });
 
-    write_to_log(log_code);
-}
+        let lines = buffer.lines();
+        assert_eq!(2, lines.len(), "Every event must be written as a line");
 
-// push to log file that the buy function returned an error
-pub fn log_for_buy_err(token: String) {
-    let log_code = format!("BUY-TOKEN:{}-ERROR", token);
-    write_to_log(log_code);
-}
-
-// push to log file that the buy function returned an ok
-pub fn log_for_buy(token: String) {
-    let log_code = format!("BUY-TOKEN:{}-OK", token);
-    write_to_log(log_code);
-}
+        let record: serde_json::Value = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!("SGX", record["market"]);
+        assert_eq!("lock_buy_error", record["event"]);
+        assert_eq!("USD", record["kind_to_buy"]);
+        assert_eq!("MaxAllowedLocksReached", record["error"]);
+        assert!(record["time"].is_string(), "Record must contain the time");
 
-// push to log file that the sell function returned an error
-pub fn log_for_sell_err(token: String) {
-    let log_code = format!("SELL-TOKEN:{}-ERROR", token);
-    write_to_log(log_code);
-}
+        let event: LogEvent = serde_json::from_str(&lines[0]).unwrap();
+        assert_eq!(lock_buy_error(), event, "Record must be read back as event");
 
-// push to log file that the sell function returned an ok
-pub fn log_for_sell(token: String) {
-    let log_code = format!("SELL-TOKEN:{}-OK", token);
-    write_to_log(log_code);
+        let event: LogEvent = serde_json::from_str(&lines[1]).unwrap();
+        assert_eq!(LogEvent::Wait { day: 1 }, event);
+    }
 }
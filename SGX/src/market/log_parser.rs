@@ -0,0 +1,419 @@
+//! Parses log files, that are written in the text format of the market common
+//! (e.g. `SGX|2022::11::20::6::30::20::1423|BUY-TOKEN:...-OK`), back into `LogEvent`s.
+//!
+//! The text format does not contain the returned errors, therefore the `error` of a parsed
+//! error event is always empty. The multi-line `MARKET_INITIALIZATION` block is parsed as a
+//! single `LogEvent::MarketInit`.
+use chrono::{NaiveDate, NaiveDateTime};
+use std::fmt::{Display, Formatter};
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use unitn_market_2022::good::good_kind::GoodKind;
+
+use crate::goods::serde_good_kind::kind_from_name;
+use crate::market::log::LogEvent;
+
+/// A single parsed entry of the log
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogEntry {
+    /// Name of the market that wrote the entry
+    pub market: String,
+    /// Local time the entry was written
+    pub time: NaiveDateTime,
+    pub event: LogEvent,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum LogParseError {
+    /// The log can't be read
+    Io(String),
+    /// The line does not match the text format
+    InvalidLine {
+        line_number: usize,
+        line: String,
+        reason: String,
+    },
+    /// The log ends inside a `MARKET_INITIALIZATION` block
+    IncompleteMarketInitialization { line_number: usize },
+}
+
+impl Display for LogParseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LogParseError::Io(e) => write!(f, "Unable to read the log: {}", e),
+            LogParseError::InvalidLine {
+                line_number,
+                line,
+                reason,
+            } => write!(f, "Invalid line {} '{}': {}", line_number, line, reason),
+            LogParseError::IncompleteMarketInitialization { line_number } => write!(
+                f,
+                "Market initialization at line {} is not complete",
+                line_number
+            ),
+        }
+    }
+}
+
+/// Parses the log file at the given path
+pub fn parse_log_file(path: &str) -> Result<Vec<LogEntry>, LogParseError> {
+    let file = File::open(path).map_err(|e| LogParseError::Io(e.to_string()))?;
+    parse_log(BufReader::new(file))
+}
+
+/// Parses all entries of the given log. Empty lines are skipped.
+pub fn parse_log<R: BufRead>(reader: R) -> Result<Vec<LogEntry>, LogParseError> {
+    let mut entries = Vec::new();
+    let mut lines = reader.lines().enumerate();
+
+    while let Some((index, line)) = lines.next() {
+        let line_number = index + 1;
+        let line = line.map_err(|e| LogParseError::Io(e.to_string()))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let invalid = |reason: &str| LogParseError::InvalidLine {
+            line_number,
+            line: line.clone(),
+            reason: reason.to_string(),
+        };
+        let (market, time, log_code) = parse_header(&line).map_err(invalid)?;
+
+        let event = if log_code.is_empty() {
+            // the header of the market initialization is followed by its block
+            let mut block = Vec::new();
+            for (index, line) in lines.by_ref() {
+                let line = line.map_err(|e| LogParseError::Io(e.to_string()))?;
+                let is_end = line.trim() == "END_MARKET_INITIALIZATION";
+                block.push((index + 1, line));
+                if is_end {
+                    break;
+                }
+            }
+            parse_market_init(line_number, &block)?
+        } else {
+            parse_log_code(log_code).map_err(invalid)?
+        };
+
+        entries.push(LogEntry {
+            market: market.to_string(),
+            time,
+            event,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Parses a single line of the log. The market initialization spans multiple lines and
+/// can only be parsed by `parse_log`.
+pub fn parse_line(line: &str) -> Result<LogEntry, LogParseError> {
+    let invalid = |reason: &str| LogParseError::InvalidLine {
+        line_number: 1,
+        line: line.to_string(),
+        reason: reason.to_string(),
+    };
+    let (market, time, log_code) = parse_header(line).map_err(invalid)?;
+    if log_code.is_empty() {
+        return Err(LogParseError::IncompleteMarketInitialization { line_number: 1 });
+    }
+    let event = parse_log_code(log_code).map_err(invalid)?;
+    Ok(LogEntry {
+        market: market.to_string(),
+        time,
+        event,
+    })
+}
+
+/// Splits the line into the market name, the time and the log code
+fn parse_header(line: &str) -> Result<(&str, NaiveDateTime, &str), &'static str> {
+    let mut parts = line.splitn(3, '|');
+    let market = parts.next().ok_or("Missing market name")?;
+    let date = parts.next().ok_or("Missing date")?;
+    let log_code = parts.next().ok_or("Missing log code")?;
+    Ok((market, parse_date(date)?, log_code.trim_end()))
+}
+
+/// Parses a date of the format `yyyy::mm::dd::hh::mm::ss::nnnn`
+fn parse_date(date: &str) -> Result<NaiveDateTime, &'static str> {
+    let values = date
+        .split("::")
+        .map(|v| v.parse::<u32>())
+        .collect::<Result<Vec<u32>, _>>()
+        .map_err(|_| "Date contains invalid numbers")?;
+    if values.len() != 7 {
+        return Err("Date must contain 7 values");
+    }
+    NaiveDate::from_ymd_opt(values[0] as i32, values[1], values[2])
+        .and_then(|d| d.and_hms_nano_opt(values[3], values[4], values[5], values[6]))
+        .ok_or("Date is out of range")
+}
+
+/// Parses the block of a market initialization, the first line is the line of the header
+fn parse_market_init(
+    line_number: usize,
+    block: &[(usize, String)],
+) -> Result<LogEvent, LogParseError> {
+    let is_complete = block.first().map(|(_, l)| l.trim()) == Some("MARKET_INITIALIZATION")
+        && block.last().map(|(_, l)| l.trim()) == Some("END_MARKET_INITIALIZATION");
+    if !is_complete {
+        return Err(LogParseError::IncompleteMarketInitialization { line_number });
+    }
+
+    let (mut eur, mut usd, mut yen, mut yuan) = (None, None, None, None);
+    for (line_number, line) in &block[1..block.len() - 1] {
+        let invalid = |reason: &str| LogParseError::InvalidLine {
+            line_number: *line_number,
+            line: line.clone(),
+            reason: reason.to_string(),
+        };
+        // the EUR line has no colon (e.g. `EUR+1e4`), but the others have (e.g. `USD:+1e4`)
+        let line = line.trim();
+        let name_len = line
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(line.len());
+        let (name, value) = line.split_at(name_len);
+        let value = value
+            .trim_start_matches(':')
+            .parse::<f32>()
+            .map_err(|_| invalid("Invalid quantity"))?;
+        match kind_from_name(name) {
+            Some(GoodKind::EUR) => eur = Some(value),
+            Some(GoodKind::USD) => usd = Some(value),
+            Some(GoodKind::YEN) => yen = Some(value),
+            Some(GoodKind::YUAN) => yuan = Some(value),
+            None => return Err(invalid("Unknown good kind")),
+        }
+    }
+
+    match (eur, usd, yen, yuan) {
+        (Some(eur), Some(usd), Some(yen), Some(yuan)) => Ok(LogEvent::MarketInit {
+            eur,
+            usd,
+            yen,
+            yuan,
+        }),
+        _ => Err(LogParseError::IncompleteMarketInitialization { line_number }),
+    }
+}
+
+/// Parses the log code (everything after the date) of a single line
+fn parse_log_code(log_code: &str) -> Result<LogEvent, &'static str> {
+    if let Some(rest) = log_code.strip_prefix("LOCK_BUY-") {
+        let (trader_name, kind, quantity, price, token) =
+            parse_lock(rest, "-KIND_TO_BUY:", "-QUANTITY_TO_BUY:", "-BID:")?;
+        return Ok(match token {
+            Some(token) => LogEvent::LockBuy {
+                trader_name,
+                kind_to_buy: kind,
+                quantity_to_buy: quantity,
+                bid: price,
+                token,
+            },
+            None => LogEvent::LockBuyError {
+                trader_name,
+                kind_to_buy: kind,
+                quantity_to_buy: quantity,
+                bid: price,
+                error: String::new(),
+            },
+        });
+    }
+
+    if let Some(rest) = log_code.strip_prefix("LOCK-SELL-") {
+        let (trader_name, kind, quantity, price, token) =
+            parse_lock(rest, "-KIND_TO_SELL:", "-QUANTITY_TO_SELL:", "-OFFER:")?;
+        return Ok(match token {
+            Some(token) => LogEvent::LockSell {
+                trader_name,
+                kind_to_sell: kind,
+                quantity_to_sell: quantity,
+                offer: price,
+                token,
+            },
+            None => LogEvent::LockSellError {
+                trader_name,
+                kind_to_sell: kind,
+                quantity_to_sell: quantity,
+                offer: price,
+                error: String::new(),
+            },
+        });
+    }
+
+    if let Some(rest) = log_code.strip_prefix("BUY-TOKEN:") {
+        return match parse_result(rest)? {
+            (token, true) => Ok(LogEvent::Buy { token }),
+            (token, false) => Ok(LogEvent::BuyError {
+                token,
+                error: String::new(),
+            }),
+        };
+    }
+
+    if let Some(rest) = log_code.strip_prefix("SELL-TOKEN:") {
+        return match parse_result(rest)? {
+            (token, true) => Ok(LogEvent::Sell { token }),
+            (token, false) => Ok(LogEvent::SellError {
+                token,
+                error: String::new(),
+            }),
+        };
+    }
+
+    Err("Unknown log code")
+}
+
+/// The values of a lock line: trader name, kind, quantity, price and the token if it was
+/// successful
+type LockValues = (String, GoodKind, f32, f32, Option<String>);
+
+/// Parses the values of a lock line (without its prefix), e.g.
+/// `trader-KIND_TO_BUY:USD-QUANTITY_TO_BUY:10-BID:5-TOKEN:abc`.
+/// Names and tokens may contain dashes, therefore the line is split at the keys.
+fn parse_lock(
+    rest: &str,
+    kind_key: &str,
+    quantity_key: &str,
+    price_key: &str,
+) -> Result<LockValues, &'static str> {
+    let (trader_name, rest) = rest.split_once(kind_key).ok_or("Missing good kind")?;
+    let (kind, rest) = rest.split_once(quantity_key).ok_or("Missing quantity")?;
+    let (quantity, rest) = rest.split_once(price_key).ok_or("Missing price")?;
+    let (price, token) = match rest.strip_suffix("-ERROR") {
+        Some(price) => (price, None),
+        None => {
+            let (price, token) = rest.split_once("-TOKEN:").ok_or("Missing result")?;
+            (price, Some(token.to_string()))
+        }
+    };
+
+    let kind = kind_from_name(kind).ok_or("Unknown good kind")?;
+    let quantity = quantity.parse::<f32>().map_err(|_| "Invalid quantity")?;
+    let price = price.parse::<f32>().map_err(|_| "Invalid price")?;
+    Ok((trader_name.to_string(), kind, quantity, price, token))
+}
+
+/// Parses the token and the result (`true` if `OK`) of a buy or sell line (without its prefix)
+fn parse_result(rest: &str) -> Result<(String, bool), &'static str> {
+    if let Some(token) = rest.strip_suffix("-OK") {
+        Ok((token.to_string(), true))
+    } else if let Some(token) = rest.strip_suffix("-ERROR") {
+        Ok((token.to_string(), false))
+    } else {
+        Err("Missing result")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::market::log::LogEvent;
+    use crate::market::log_parser::{parse_line, parse_log, LogParseError};
+    use chrono::{NaiveDate, Timelike};
+    use unitn_market_2022::good::good_kind::GoodKind;
+
+    #[test]
+    fn test_parse_log() {
+        let log = "SGX|2022::11::20::6::30::20::1423|\n\
+                   MARKET_INITIALIZATION\n\
+                   EUR+1e4\n\
+                   USD:+2.5e3\n\
+                   YEN:+1e5\n\
+                   YUAN:+7e3\n\
+                   END_MARKET_INITIALIZATION\n\
+                   SGX|2022::11::20::6::30::21::0|LOCK_BUY-my-trader-KIND_TO_BUY:USD-QUANTITY_TO_BUY:10.5-BID:12-TOKEN:0000-abcd\n\
+                   SGX|2022::11::20::6::30::22::0|LOCK-SELL-my-trader-KIND_TO_SELL:YEN-QUANTITY_TO_SELL:-1-OFFER:3-ERROR\n\
+                   SGX|2022::11::20::6::30::23::0|BUY-TOKEN:0000-abcd-OK\n\
+                   \n\
+                   SGX|2022::11::20::6::30::24::0|SELL-TOKEN:unknown-ERROR\n";
+
+        let entries = parse_log(log.as_bytes()).unwrap();
+        let events = entries
+            .iter()
+            .map(|e| e.event.clone())
+            .collect::<Vec<LogEvent>>();
+        assert_eq!(
+            vec![
+                LogEvent::MarketInit {
+                    eur: 10_000.0,
+                    usd: 2_500.0,
+                    yen: 100_000.0,
+                    yuan: 7_000.0
+                },
+                LogEvent::LockBuy {
+                    trader_name: "my-trader".to_string(),
+                    kind_to_buy: GoodKind::USD,
+                    quantity_to_buy: 10.5,
+                    bid: 12.0,
+                    token: "0000-abcd".to_string()
+                },
+                LogEvent::LockSellError {
+                    trader_name: "my-trader".to_string(),
+                    kind_to_sell: GoodKind::YEN,
+                    quantity_to_sell: -1.0,
+                    offer: 3.0,
+                    error: String::new()
+                },
+                LogEvent::Buy {
+                    token: "0000-abcd".to_string()
+                },
+                LogEvent::SellError {
+                    token: "unknown".to_string(),
+                    error: String::new()
+                },
+            ],
+            events
+        );
+
+        let expected_time = NaiveDate::from_ymd_opt(2022, 11, 20)
+            .unwrap()
+            .and_hms_nano_opt(6, 30, 20, 1423)
+            .unwrap();
+        assert_eq!("SGX", entries[0].market);
+        assert_eq!(expected_time, entries[0].time);
+        assert_eq!(21, entries[1].time.second());
+    }
+
+    #[test]
+    fn test_written_lines_are_parsed() {
+        let event = LogEvent::LockSell {
+            trader_name: "TEST_TRADER".to_string(),
+            kind_to_sell: GoodKind::YUAN,
+            quantity_to_sell: 0.25,
+            offer: 1234.5,
+            token: "00000001-0123456789abcdef".to_string(),
+        };
+        let line = format!(
+            "{}{}",
+            crate::market::log::get_date(),
+            event.to_text().unwrap()
+        );
+        assert_eq!(event, parse_line(&line).unwrap().event);
+    }
+
+    #[test]
+    fn test_invalid_lines() {
+        let invalid_lines = [
+            "not a log line",
+            "SGX|2022::11::20|BUY-TOKEN:abc-OK",
+            "SGX|2022::11::20::6::30::20::1423|UNKNOWN-CODE",
+            "SGX|2022::11::20::6::30::20::1423|BUY-TOKEN:abc",
+            "SGX|2022::11::20::6::30::20::1423|LOCK_BUY-t-KIND_TO_BUY:GOLD-QUANTITY_TO_BUY:1-BID:1-ERROR",
+        ];
+        for line in invalid_lines {
+            assert!(
+                matches!(parse_line(line), Err(LogParseError::InvalidLine { .. })),
+                "Line '{}' must be invalid",
+                line
+            );
+        }
+
+        let incomplete_init =
+            "SGX|2022::11::20::6::30::20::1423|\nMARKET_INITIALIZATION\nEUR+1e4\n";
+        assert_eq!(
+            Err(LogParseError::IncompleteMarketInitialization { line_number: 1 }),
+            parse_log(incomplete_init.as_bytes())
+        );
+    }
+}
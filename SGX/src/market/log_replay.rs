@@ -0,0 +1,255 @@
+//! Replays the parsed entries of a log (see `log_parser`) to rebuild the timeline of the
+//! quantities of a market.
+//!
+//! Locks do not change any quantity. A successful `buy` moves the locked quantity from the
+//! market to the trader and the bid from the trader to the market, a successful `sell` does
+//! the opposite with the offer. Next to the market quantities, the replayer sums up the net
+//! changes of each trader, which can be compared with the history of a `Trader`.
+use chrono::NaiveDateTime;
+use std::collections::HashMap;
+use unitn_market_2022::good::good_kind::GoodKind;
+
+use crate::market::log::LogEvent;
+use crate::market::log_parser::LogEntry;
+
+/// The quantities of all goods
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Quantities {
+    pub eur: f32,
+    pub usd: f32,
+    pub yen: f32,
+    pub yuan: f32,
+}
+
+impl Quantities {
+    /// Returns the quantity of the given kind
+    pub fn get(&self, kind: GoodKind) -> f32 {
+        match kind {
+            GoodKind::EUR => self.eur,
+            GoodKind::USD => self.usd,
+            GoodKind::YEN => self.yen,
+            GoodKind::YUAN => self.yuan,
+        }
+    }
+
+    /// Adds the given (possibly negative) quantity to the given kind
+    fn add(&mut self, kind: GoodKind, quantity: f32) {
+        match kind {
+            GoodKind::EUR => self.eur += quantity,
+            GoodKind::USD => self.usd += quantity,
+            GoodKind::YEN => self.yen += quantity,
+            GoodKind::YUAN => self.yuan += quantity,
+        }
+    }
+}
+
+/// The quantities of the market after an entry changed them
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimelineStep {
+    /// Time of the entry
+    pub time: NaiveDateTime,
+    /// Index of the entry in the replayed entries
+    pub entry_index: usize,
+    /// Quantities of the market after the entry
+    pub quantities: Quantities,
+}
+
+/// An open lock, that is known from a lock entry
+#[derive(Debug, Clone)]
+struct ReplayedLock {
+    trader_name: String,
+    kind: GoodKind,
+    quantity: f32,
+    /// The bid (buy) or the offer (sell)
+    eur_quantity: f32,
+    is_buy: bool,
+}
+
+/// The result of a replayed log
+#[derive(Debug, Default)]
+pub struct Replay {
+    /// Every change of the market quantities, starting with the initialization
+    pub timeline: Vec<TimelineStep>,
+    /// Net changes of the quantities of each trader
+    pub trader_changes: HashMap<String, Quantities>,
+    /// Tokens of successful buys or sells, that don't belong to any known lock
+    pub unknown_tokens: Vec<String>,
+}
+
+impl Replay {
+    /// Returns the quantities of the market after the last entry
+    pub fn get_final_quantities(&self) -> Option<Quantities> {
+        self.timeline.last().map(|step| step.quantities)
+    }
+
+    /// Returns the net changes of the quantities of the given trader
+    pub fn get_trader_changes(&self, trader_name: &str) -> Quantities {
+        self.trader_changes
+            .get(trader_name)
+            .copied()
+            .unwrap_or_default()
+    }
+}
+
+/// Replays the given entries. Entries before the first market initialization start with
+/// zero quantities, a later initialization resets the quantities.
+pub fn replay(entries: &[LogEntry]) -> Replay {
+    let mut replay = Replay::default();
+    let mut quantities = Quantities::default();
+    let mut locks: HashMap<String, ReplayedLock> = HashMap::new();
+
+    for (entry_index, entry) in entries.iter().enumerate() {
+        let has_changed = match &entry.event {
+            LogEvent::MarketInit {
+                eur,
+                usd,
+                yen,
+                yuan,
+            } => {
+                quantities = Quantities {
+                    eur: *eur,
+                    usd: *usd,
+                    yen: *yen,
+                    yuan: *yuan,
+                };
+                locks.clear();
+                true
+            }
+            LogEvent::LockBuy {
+                trader_name,
+                kind_to_buy,
+                quantity_to_buy,
+                bid,
+                token,
+            } => {
+                let lock = ReplayedLock {
+                    trader_name: trader_name.clone(),
+                    kind: *kind_to_buy,
+                    quantity: *quantity_to_buy,
+                    eur_quantity: *bid,
+                    is_buy: true,
+                };
+                locks.insert(token.clone(), lock);
+                false
+            }
+            LogEvent::LockSell {
+                trader_name,
+                kind_to_sell,
+                quantity_to_sell,
+                offer,
+                token,
+            } => {
+                let lock = ReplayedLock {
+                    trader_name: trader_name.clone(),
+                    kind: *kind_to_sell,
+                    quantity: *quantity_to_sell,
+                    eur_quantity: *offer,
+                    is_buy: false,
+                };
+                locks.insert(token.clone(), lock);
+                false
+            }
+            LogEvent::Buy { token } | LogEvent::Sell { token } => match locks.remove(token) {
+                Some(lock) => {
+                    // the trader buys the locked good for the bid, or sells it for the offer
+                    let direction = if lock.is_buy { 1.0 } else { -1.0 };
+                    quantities.add(lock.kind, -direction * lock.quantity);
+                    quantities.add(GoodKind::EUR, direction * lock.eur_quantity);
+
+                    let trader = replay.trader_changes.entry(lock.trader_name).or_default();
+                    trader.add(lock.kind, direction * lock.quantity);
+                    trader.add(GoodKind::EUR, -direction * lock.eur_quantity);
+                    true
+                }
+                None => {
+                    replay.unknown_tokens.push(token.clone());
+                    false
+                }
+            },
+            // a failed buy or sell, or a cancel closes the lock
+            LogEvent::BuyError { token, .. }
+            | LogEvent::SellError { token, .. }
+            | LogEvent::CancelLock { token } => {
+                locks.remove(token);
+                false
+            }
+            LogEvent::LockBuyError { .. }
+            | LogEvent::LockSellError { .. }
+            | LogEvent::CancelLockError { .. }
+            | LogEvent::Wait { .. } => false,
+        };
+
+        if has_changed {
+            replay.timeline.push(TimelineStep {
+                time: entry.time,
+                entry_index,
+                quantities,
+            });
+        }
+    }
+
+    replay
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::market::log_parser::parse_log;
+    use crate::market::log_replay::{replay, Quantities};
+
+    #[test]
+    fn test_replay() {
+        let log = "SGX|2022::11::20::6::30::20::0|\n\
+                   MARKET_INITIALIZATION\n\
+                   EUR+1e3\n\
+                   USD:+1e3\n\
+                   YEN:+1e3\n\
+                   YUAN:+1e3\n\
+                   END_MARKET_INITIALIZATION\n\
+                   SGX|2022::11::20::6::30::21::0|LOCK_BUY-TRADER-KIND_TO_BUY:USD-QUANTITY_TO_BUY:100-BID:110-TOKEN:a\n\
+                   SGX|2022::11::20::6::30::22::0|LOCK-SELL-TRADER-KIND_TO_SELL:YEN-QUANTITY_TO_SELL:50-OFFER:20-TOKEN:b\n\
+                   SGX|2022::11::20::6::30::23::0|BUY-TOKEN:a-OK\n\
+                   SGX|2022::11::20::6::30::24::0|SELL-TOKEN:b-OK\n\
+                   SGX|2022::11::20::6::30::25::0|SELL-TOKEN:b-OK\n";
+        let entries = parse_log(log.as_bytes()).unwrap();
+        let replay = replay(&entries);
+
+        assert_eq!(
+            3,
+            replay.timeline.len(),
+            "Init, buy and sell change quantities"
+        );
+        assert_eq!(3, replay.timeline[1].entry_index);
+        assert_eq!(
+            Quantities {
+                eur: 1_110.0,
+                usd: 900.0,
+                yen: 1_000.0,
+                yuan: 1_000.0
+            },
+            replay.timeline[1].quantities
+        );
+        assert_eq!(
+            Some(Quantities {
+                eur: 1_090.0,
+                usd: 900.0,
+                yen: 1_050.0,
+                yuan: 1_000.0
+            }),
+            replay.get_final_quantities()
+        );
+        assert_eq!(
+            Quantities {
+                eur: -90.0,
+                usd: 100.0,
+                yen: -50.0,
+                yuan: 0.0
+            },
+            replay.get_trader_changes("TRADER")
+        );
+        assert_eq!(
+            vec!["b".to_string()],
+            replay.unknown_tokens,
+            "A token can only be used once"
+        );
+    }
+}
@@ -1,3 +1,14 @@
+pub mod config;
+pub mod consensus;
 mod consts;
+pub mod errors;
+pub mod lock_listener;
 pub mod log;
+pub mod log_parser;
+pub mod log_replay;
+pub mod order_book;
+pub mod pricing;
 pub mod sgx;
+pub mod snapshot;
+pub mod stats;
+pub mod trader_market;
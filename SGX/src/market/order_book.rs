@@ -0,0 +1,201 @@
+//! Limit orders of the market. Next to the quote-then-lock trading of the market common, a
+//! trader can post a resting order for a good at a price per unit. The market fills the order
+//! as soon as its own price reaches the limit (see `SGX::place_order`).
+//!
+//! The market can't take anything from a trader later on, therefore the trader has to hand
+//! over an escrow when posting the order: the EUR of a bid, or the good of an ask. An order is
+//! always filled at its limit price.
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use unitn_market_2022::good::good_kind::GoodKind;
+
+/// The side of a limit order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderSide {
+    /// The trader wants to buy the good from the market
+    Bid,
+    /// The trader wants to sell the good to the market
+    Ask,
+}
+
+/// The status of a limit order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    /// The order waits for the market price to reach its limit
+    Open,
+    /// The order was filled, its proceeds can be claimed
+    Filled,
+}
+
+/**
+ * A `LimitOrder` is a resting bid or ask of a trader.
+ * The escrow is the EUR quantity (bid) or the good quantity (ask), the trader handed over.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LimitOrder {
+    /// Id of the order, unique within the market
+    pub id: u64,
+    /// Name of the trader that posted the order
+    pub trader_name: String,
+    /// Bid or ask
+    pub side: OrderSide,
+    /// Kind of the good to buy or to sell
+    #[serde(with = "crate::goods::serde_good_kind")]
+    pub kind: GoodKind,
+    /// Quantity of the good to buy or to sell
+    pub quantity: f32,
+    /// Highest (bid) or lowest (ask) acceptable price per unit, in EUR
+    pub price_per_unit: f32,
+    /// Open or filled
+    pub status: OrderStatus,
+}
+
+impl LimitOrder {
+    /// Returns the price of the whole quantity at the limit, in EUR
+    pub fn get_limit_price(&self) -> f32 {
+        self.quantity * self.price_per_unit
+    }
+
+    /// Returns the kind and the quantity of the escrow of the order
+    pub fn get_escrow(&self) -> (GoodKind, f32) {
+        match self.side {
+            OrderSide::Bid => (GoodKind::EUR, self.get_limit_price()),
+            OrderSide::Ask => (self.kind, self.quantity),
+        }
+    }
+
+    /// Returns the kind and the quantity the trader receives, after the order was filled
+    pub fn get_proceeds(&self) -> (GoodKind, f32) {
+        match self.side {
+            OrderSide::Bid => (self.kind, self.quantity),
+            OrderSide::Ask => (GoodKind::EUR, self.get_limit_price()),
+        }
+    }
+
+    /// Checks if the order is owned by the given trader
+    pub fn is_owned_by(&self, trader_name: &str) -> bool {
+        self.trader_name == trader_name
+    }
+}
+
+/// All orders of a market that were neither cancelled nor claimed yet, by id
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct OrderBook {
+    orders: BTreeMap<u64, LimitOrder>,
+    next_order_id: u64,
+}
+
+impl OrderBook {
+    /// Adds a new open order and returns its id
+    pub fn add_order(
+        &mut self,
+        trader_name: String,
+        side: OrderSide,
+        kind: GoodKind,
+        quantity: f32,
+        price_per_unit: f32,
+    ) -> u64 {
+        let id = self.next_order_id;
+        self.next_order_id += 1;
+        let order = LimitOrder {
+            id,
+            trader_name,
+            side,
+            kind,
+            quantity,
+            price_per_unit,
+            status: OrderStatus::Open,
+        };
+        self.orders.insert(id, order);
+        id
+    }
+
+    /// Returns the order with the given id
+    pub fn get_order(&self, id: u64) -> Option<&LimitOrder> {
+        self.orders.get(&id)
+    }
+
+    /// Returns all orders of the given trader
+    pub fn get_orders_of(&self, trader_name: &str) -> Vec<&LimitOrder> {
+        self.orders
+            .values()
+            .filter(|o| o.is_owned_by(trader_name))
+            .collect()
+    }
+
+    /// Returns the ids of all open orders, oldest first
+    pub fn get_open_order_ids(&self) -> Vec<u64> {
+        self.orders
+            .values()
+            .filter(|o| o.status == OrderStatus::Open)
+            .map(|o| o.id)
+            .collect()
+    }
+
+    /// Marks the order with the given id as filled
+    pub fn fill_order(&mut self, id: u64) {
+        if let Some(order) = self.orders.get_mut(&id) {
+            order.status = OrderStatus::Filled;
+        }
+    }
+
+    /// Removes the order with the given id
+    pub fn remove_order(&mut self, id: u64) -> Option<LimitOrder> {
+        self.orders.remove(&id)
+    }
+
+    /// Returns the number of orders
+    pub fn len(&self) -> usize {
+        self.orders.len()
+    }
+
+    /// Checks if there are no orders
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::market::order_book::{OrderBook, OrderSide, OrderStatus};
+    use unitn_market_2022::good::good_kind::GoodKind;
+
+    #[test]
+    fn test_order_book() {
+        let mut book = OrderBook::default();
+        let bid = book.add_order(
+            "TRADER".to_string(),
+            OrderSide::Bid,
+            GoodKind::USD,
+            10.0,
+            2.0,
+        );
+        let ask = book.add_order("OTHER".to_string(), OrderSide::Ask, GoodKind::YEN, 5.0, 0.5);
+        assert_ne!(bid, ask, "Ids must be unique");
+
+        let order = book.get_order(bid).unwrap();
+        assert_eq!((GoodKind::EUR, 20.0), order.get_escrow());
+        assert_eq!((GoodKind::USD, 10.0), order.get_proceeds());
+        let order = book.get_order(ask).unwrap();
+        assert_eq!((GoodKind::YEN, 5.0), order.get_escrow());
+        assert_eq!((GoodKind::EUR, 2.5), order.get_proceeds());
+
+        book.fill_order(bid);
+        assert_eq!(OrderStatus::Filled, book.get_order(bid).unwrap().status);
+        assert_eq!(vec![ask], book.get_open_order_ids());
+        assert_eq!(1, book.get_orders_of("TRADER").len());
+
+        assert!(book.remove_order(bid).is_some());
+        assert!(book.get_order(bid).is_none());
+        let next = book.add_order(
+            "TRADER".to_string(),
+            OrderSide::Bid,
+            GoodKind::USD,
+            1.0,
+            1.0,
+        );
+        assert!(next > ask, "Ids of removed orders must not be reused");
+    }
+}
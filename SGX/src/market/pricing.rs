@@ -0,0 +1,503 @@
+//! Pricing models of the market. A `PricingModel` calculates the buy and sell prices of a good
+//! and decides how the base prices fluctuate after trades, external events and days.
+//!
+//! All functions only get plain values, therefore a model does not need to know anything about
+//! the goods or locks of the market. Prices are always in EUR. A `QuoteBreakdown` splits a
+//! price into its base cost, margin and slippage.
+//!
+//! - `DemandPricing` is the default model of the SGX (see README)
+//! - `ConstantProductPricing` is a constant-product curve, as known from AMMs
+//! - `LinearSpreadPricing` adds a constant spread and a linear price impact to the base price
+use serde::{Deserialize, Serialize};
+
+/// Factors the base prices are multiplied with, when they fluctuate
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FluctuationFactors {
+    pub buy: f32,
+    pub sell: f32,
+}
+
+impl Default for FluctuationFactors {
+    /// The prices don't change
+    fn default() -> Self {
+        Self {
+            buy: 1.0,
+            sell: 1.0,
+        }
+    }
+}
+
+/**
+ * The components of a quote, in EUR. The base cost is the quantity at the base price, the margin
+ * is what the market adds (or removes) on top, and the slippage is the price impact of the
+ * quantity. Margin and slippage are signed, their sum with the base cost is the price.
+ */
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct QuoteBreakdown {
+    /// Quantity of the good
+    pub quantity: f32,
+    /// Price of the quantity at the base price
+    pub base_cost: f32,
+    /// Margin or fee of the market
+    pub margin: f32,
+    /// Price impact of the quantity (e.g. of the demand factor)
+    pub slippage: f32,
+    /// Final price of the quantity
+    pub price: f32,
+}
+
+impl QuoteBreakdown {
+    /// Constructs a quote of the given price. The slippage is everything, that is neither base
+    /// cost nor margin.
+    pub fn new(quantity: f32, base_cost: f32, margin: f32, price: f32) -> Self {
+        Self {
+            quantity,
+            base_cost,
+            margin,
+            slippage: price - base_cost - margin,
+            price,
+        }
+    }
+
+    /// Returns the price per unit, comparable with the exchange rates of a `GoodLabel`
+    pub fn get_unit_price(&self) -> f32 {
+        self.price / self.quantity
+    }
+}
+
+pub trait PricingModel {
+    /// Returns the price a trader has to pay, to buy the given quantity from the market.
+    /// The market has at least the given quantity available.
+    fn get_buy_price(&self, quantity: f32, available_quantity: f32, base_buy_price: f32) -> f32;
+
+    /// Returns the price the market pays, when a trader sells the given quantity to the market
+    fn get_sell_price(&self, quantity: f32, available_quantity: f32, base_sell_price: f32) -> f32;
+
+    /// Returns the components of the buy price. By default, everything above the base cost is
+    /// slippage.
+    fn get_buy_quote(
+        &self,
+        quantity: f32,
+        available_quantity: f32,
+        base_buy_price: f32,
+    ) -> QuoteBreakdown {
+        let price = self.get_buy_price(quantity, available_quantity, base_buy_price);
+        QuoteBreakdown::new(quantity, quantity * base_buy_price, 0.0, price)
+    }
+
+    /// Returns the components of the sell price. By default, everything apart from the base
+    /// cost is slippage.
+    fn get_sell_quote(
+        &self,
+        quantity: f32,
+        available_quantity: f32,
+        base_sell_price: f32,
+    ) -> QuoteBreakdown {
+        let price = self.get_sell_price(quantity, available_quantity, base_sell_price);
+        QuoteBreakdown::new(quantity, quantity * base_sell_price, 0.0, price)
+    }
+
+    /// Returns the factor the base price is multiplied with, after the quantity of the good has
+    /// changed from the old quantity to the new quantity because of a trade with this market
+    fn get_trade_fluctuation_factor(&self, _old_quantity: f32, _new_quantity: f32) -> f32 {
+        1.0
+    }
+
+    /// Returns the factors, after another market has sold (or locked to sell) a good to a
+    /// trader for the given price. Our price is the price we would ask for the same quantity.
+    fn on_external_buy(&self, _our_price: f32, _their_price: f32) -> FluctuationFactors {
+        FluctuationFactors::default()
+    }
+
+    /// Returns the factors, after a trader has sold (or locked to sell) a good to another
+    /// market for the given price. Our price is the price we would pay for the same quantity.
+    fn on_external_sell(&self, _our_price: f32, _their_price: f32) -> FluctuationFactors {
+        FluctuationFactors::default()
+    }
+
+    /// Returns the factors, after a day has passed. They are not applied to the default good.
+    fn on_wait(&self) -> FluctuationFactors {
+        FluctuationFactors::default()
+    }
+}
+
+/**
+ * Prices are based on demand. The price of the quantity is multiplied with a demand factor,
+ * that is based on the available quantity before and after the trade, and a margin is added.
+ * Base prices fluctuate after every trade, external event and day.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DemandPricing {
+    /// Margin that is added to the buy price (e.g. 0.05 for 5%)
+    pub buy_margin: f32,
+    /// Margin that is added to the sell price (e.g. 0.15 for 15%)
+    pub sell_margin: f32,
+    /// Factor the price of the other side is increased with, after an external event
+    pub external_event_factor: f32,
+    /// Factor the buy price is multiplied with, after a day has passed
+    pub wait_buy_factor: f32,
+    /// Factor the sell price is multiplied with, after a day has passed
+    pub wait_sell_factor: f32,
+}
+
+impl Default for DemandPricing {
+    fn default() -> Self {
+        Self {
+            buy_margin: 0.05,
+            sell_margin: 0.15,
+            external_event_factor: 1.05,
+            wait_buy_factor: 0.9, // buy price always cheaper
+            wait_sell_factor: 0.95,
+        }
+    }
+}
+
+impl PricingModel for DemandPricing {
+    fn get_buy_price(&self, quantity: f32, available_quantity: f32, base_buy_price: f32) -> f32 {
+        // Get buy price for quantity
+        let exchange_price = quantity * base_buy_price;
+        // Calculate a factor based on demand
+        let new_quantity = available_quantity - quantity;
+        let demand_factor = available_quantity / new_quantity;
+        // Calculate a margin (buy price must always be cheaper than sell price)
+        let margin = exchange_price * self.buy_margin;
+
+        (exchange_price + margin) * demand_factor
+    }
+
+    fn get_sell_price(&self, quantity: f32, available_quantity: f32, base_sell_price: f32) -> f32 {
+        // Get sell price for quantity
+        let exchange_price = quantity * base_sell_price;
+        // Calculate a factor based on demand
+        let new_quantity = available_quantity + quantity;
+        let demand_factor = available_quantity / new_quantity;
+        // Calculate a margin (sell price must always be higher than buy price)
+        let margin = exchange_price * self.sell_margin;
+
+        (exchange_price + margin) * demand_factor
+    }
+
+    fn get_buy_quote(
+        &self,
+        quantity: f32,
+        available_quantity: f32,
+        base_buy_price: f32,
+    ) -> QuoteBreakdown {
+        let base_cost = quantity * base_buy_price;
+        let price = self.get_buy_price(quantity, available_quantity, base_buy_price);
+        QuoteBreakdown::new(quantity, base_cost, base_cost * self.buy_margin, price)
+    }
+
+    fn get_sell_quote(
+        &self,
+        quantity: f32,
+        available_quantity: f32,
+        base_sell_price: f32,
+    ) -> QuoteBreakdown {
+        let base_cost = quantity * base_sell_price;
+        let price = self.get_sell_price(quantity, available_quantity, base_sell_price);
+        QuoteBreakdown::new(quantity, base_cost, base_cost * self.sell_margin, price)
+    }
+
+    fn get_trade_fluctuation_factor(&self, old_quantity: f32, new_quantity: f32) -> f32 {
+        old_quantity / new_quantity
+    }
+
+    fn on_external_buy(&self, our_price: f32, their_price: f32) -> FluctuationFactors {
+        // only decrease buy if our price is already higher
+        let buy = if our_price > their_price {
+            their_price / our_price
+        } else {
+            1.0
+        };
+        FluctuationFactors {
+            buy,
+            sell: self.external_event_factor,
+        }
+    }
+
+    fn on_external_sell(&self, our_price: f32, their_price: f32) -> FluctuationFactors {
+        // a good was sold => demand to sell increases => lower our prices
+        let sell = if our_price > their_price {
+            their_price / our_price
+        } else {
+            1.0
+        };
+        FluctuationFactors {
+            buy: self.external_event_factor,
+            sell,
+        }
+    }
+
+    fn on_wait(&self) -> FluctuationFactors {
+        FluctuationFactors {
+            buy: self.wait_buy_factor,
+            sell: self.wait_sell_factor,
+        }
+    }
+}
+
+/**
+ * A constant-product curve (`x * y = k`). The good reserve `x` is the available quantity and
+ * the EUR reserve `y` is implied by the base price (`y = x * base_price`). A fee is added to
+ * the buy price and removed from the sell price.
+ * The price already moves with the available quantity, therefore base prices never fluctuate.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConstantProductPricing {
+    /// Fee of a trade (e.g. 0.003 for 0.3%)
+    pub fee: f32,
+}
+
+impl Default for ConstantProductPricing {
+    fn default() -> Self {
+        Self { fee: 0.003 }
+    }
+}
+
+impl ConstantProductPricing {
+    /// Returns the buy price of the curve, without the fee
+    fn get_curve_buy_price(quantity: f32, available_quantity: f32, base_buy_price: f32) -> f32 {
+        let eur_reserve = available_quantity * base_buy_price;
+        // the EUR reserve has to grow, so that the product stays the same
+        eur_reserve * quantity / (available_quantity - quantity)
+    }
+
+    /// Returns the sell price of the curve, without the fee
+    fn get_curve_sell_price(quantity: f32, available_quantity: f32, base_sell_price: f32) -> f32 {
+        let eur_reserve = available_quantity * base_sell_price;
+        // the EUR reserve has to shrink, so that the product stays the same
+        eur_reserve * quantity / (available_quantity + quantity)
+    }
+}
+
+impl PricingModel for ConstantProductPricing {
+    fn get_buy_price(&self, quantity: f32, available_quantity: f32, base_buy_price: f32) -> f32 {
+        let price = Self::get_curve_buy_price(quantity, available_quantity, base_buy_price);
+        price * (1.0 + self.fee)
+    }
+
+    fn get_sell_price(&self, quantity: f32, available_quantity: f32, base_sell_price: f32) -> f32 {
+        let price = Self::get_curve_sell_price(quantity, available_quantity, base_sell_price);
+        price * (1.0 - self.fee)
+    }
+
+    fn get_buy_quote(
+        &self,
+        quantity: f32,
+        available_quantity: f32,
+        base_buy_price: f32,
+    ) -> QuoteBreakdown {
+        let curve_price = Self::get_curve_buy_price(quantity, available_quantity, base_buy_price);
+        let price = self.get_buy_price(quantity, available_quantity, base_buy_price);
+        let base_cost = quantity * base_buy_price;
+        QuoteBreakdown::new(quantity, base_cost, curve_price * self.fee, price)
+    }
+
+    fn get_sell_quote(
+        &self,
+        quantity: f32,
+        available_quantity: f32,
+        base_sell_price: f32,
+    ) -> QuoteBreakdown {
+        let curve_price = Self::get_curve_sell_price(quantity, available_quantity, base_sell_price);
+        let price = self.get_sell_price(quantity, available_quantity, base_sell_price);
+        let base_cost = quantity * base_sell_price;
+        QuoteBreakdown::new(quantity, base_cost, -curve_price * self.fee, price)
+    }
+}
+
+/**
+ * The price is the base price plus (buy) or minus (sell) half of a constant spread. Additionally,
+ * the price changes linear with the share of the available quantity that is traded.
+ * Base prices never fluctuate.
+ */
+#[derive(Debug, Clone, PartialEq)]
+pub struct LinearSpreadPricing {
+    /// Spread between buy and sell price (e.g. 0.1 for 10%)
+    pub spread: f32,
+    /// Price impact, when the whole available quantity is traded (e.g. 0.5 for 50%)
+    pub impact: f32,
+}
+
+impl Default for LinearSpreadPricing {
+    fn default() -> Self {
+        Self {
+            spread: 0.1,
+            impact: 0.5,
+        }
+    }
+}
+
+impl PricingModel for LinearSpreadPricing {
+    fn get_buy_price(&self, quantity: f32, available_quantity: f32, base_buy_price: f32) -> f32 {
+        let impact = self.impact * quantity / available_quantity;
+        quantity * base_buy_price * (1.0 + self.spread / 2.0 + impact)
+    }
+
+    fn get_sell_price(&self, quantity: f32, available_quantity: f32, base_sell_price: f32) -> f32 {
+        let impact = self.impact * quantity / (available_quantity + quantity);
+        let factor = 1.0 - self.spread / 2.0 - impact;
+        quantity * base_sell_price * factor.max(0.0)
+    }
+
+    fn get_buy_quote(
+        &self,
+        quantity: f32,
+        available_quantity: f32,
+        base_buy_price: f32,
+    ) -> QuoteBreakdown {
+        let base_cost = quantity * base_buy_price;
+        let price = self.get_buy_price(quantity, available_quantity, base_buy_price);
+        QuoteBreakdown::new(quantity, base_cost, base_cost * self.spread / 2.0, price)
+    }
+
+    fn get_sell_quote(
+        &self,
+        quantity: f32,
+        available_quantity: f32,
+        base_sell_price: f32,
+    ) -> QuoteBreakdown {
+        let base_cost = quantity * base_sell_price;
+        let price = self.get_sell_price(quantity, available_quantity, base_sell_price);
+        QuoteBreakdown::new(quantity, base_cost, -base_cost * self.spread / 2.0, price)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::market::pricing::{
+        ConstantProductPricing, DemandPricing, FluctuationFactors, LinearSpreadPricing,
+        PricingModel, QuoteBreakdown,
+    };
+
+    /// Checks if both prices are equal, apart from rounding errors
+    fn assert_price_eq(expected: f32, actual: f32) {
+        assert!(
+            (expected - actual).abs() < 1e-4,
+            "Price is {}, but expected {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn test_demand_pricing() {
+        let model = DemandPricing::default();
+        // (10 * 2 + 5%) * (100 / 90)
+        let expected_buy_price = 21.0 * 100.0 / 90.0;
+        assert_price_eq(expected_buy_price, model.get_buy_price(10.0, 100.0, 2.0));
+        // (10 * 2 + 15%) * (100 / 110)
+        let expected_sell_price = 23.0 * 100.0 / 110.0;
+        assert_price_eq(expected_sell_price, model.get_sell_price(10.0, 100.0, 2.0));
+
+        assert_eq!(
+            FluctuationFactors {
+                buy: 0.5,
+                sell: 1.05
+            },
+            model.on_external_buy(20.0, 10.0)
+        );
+        assert_eq!(
+            FluctuationFactors {
+                buy: 1.05,
+                sell: 1.0
+            },
+            model.on_external_sell(10.0, 20.0)
+        );
+        assert_eq!(
+            FluctuationFactors {
+                buy: 0.9,
+                sell: 0.95
+            },
+            model.on_wait()
+        );
+    }
+
+    #[test]
+    fn test_constant_product_pricing() {
+        let model = ConstantProductPricing { fee: 0.0 };
+        // x = 100, y = 200 => k = 20000; buying 20 => y = 20000 / 80 = 250
+        assert_eq!(50.0, model.get_buy_price(20.0, 100.0, 2.0));
+        // selling 25 => y = 20000 / 125 = 160
+        assert_eq!(40.0, model.get_sell_price(25.0, 100.0, 2.0));
+        assert_eq!(FluctuationFactors::default(), model.on_wait());
+    }
+
+    #[test]
+    fn test_linear_spread_pricing() {
+        let model = LinearSpreadPricing {
+            spread: 0.2,
+            impact: 0.5,
+        };
+        // 10 * 2 * (1 + 0.1 + 0.05)
+        assert_price_eq(23.0, model.get_buy_price(10.0, 100.0, 2.0));
+        let buy_price = model.get_buy_price(1.0, 100.0, 2.0);
+        let sell_price = model.get_sell_price(1.0, 100.0, 2.0);
+        assert!(
+            sell_price < buy_price,
+            "Sell price {} must be lower than buy price {}",
+            sell_price,
+            buy_price
+        );
+        // the impact approaches 0.5 for large quantities, so the price stays just above
+        // 1_000_000 * 2 * (1 - 0.1 - 0.5)
+        let sell_price = model.get_sell_price(1_000_000.0, 1.0, 2.0);
+        assert!(
+            (sell_price - 800_001.0).abs() < 1.0,
+            "Sell price {} must be close to the lower bound of 800000",
+            sell_price
+        );
+
+        // spread and impact can be larger than the price
+        let model = LinearSpreadPricing {
+            spread: 0.5,
+            impact: 2.0,
+        };
+        assert_eq!(
+            0.0,
+            model.get_sell_price(1_000_000.0, 1.0, 2.0),
+            "Sell price can't be negative"
+        );
+    }
+
+    /// Checks that the components of the quote sum up to its price
+    fn assert_quote_sums_up(quote: QuoteBreakdown) {
+        assert_price_eq(quote.price, quote.base_cost + quote.margin + quote.slippage);
+    }
+
+    #[test]
+    fn test_quote_breakdown() {
+        let model = DemandPricing::default();
+        let quote = model.get_buy_quote(10.0, 100.0, 2.0);
+        assert_eq!(model.get_buy_price(10.0, 100.0, 2.0), quote.price);
+        assert_eq!(20.0, quote.base_cost);
+        assert_price_eq(1.0, quote.margin);
+        // 21 * (100 / 90 - 1)
+        assert_price_eq(21.0 / 9.0, quote.slippage);
+        assert_price_eq(quote.price / 10.0, quote.get_unit_price());
+        let quote = model.get_sell_quote(10.0, 100.0, 2.0);
+        assert!(
+            quote.slippage < 0.0,
+            "Selling must have a negative slippage"
+        );
+        assert_quote_sums_up(quote);
+
+        let models: Vec<Box<dyn PricingModel>> = vec![
+            Box::new(ConstantProductPricing::default()),
+            Box::new(LinearSpreadPricing::default()),
+        ];
+        for model in models {
+            assert_quote_sums_up(model.get_buy_quote(10.0, 100.0, 2.0));
+            assert_quote_sums_up(model.get_sell_quote(10.0, 100.0, 2.0));
+        }
+
+        let model = ConstantProductPricing { fee: 0.0 };
+        let quote = model.get_buy_quote(20.0, 100.0, 2.0);
+        assert_eq!(0.0, quote.margin);
+        assert_eq!(
+            10.0, quote.slippage,
+            "Curve price is 50 for a base cost of 40"
+        );
+    }
+}
@@ -1,4 +1,7 @@
 use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::fmt::Debug;
+use std::io;
 use std::rc::Rc;
 
 use unitn_market_2022::event::event::{Event, EventKind};
@@ -12,23 +15,117 @@ use unitn_market_2022::market::{
 };
 
 use crate::goods::good_storage::GoodStorage;
+use crate::goods::token_generator::TokenGenerator;
+use crate::market::config::{SGXBuilder, SGXConfig};
+use crate::market::consensus::{
+    ConsensusConfig, ConsensusDeviation, ConsensusPrices, GoodConsensus,
+};
 use crate::market::consts::NAME;
-use crate::market::log::{
-    log_for_buy, log_for_buy_err, log_for_lock_buy, log_for_lock_buy_err, log_for_lock_sell,
-    log_for_lock_sell_err, log_for_market_init, log_for_sell, log_for_sell_err,
+use crate::market::errors::{
+    CancelLockError, CheckedLockError, LockLimitError, OrderError, OwnedTokenError,
 };
+use crate::market::lock_listener::{LockListener, LockSide};
+use crate::market::log::{LogEvent, Logger};
+use crate::market::order_book::{LimitOrder, OrderBook, OrderSide, OrderStatus};
+use crate::market::pricing::{PricingModel, QuoteBreakdown};
+use crate::market::snapshot::SGXSnapshot;
+use crate::market::stats::MarketStats;
+
+/// Checks if the given quantity or price can't be traded, because it is not positive or not
+/// finite (e.g. NaN)
+fn is_invalid_amount(amount: f32) -> bool {
+    !amount.is_finite() || amount <= 0.0
+}
+
+/// Describes a rejected lock for the log. A reached lock limit is logged as
+/// `MaxAllowedLocksReached`, followed by its reason.
+fn describe_lock_error<E: Debug>(error: &CheckedLockError<E>) -> String {
+    match error {
+        CheckedLockError::LockLimit(e) => format!("MaxAllowedLocksReached ({})", e),
+        CheckedLockError::Market(e) => format!("{:?}", e),
+    }
+}
 
 pub struct SGX {
     good_storage: GoodStorage,
     subscribers: Vec<Box<dyn Notifiable>>,
+    /// Path of the file the market state is written to, when the market is dropped
+    file_path: Option<String>,
+    /// Logger that writes all events of the market
+    logger: Logger,
+    /// Number of days since the market initialization
+    day: u32,
+    /// Model that calculates the prices and their fluctuation
+    pricing_model: Box<dyn PricingModel>,
+    /// Parameters of the market
+    config: SGXConfig,
+    /// Time series of the prices, quantities and trades of every good
+    stats: MarketStats,
+    /// Limit orders of the traders, only used if enabled in the config
+    order_book: OrderBook,
+    /// Listeners that are notified about cancelled and expired locks
+    lock_listeners: Vec<Box<dyn LockListener>>,
+    /// Prices of other markets, estimated from their events
+    consensus: ConsensusPrices,
+    /// Events of orders that were filled during `on_event`. They are sent to the subscribers
+    /// with the next event of this market, since the market that called `on_event` is still
+    /// borrowed at that moment.
+    pending_events: Vec<Event>,
+    /// Generates the tokens of the locks
+    token_generator: TokenGenerator,
 }
 
 impl SGX {
-    /// Notifies all subscribers of the given event
+    /// Constructs a new market with the given goods and config and logs its initialization
+    pub(crate) fn from_config(
+        good_storage: GoodStorage,
+        file_path: Option<String>,
+        mut logger: Logger,
+        config: SGXConfig,
+    ) -> Self {
+        let quantity_for_kind = |kind: GoodKind| {
+            good_storage
+                .get_good_for_kind(&kind)
+                .map(|(good, _)| good.get_qty())
+                .unwrap_or_default()
+        };
+        logger.log(LogEvent::MarketInit {
+            eur: quantity_for_kind(GoodKind::EUR),
+            usd: quantity_for_kind(GoodKind::USD),
+            yen: quantity_for_kind(GoodKind::YEN),
+            yuan: quantity_for_kind(GoodKind::YUAN),
+        });
+        let stats = MarketStats::new(&good_storage);
+        Self {
+            good_storage,
+            subscribers: Vec::new(),
+            file_path,
+            logger,
+            day: 0,
+            pricing_model: Box::new(config.pricing.clone()),
+            config,
+            stats,
+            order_book: OrderBook::default(),
+            lock_listeners: Vec::new(),
+            consensus: ConsensusPrices::default(),
+            pending_events: Vec::new(),
+            token_generator: TokenGenerator::default(),
+        }
+    }
+
+    /// Notifies all subscribers of the pending events and the given event
     fn notify(&mut self, event: Event) {
-        self.subscribers
-            .iter_mut()
-            .for_each(|s| s.as_mut().on_event(event.clone()));
+        self.pending_events.push(event);
+        self.notify_pending_events();
+    }
+
+    /// Notifies all subscribers of the pending events, the oldest first
+    fn notify_pending_events(&mut self) {
+        for event in std::mem::take(&mut self.pending_events) {
+            self.subscribers
+                .iter_mut()
+                .for_each(|s| s.as_mut().on_event(event.clone()));
+        }
     }
 
     /// Merges the default good with the given good
@@ -45,275 +142,274 @@ impl SGX {
         }
     }
 
-    /// Checks if another Good can be locked for buy
-    fn can_add_buy_lock(&self) -> bool {
-        self.good_storage.get_buy_locks_len() < self.good_storage.len() - 2
+    /// Takes a sample of the stats of every good
+    fn sample_stats(&mut self) {
+        self.stats.sample(self.day, &self.good_storage);
     }
 
-    /// Checks if another Good can be locked for sell
-    fn can_add_sell_lock(&self) -> bool {
-        self.good_storage.get_sell_locks_len() < self.good_storage.len() - 2
+    /// Generates the token of a new lock. Tokens that are still known to the market (e.g. of a
+    /// restored lock) are skipped.
+    fn gen_token(&mut self) -> String {
+        loop {
+            let token = self.token_generator.next_token();
+            let is_known = self.good_storage.get_lock_for_token(&token).is_some()
+                || self.good_storage.has_good_expired_buy_token(&token)
+                || self.good_storage.has_good_expired_sell_token(&token);
+            if !is_known {
+                return token;
+            }
+        }
     }
-}
 
-impl Notifiable for SGX {
-    fn add_subscriber(&mut self, subscriber: Box<dyn Notifiable>) {
-        self.subscribers.push(subscriber);
+    /// Lets the tokens of the locks be generated with the given seed, so that they can be
+    /// reproduced
+    pub(crate) fn set_token_seed(&mut self, seed: u64) {
+        self.token_generator = TokenGenerator::new(Some(seed));
     }
 
-    fn on_event(&mut self, event: Event) {
-        match event.kind {
-            EventKind::Bought | EventKind::LockedBuy => {
-                let our_price = match self.get_buy_price(event.good_kind, event.quantity) {
-                    Ok(our_price) => our_price,
-                    _ => {
-                        return; // not able get our price
-                    }
-                };
+    /// Returns the max. number of goods that a trader can lock at the same time
+    fn get_max_locked_goods(&self) -> usize {
+        self.config.get_max_locked_goods(self.good_storage.len())
+    }
 
-                let (_, bought_meta) =
-                    match self.good_storage.get_mut_good_for_kind(&event.good_kind) {
-                        Some((good, meta)) => (good, meta),
-                        _ => return, // no good found
-                    };
-                bought_meta.fluctuate_sell_price_with_factor(1.05);
-                // only decrease buy if our price is already higher
-                if our_price > event.price {
-                    let factor = event.price / our_price;
-                    bought_meta.fluctuate_buy_price_with_factor(factor);
-                }
-            }
-            EventKind::Sold | EventKind::LockedSell => {
-                // a good was sold => demand to sell increases => lower our prices
-                let our_price = match self.get_sell_price(event.good_kind, event.quantity) {
-                    Ok(our_price) => our_price,
-                    _ => {
-                        return; // not able get our price
+    /// Cancels the lock for the given token, see `SGX::cancel_lock`. Nothing is logged.
+    fn process_cancel_lock(&mut self, token: String) -> Result<(), CancelLockError> {
+        let revert_price = self.config.revert_price_on_cancel;
+        let cancelled =
+            if let Some((_, meta)) = self.good_storage.get_mut_good_for_buy_token(&token) {
+                meta.unlock_for_buy(&token).map(|lock| {
+                    if revert_price {
+                        meta.fluctuate_buy_price_with_factor(1.0 / lock.price_factor);
                     }
-                };
+                    (LockSide::Buy, lock)
+                })
+            } else if let Some((_, meta)) = self.good_storage.get_mut_good_for_sell_token(&token) {
+                meta.unlock_for_sell(&token).map(|lock| {
+                    if revert_price {
+                        meta.fluctuate_sell_price_with_factor(1.0 / lock.price_factor);
+                    }
+                    (LockSide::Sell, lock)
+                })
+            } else {
+                None
+            };
 
-                let (_, bought_meta) =
-                    match self.good_storage.get_mut_good_for_kind(&event.good_kind) {
-                        Some((good, meta)) => (good, meta),
-                        _ => return, // no good found
-                    };
-                bought_meta.fluctuate_buy_price_with_factor(1.05);
-                // only decrease buy if our price is already higher
-                if our_price > event.price {
-                    let factor = event.price / our_price;
-                    bought_meta.fluctuate_sell_price_with_factor(factor);
-                }
+        match cancelled {
+            Some((side, lock)) => {
+                self.lock_listeners
+                    .iter_mut()
+                    .for_each(|l| l.on_lock_cancelled(side, &lock));
+                Ok(())
             }
-            EventKind::Wait => {
-                // A day has passed, we may decrease our prices
-                let iter = self.good_storage.iter_mut();
-                for (good, meta) in iter {
-                    // fluctuate prices each day
-                    if good.get_kind() != DEFAULT_GOOD_KIND {
-                        meta.fluctuate_buy_price_with_factor(0.9); // buy price always cheaper
-                        meta.fluctuate_sell_price_with_factor(0.95);
-                    }
-                    // unlock for sell if needed
-                    if let Some(lock) = meta.get_mut_sell_lock() {
-                        if lock.age_in_days < 15 {
-                            lock.increase_age_by_one();
-                        } else {
-                            meta.unlock_for_sell();
-                        }
-                    }
-                    // unlock for buy if needed
-                    if let Some(lock) = meta.get_mut_buy_lock() {
-                        if lock.age_in_days < 15 {
-                            lock.increase_age_by_one();
-                        } else {
-                            meta.unlock_for_buy();
-                        }
-                    }
-                }
+            None if self.good_storage.has_good_expired_buy_token(&token)
+                || self.good_storage.has_good_expired_sell_token(&token) =>
+            {
+                Err(CancelLockError::ExpiredToken {
+                    expired_token: token,
+                })
             }
+            None => Err(CancelLockError::UnrecognizedToken {
+                unrecognized_token: token,
+            }),
         }
     }
-}
-
-impl Market for SGX {
-    fn new_random() -> Rc<RefCell<dyn Market>> {
-        let good_storage = GoodStorage::new_random(STARTING_CAPITAL);
-        let (eur, _) = good_storage.get_good_for_kind(&GoodKind::EUR).unwrap();
-        let (usd, _) = good_storage.get_good_for_kind(&GoodKind::USD).unwrap();
-        let (yen, _) = good_storage.get_good_for_kind(&GoodKind::YEN).unwrap();
-        let (yuan, _) = good_storage.get_good_for_kind(&GoodKind::YUAN).unwrap();
-        log_for_market_init(eur.get_qty(), yen.get_qty(), usd.get_qty(), yuan.get_qty());
-        let rand_market = SGX {
-            good_storage,
-            subscribers: Vec::new(),
-        };
-        Rc::new(RefCell::new(rand_market))
-    }
-
-    fn new_with_quantities(eur: f32, yen: f32, usd: f32, yuan: f32) -> Rc<RefCell<dyn Market>> {
-        log_for_market_init(eur, yen, usd, yuan);
-        let market = SGX {
-            good_storage: GoodStorage::with_quantities(eur, yen, usd, yuan),
-            subscribers: Vec::new(),
-        };
-        Rc::new(RefCell::new(market))
-    }
-
-    fn new_file(_path: &str) -> Rc<RefCell<dyn Market>> {
-        todo!()
-    }
 
-    fn get_name(&self) -> &'static str {
-        NAME
-    }
-
-    fn get_budget(&self) -> f32 {
-        self.good_storage.get_default_good().0.get_qty()
+    /// Fills all open limit orders, whose limit is reached by the current price of the market.
+    /// Orders are filled at their limit price, the oldest first. The events of the filled orders
+    /// are pending, see `SGX::flush_pending_events`.
+    fn match_orders(&mut self) {
+        for id in self.order_book.get_open_order_ids() {
+            let order = match self.order_book.get_order(id) {
+                Some(order) => order.clone(),
+                _ => continue,
+            };
+            let is_filled = match order.side {
+                OrderSide::Bid => self.fill_bid(&order),
+                OrderSide::Ask => self.fill_ask(&order),
+            };
+            if is_filled {
+                self.order_book.fill_order(id);
+                self.stats.add_traded_volume(&order.kind, order.quantity);
+            }
+        }
     }
 
-    fn get_buy_price(&self, kind: GoodKind, quantity: f32) -> Result<f32, MarketGetterError> {
-        if quantity <= 0.0 {
-            return Err(MarketGetterError::NonPositiveQuantityAsked);
+    /// Sells the good of the given bid to its trader, if our price is not higher than the limit
+    fn fill_bid(&mut self, order: &LimitOrder) -> bool {
+        let limit_price = order.get_limit_price();
+        let is_available =
+            self.good_storage.get_free_quantity_for_kind(&order.kind) >= order.quantity;
+        match self.get_buy_price(order.kind, order.quantity) {
+            Ok(price) if is_available && price <= limit_price => {}
+            _ => return false,
         }
 
-        let (good, meta) = self
-            .good_storage
-            .get_good_for_kind(&kind)
-            .expect("Not able to get good for buy price");
-        let available_good_quantity = good.get_qty();
-
-        if available_good_quantity < quantity {
-            return Err(MarketGetterError::InsufficientGoodQuantityAvailable {
-                requested_good_kind: kind,
-                available_good_quantity,
-                requested_good_quantity: quantity,
-            });
+        let (good, meta) = match self.good_storage.get_mut_good_for_kind(&order.kind) {
+            Some((good, meta)) => (good, meta),
+            _ => return false,
+        };
+        let old_quantity = good.get_qty();
+        if good.split(order.quantity).is_err() {
+            return false;
         }
+        // fluctuation
+        let factor = self
+            .pricing_model
+            .get_trade_fluctuation_factor(old_quantity, good.get_qty());
+        meta.fluctuate_buy_price_with_factor(factor);
+        // the escrow of the bid is ours now
+        self.merge_default_good(Good::new(DEFAULT_GOOD_KIND, limit_price));
 
-        // Get buy price for quantity
-        let exchange_price = quantity * meta.base_buy_price;
-        // Calculate a factor based on demand
-        let new_quantity = available_good_quantity - quantity;
-        let demand_factor = available_good_quantity / new_quantity;
-        // Calculate a margin (buy price must always be cheaper than sell price)
-        let margin = exchange_price * 0.05;
-
-        let price = (exchange_price + margin) * demand_factor;
-        Ok(price)
+        let event = Event {
+            kind: EventKind::Bought,
+            good_kind: order.kind,
+            quantity: order.quantity,
+            price: limit_price,
+        };
+        self.pending_events.push(event);
+        true
     }
 
-    fn get_sell_price(&self, kind: GoodKind, quantity: f32) -> Result<f32, MarketGetterError> {
-        if quantity <= 0.0 {
-            return Err(MarketGetterError::NonPositiveQuantityAsked);
-        }
-
-        let (good, meta) = self
+    /// Buys the good of the given ask from its trader, if our price is not lower than the limit
+    fn fill_ask(&mut self, order: &LimitOrder) -> bool {
+        let limit_price = order.get_limit_price();
+        let free_budget = self
             .good_storage
-            .get_good_for_kind(&kind)
-            .expect("Not able to get good for sell price");
-        let available_good_quantity = good.get_qty();
-
-        // Get sell price for quantity
-        let exchange_price = quantity * meta.base_sell_price;
-        // Calculate a factor based on demand
-        let new_quantity = available_good_quantity + quantity;
-        let demand_factor = available_good_quantity / new_quantity;
-        // Calculate a margin (sell price must always be higher than buy price)
-        let margin = exchange_price * 0.15;
+            .get_free_quantity_for_kind(&DEFAULT_GOOD_KIND);
+        match self.get_sell_price(order.kind, order.quantity) {
+            Ok(price) if free_budget >= limit_price && price >= limit_price => {}
+            _ => return false,
+        }
 
-        let price = (exchange_price + margin) * demand_factor;
-        Ok(price)
-    }
+        let (good, meta) = match self.good_storage.get_mut_good_for_kind(&order.kind) {
+            Some((good, meta)) => (good, meta),
+            _ => return false,
+        };
+        let old_quantity = good.get_qty();
+        // the escrow of the ask is ours now
+        if good.merge(Good::new(order.kind, order.quantity)).is_err() {
+            return false;
+        }
+        // fluctuation
+        let factor = self
+            .pricing_model
+            .get_trade_fluctuation_factor(old_quantity, good.get_qty());
+        meta.fluctuate_sell_price_with_factor(factor);
+        self.split_default_good(limit_price);
 
-    fn get_goods(&self) -> Vec<GoodLabel> {
-        self.good_storage.get_good_labels()
+        let event = Event {
+            kind: EventKind::Sold,
+            good_kind: order.kind,
+            quantity: order.quantity,
+            price: limit_price,
+        };
+        self.pending_events.push(event);
+        true
     }
 
-    fn lock_buy(
+    /// Locks the good for buy, see `Market::lock_buy`. A reached lock limit is returned with its
+    /// reason. Nothing is logged.
+    fn process_lock_buy(
         &mut self,
         kind_to_buy: GoodKind,
         quantity_to_buy: f32,
         bid: f32,
         trader_name: String,
-    ) -> Result<String, LockBuyError> {
-        // First check if good is available
-        let (good, meta) = match self.good_storage.get_good_for_kind(&kind_to_buy) {
-            Some((good, meta)) => (good, meta),
-            _ => {
-                panic!(
-                    "Good {} can't be locked for buy, because it was not found",
-                    kind_to_buy
-                )
-            }
-        };
-
-        // Is the Good already locked?
-        if let Some(lock) = meta.get_buy_lock() {
-            log_for_lock_buy_err(trader_name, kind_to_buy, quantity_to_buy, bid);
-            return Err(LockBuyError::GoodAlreadyLocked {
-                token: lock.transaction_token.clone(),
-            });
+    ) -> Result<String, CheckedLockError<LockBuyError>> {
+        // First check if good is available, goods that are not listed can't be bought
+        if self.good_storage.get_good_for_kind(&kind_to_buy).is_none() {
+            return Err(CheckedLockError::Market(
+                LockBuyError::InsufficientGoodQuantityAvailable {
+                    requested_good_kind: kind_to_buy,
+                    requested_good_quantity: quantity_to_buy,
+                    available_good_quantity: 0.0,
+                },
+            ));
         }
 
         // Is the number of max. locks achieved
-        if !self.can_add_buy_lock() {
-            log_for_lock_buy_err(trader_name, kind_to_buy, quantity_to_buy, bid);
-            return Err(LockBuyError::MaxAllowedLocksReached);
+        if let Err(e) = self.check_lock_limit(LockSide::Buy, kind_to_buy, &trader_name) {
+            return Err(CheckedLockError::LockLimit(e));
         }
 
-        // Not locked, but is the quantity enough?
-        if quantity_to_buy <= 0.0 {
-            log_for_lock_buy_err(trader_name, kind_to_buy, quantity_to_buy, bid);
-            return Err(LockBuyError::NonPositiveQuantityToBuy {
-                negative_quantity_to_buy: quantity_to_buy,
-            });
-        } else if quantity_to_buy > good.get_qty() {
-            log_for_lock_buy_err(trader_name, kind_to_buy, quantity_to_buy, bid);
-            return Err(LockBuyError::InsufficientGoodQuantityAvailable {
-                requested_good_kind: kind_to_buy,
-                requested_good_quantity: quantity_to_buy,
-                available_good_quantity: good.get_qty(),
-            });
+        // Is the quantity that is not locked yet enough?
+        let free_quantity = self.good_storage.get_free_quantity_for_kind(&kind_to_buy);
+        if is_invalid_amount(quantity_to_buy) {
+            return Err(CheckedLockError::Market(
+                LockBuyError::NonPositiveQuantityToBuy {
+                    negative_quantity_to_buy: quantity_to_buy,
+                },
+            ));
+        } else if quantity_to_buy > free_quantity {
+            return Err(CheckedLockError::Market(
+                LockBuyError::InsufficientGoodQuantityAvailable {
+                    requested_good_kind: kind_to_buy,
+                    requested_good_quantity: quantity_to_buy,
+                    available_good_quantity: free_quantity,
+                },
+            ));
         }
 
         let lowest_acceptable_bid = match self.get_buy_price(kind_to_buy, quantity_to_buy) {
             Ok(price) => price,
             Err(_) => {
-                log_for_lock_buy_err(trader_name, kind_to_buy, quantity_to_buy, bid);
-                return Err(LockBuyError::InsufficientGoodQuantityAvailable {
-                    requested_good_kind: kind_to_buy,
-                    requested_good_quantity: quantity_to_buy,
-                    available_good_quantity: good.get_qty(),
-                });
+                return Err(CheckedLockError::Market(
+                    LockBuyError::InsufficientGoodQuantityAvailable {
+                        requested_good_kind: kind_to_buy,
+                        requested_good_quantity: quantity_to_buy,
+                        available_good_quantity: free_quantity,
+                    },
+                ));
             }
         };
 
-        if bid <= 0.0 {
-            log_for_lock_buy_err(trader_name, kind_to_buy, quantity_to_buy, bid);
-            return Err(LockBuyError::NonPositiveBid { negative_bid: bid });
+        if is_invalid_amount(bid) {
+            return Err(CheckedLockError::Market(LockBuyError::NonPositiveBid {
+                negative_bid: bid,
+            }));
         } else if bid < lowest_acceptable_bid {
-            log_for_lock_buy_err(trader_name, kind_to_buy, quantity_to_buy, bid);
-            return Err(LockBuyError::BidTooLow {
+            return Err(CheckedLockError::Market(LockBuyError::BidTooLow {
                 requested_good_kind: kind_to_buy,
                 requested_good_quantity: quantity_to_buy,
                 low_bid: bid,
                 lowest_acceptable_bid,
-            });
+            }));
         }
 
-        // Now we can lock, we are able to unwrap here, because we checked earlier
-        let (good, meta) = self
-            .good_storage
-            .get_mut_good_for_kind(&kind_to_buy)
-            .expect("Not able to get mutable ref for good");
+        // Now we can lock
+        let token = self.gen_token();
+        let (good, meta) = match self.good_storage.get_mut_good_for_kind(&kind_to_buy) {
+            Some((good, meta)) => (good, meta),
+            None => {
+                return Err(CheckedLockError::Market(
+                    LockBuyError::InsufficientGoodQuantityAvailable {
+                        requested_good_kind: kind_to_buy,
+                        requested_good_quantity: quantity_to_buy,
+                        available_good_quantity: 0.0,
+                    },
+                ))
+            }
+        };
 
         // Lock the Good (update its metadata)
-        let token = meta.lock_for_buy(quantity_to_buy, kind_to_buy, bid, trader_name.clone());
+        meta.lock_for_buy(
+            quantity_to_buy,
+            kind_to_buy,
+            bid,
+            token.clone(),
+            trader_name.clone(),
+        );
 
         // fluctuation
         let new_quantity = good.get_qty() - quantity_to_buy;
-        let factor = good.get_qty() / new_quantity;
+        let factor = self
+            .pricing_model
+            .get_trade_fluctuation_factor(good.get_qty(), new_quantity);
         meta.fluctuate_buy_price_with_factor(factor);
+        if let Some(lock) = meta.buy_locks.get_mut(&token) {
+            // remember the factor, so that it can be reverted if the lock is cancelled
+            lock.price_factor = factor;
+        }
 
         // notify
         let event = Event {
@@ -323,32 +419,22 @@ impl Market for SGX {
             price: bid,
         };
         self.notify(event);
-
-        log_for_lock_buy(
-            trader_name,
-            kind_to_buy,
-            quantity_to_buy,
-            bid,
-            token.clone(),
-        );
         Ok(token)
     }
 
-    /// Call when a trader **buys from this market**
-    fn buy(&mut self, token: String, cash: &mut Good) -> Result<Good, BuyError> {
+    /// Buys the locked good, see `Market::buy`. Nothing is logged.
+    fn process_buy(&mut self, token: String, cash: &mut Good) -> Result<Good, BuyError> {
         // get the good as mut that was locked (in lock_buy) with the given token
         let (locked_good, locked_meta) = match self.good_storage.get_mut_good_for_buy_token(&token)
         {
             Some((good, meta)) => (good, meta),
             _ => {
                 return if self.good_storage.has_good_expired_buy_token(&token) {
-                    log_for_buy_err(token.clone());
                     Err(BuyError::ExpiredToken {
                         expired_token: token,
                     })
                 } else {
                     // this token is invalid
-                    log_for_buy_err(token.clone());
                     Err(BuyError::UnrecognizedToken {
                         unrecognized_token: token,
                     })
@@ -357,24 +443,26 @@ impl Market for SGX {
         };
 
         // Get the lock
-        let lock = locked_meta
-            .get_buy_lock()
-            .expect("Not able to get lock from metadata")
-            .clone();
+        let lock = match locked_meta.get_buy_lock(&token) {
+            Some(lock) => lock.clone(),
+            None => {
+                return Err(BuyError::UnrecognizedToken {
+                    unrecognized_token: token,
+                })
+            }
+        };
 
         // check if cash is of default kind (we only sell for EUR)
         if cash.get_kind() != DEFAULT_GOOD_KIND {
-            locked_meta.unlock_for_buy();
-            log_for_buy_err(token.clone());
+            locked_meta.unlock_for_buy(&token);
             return Err(BuyError::GoodKindNotDefault {
                 non_default_good_kind: cash.get_kind(),
             });
         }
 
         // check if cash quantity is at least equal the agreed price
-        if cash.get_qty() < lock.eur_quantity {
-            locked_meta.unlock_for_buy();
-            log_for_buy_err(token.clone());
+        if cash.get_qty().is_nan() || cash.get_qty() < lock.eur_quantity {
+            locked_meta.unlock_for_buy(&token);
             return Err(BuyError::InsufficientGoodQuantity {
                 contained_quantity: cash.get_qty(),
                 pre_agreed_quantity: lock.eur_quantity,
@@ -388,16 +476,18 @@ impl Market for SGX {
         if let Ok(splitted_good) = splitted_good {
             // fluctuation
             let new_quantity = old_quantity - lock.locked_original_qty;
-            let factor = old_quantity / new_quantity;
+            let factor = self
+                .pricing_model
+                .get_trade_fluctuation_factor(old_quantity, new_quantity);
             locked_meta.fluctuate_buy_price_with_factor(factor);
 
-            // unlock good
-            locked_meta.unlock_for_buy();
+            // settle the lock
+            locked_meta.unlock_for_buy(&token);
 
-            // update default good (increase our EUR with cash)
-            self.merge_default_good(cash.clone());
-            // split buy price from buyers cash
-            let _ = cash.split(lock.eur_quantity);
+            // split buy price from buyers cash and increase our EUR with it
+            if let Ok(payment) = cash.split(lock.eur_quantity) {
+                self.merge_default_good(payment);
+            }
 
             // notify
             let event = Event {
@@ -407,12 +497,9 @@ impl Market for SGX {
                 price: lock.eur_quantity,
             };
             self.notify(event);
-
-            log_for_buy(token);
             Ok(splitted_good)
         } else {
-            locked_meta.unlock_for_buy();
-            log_for_buy_err(token.clone());
+            locked_meta.unlock_for_buy(&token);
             // Wasn't able to split the good
             Err(BuyError::InsufficientGoodQuantity {
                 contained_quantity: cash.get_qty(),
@@ -421,95 +508,110 @@ impl Market for SGX {
         }
     }
 
-    fn lock_sell(
+    /// Locks the good for sell, see `Market::lock_sell`. A reached lock limit is returned with its
+    /// reason. Nothing is logged.
+    fn process_lock_sell(
         &mut self,
         kind_to_sell: GoodKind,
         quantity_to_sell: f32,
         offer: f32,
         trader_name: String,
-    ) -> Result<String, LockSellError> {
-        // First check if good is available
-        let (_, meta) = match self.good_storage.get_good_for_kind(&kind_to_sell) {
-            Some((good, meta)) => (good, meta),
-            _ => {
-                log_for_lock_sell_err(trader_name, kind_to_sell, quantity_to_sell, offer);
-                panic!(
-                    "Good {} can't be locked for sell, because it was not found",
-                    kind_to_sell
-                )
-            }
-        };
-        // Is the Good already locked?
-        if let Some(lock) = meta.get_sell_lock() {
-            log_for_lock_sell_err(trader_name, kind_to_sell, quantity_to_sell, offer);
-            return Err(LockSellError::GoodAlreadyLocked {
-                token: lock.transaction_token.clone(),
-            });
+    ) -> Result<String, CheckedLockError<LockSellError>> {
+        // First check if good is available, we don't pay anything for goods that are not listed
+        if self.good_storage.get_good_for_kind(&kind_to_sell).is_none() {
+            return Err(CheckedLockError::Market(LockSellError::OfferTooHigh {
+                offered_good_kind: kind_to_sell,
+                offered_good_quantity: quantity_to_sell,
+                high_offer: offer,
+                highest_acceptable_offer: 0.0,
+            }));
         }
-
         // Is the number of max. locks achieved
-        if !self.can_add_sell_lock() {
-            log_for_lock_sell_err(trader_name, kind_to_sell, quantity_to_sell, offer);
-            return Err(LockSellError::MaxAllowedLocksReached);
+        if let Err(e) = self.check_lock_limit(LockSide::Sell, kind_to_sell, &trader_name) {
+            return Err(CheckedLockError::LockLimit(e));
         }
 
-        // Not locked, but is the quantity enough?
-        if quantity_to_sell <= 0.0 {
-            log_for_lock_sell_err(trader_name, kind_to_sell, quantity_to_sell, offer);
-            return Err(LockSellError::NonPositiveQuantityToSell {
-                negative_quantity_to_sell: quantity_to_sell,
-            });
+        // Is the quantity enough?
+        if is_invalid_amount(quantity_to_sell) {
+            return Err(CheckedLockError::Market(
+                LockSellError::NonPositiveQuantityToSell {
+                    negative_quantity_to_sell: quantity_to_sell,
+                },
+            ));
         }
 
-        // Check if we have enough EUR to sell
-        if offer > self.get_budget() {
-            log_for_lock_sell_err(trader_name, kind_to_sell, quantity_to_sell, offer);
-            return Err(LockSellError::InsufficientDefaultGoodQuantityAvailable {
-                offered_good_kind: kind_to_sell,
-                available_good_quantity: self.get_budget(),
-                offered_good_quantity: quantity_to_sell,
-            });
+        // Check if we have enough EUR, that is not promised to other traders yet
+        let free_budget = self
+            .good_storage
+            .get_free_quantity_for_kind(&DEFAULT_GOOD_KIND);
+        if offer > free_budget {
+            return Err(CheckedLockError::Market(
+                LockSellError::InsufficientDefaultGoodQuantityAvailable {
+                    offered_good_kind: kind_to_sell,
+                    available_good_quantity: free_budget,
+                    offered_good_quantity: quantity_to_sell,
+                },
+            ));
         }
 
         // Is the offer acceptable?
         let highest_acceptable_offer = match self.get_sell_price(kind_to_sell, quantity_to_sell) {
             Ok(price) => price,
             Err(_) => {
-                log_for_lock_sell_err(trader_name, kind_to_sell, quantity_to_sell, offer);
-                return Err(LockSellError::NonPositiveQuantityToSell {
-                    negative_quantity_to_sell: quantity_to_sell,
-                });
+                return Err(CheckedLockError::Market(
+                    LockSellError::NonPositiveQuantityToSell {
+                        negative_quantity_to_sell: quantity_to_sell,
+                    },
+                ));
             }
         };
 
-        if offer <= 0.0 {
-            log_for_lock_sell_err(trader_name, kind_to_sell, quantity_to_sell, offer);
-            return Err(LockSellError::NonPositiveOffer {
+        if is_invalid_amount(offer) {
+            return Err(CheckedLockError::Market(LockSellError::NonPositiveOffer {
                 negative_offer: offer,
-            });
+            }));
         } else if offer > highest_acceptable_offer {
-            log_for_lock_sell_err(trader_name, kind_to_sell, quantity_to_sell, offer);
-            return Err(LockSellError::OfferTooHigh {
+            return Err(CheckedLockError::Market(LockSellError::OfferTooHigh {
                 offered_good_kind: kind_to_sell,
                 offered_good_quantity: quantity_to_sell,
                 high_offer: offer,
                 highest_acceptable_offer,
-            });
+            }));
         }
 
         // get mut ref of good
-        let (good, meta) = self
-            .good_storage
-            .get_mut_good_for_kind(&kind_to_sell)
-            .expect("Could not get mut ref of good to lock for sell");
+        let token = self.gen_token();
+        let (good, meta) = match self.good_storage.get_mut_good_for_kind(&kind_to_sell) {
+            Some((good, meta)) => (good, meta),
+            None => {
+                return Err(CheckedLockError::Market(LockSellError::OfferTooHigh {
+                    offered_good_kind: kind_to_sell,
+                    offered_good_quantity: quantity_to_sell,
+                    high_offer: offer,
+                    highest_acceptable_offer: 0.0,
+                }))
+            }
+        };
 
         // Lock the Good (update its metadata)
-        let token = meta.lock_for_sell(quantity_to_sell, kind_to_sell, offer, trader_name.clone());
+        meta.lock_for_sell(
+            quantity_to_sell,
+            kind_to_sell,
+            offer,
+            token.clone(),
+            trader_name.clone(),
+        );
 
         // fluctuation
         let new_quantity = good.get_qty() - quantity_to_sell;
-        let factor = good.get_qty() / new_quantity;
+        let factor = self
+            .pricing_model
+            .get_trade_fluctuation_factor(good.get_qty(), new_quantity);
         meta.fluctuate_sell_price_with_factor(factor);
+        if let Some(lock) = meta.sell_locks.get_mut(&token) {
+            // remember the factor, so that it can be reverted if the lock is cancelled
+            lock.price_factor = factor;
+        }
 
         // notify
         let event = Event {
@@ -519,32 +621,22 @@ impl Market for SGX {
             price: offer,
         };
         self.notify(event);
-
-        log_for_lock_sell(
-            trader_name,
-            kind_to_sell,
-            quantity_to_sell,
-            offer,
-            token.clone(),
-        );
         Ok(token)
     }
 
-    /// Call when a trader **sells to our market**
-    fn sell(&mut self, token: String, good: &mut Good) -> Result<Good, SellError> {
+    /// Sells the locked good, see `Market::sell`. Nothing is logged.
+    fn process_sell(&mut self, token: String, good: &mut Good) -> Result<Good, SellError> {
         // get mut ref for locked good
         let (locked_good, locked_meta) = match self.good_storage.get_mut_good_for_sell_token(&token)
         {
             Some((good, meta)) => (good, meta),
             _ => {
                 return if self.good_storage.has_good_expired_sell_token(&token) {
-                    log_for_sell_err(token.clone());
                     Err(SellError::ExpiredToken {
                         expired_token: token,
                     })
                 } else {
                     // this token is invalid
-                    log_for_sell_err(token.clone());
                     Err(SellError::UnrecognizedToken {
                         unrecognized_token: token,
                     })
@@ -553,17 +645,18 @@ impl Market for SGX {
         };
 
         // get the lock
-        let lock = locked_meta
-            .get_sell_lock()
-            .expect("Can't get lock for good")
-            .clone();
-
-        // check if lock is expired
+        let lock = match locked_meta.get_sell_lock(&token) {
+            Some(lock) => lock.clone(),
+            None => {
+                return Err(SellError::UnrecognizedToken {
+                    unrecognized_token: token,
+                })
+            }
+        };
 
         // token is valid, is the kind correct?
         if locked_good.get_kind() != good.get_kind() {
-            locked_meta.unlock_for_sell();
-            log_for_sell_err(token.clone());
+            locked_meta.unlock_for_sell(&token);
             return Err(SellError::WrongGoodKind {
                 wrong_good_kind: good.get_kind(),
                 pre_agreed_kind: locked_good.get_kind(),
@@ -571,9 +664,8 @@ impl Market for SGX {
         }
 
         // is the quantity correct?
-        if good.get_qty() < lock.locked_original_qty {
-            locked_meta.unlock_for_sell();
-            log_for_sell_err(token.clone());
+        if good.get_qty().is_nan() || good.get_qty() < lock.locked_original_qty {
+            locked_meta.unlock_for_sell(&token);
             return Err(SellError::InsufficientGoodQuantity {
                 contained_quantity: good.get_qty(),
                 pre_agreed_quantity: lock.locked_original_qty,
@@ -588,11 +680,13 @@ impl Market for SGX {
             // Merge our good with the sold quantity (increase our capacity)
             let _ = locked_good.merge(remaining);
             // fluctuation
-            let factor = old_quantity / locked_good.get_qty();
+            let factor = self
+                .pricing_model
+                .get_trade_fluctuation_factor(old_quantity, locked_good.get_qty());
             locked_meta.fluctuate_sell_price_with_factor(factor);
 
-            // unlock good
-            locked_meta.unlock_for_sell();
+            // settle the lock
+            locked_meta.unlock_for_sell(&token);
 
             // split our default good (remove EUR from our market)
             self.split_default_good(lock.eur_quantity);
@@ -605,14 +699,11 @@ impl Market for SGX {
                 price: lock.eur_quantity,
             };
             self.notify(event);
-
-            log_for_sell(token);
             // return the default good with the pre-agree quantity
             let res = Good::new(GoodKind::EUR, lock.eur_quantity);
             Ok(res)
         } else {
-            locked_meta.unlock_for_sell();
-            log_for_sell_err(token.clone());
+            locked_meta.unlock_for_sell(&token);
             // Splitting wasn't successful
             Err(SellError::InsufficientGoodQuantity {
                 contained_quantity: good.get_qty(),
@@ -621,3 +712,763 @@ impl Market for SGX {
         }
     }
 }
+
+impl SGX {
+    /// Returns a builder, to construct a market with another config
+    pub fn builder() -> SGXBuilder {
+        SGXBuilder::new()
+    }
+
+    /// Same as `Market::new_random`, but the random quantities are generated with the given seed.
+    /// The same seed always results in the same market.
+    pub fn new_random_with_seed(seed: u64) -> Rc<RefCell<dyn Market>> {
+        SGX::builder().seed(seed).build()
+    }
+
+    /// Returns the config of the market
+    pub fn get_config(&self) -> &SGXConfig {
+        &self.config
+    }
+
+    /// Returns the stats of the market, see `MarketStats`
+    pub fn get_stats(&self) -> &MarketStats {
+        &self.stats
+    }
+
+    /// Constructs a new market with the given quantities, that writes its events to the given
+    /// logger. The market can be used as `Market` by wrapping it into `Rc<RefCell<_>>`.
+    pub fn new_with_logger(logger: Logger, eur: f32, yen: f32, usd: f32, yuan: f32) -> Self {
+        let good_storage = GoodStorage::with_quantities(eur, yen, usd, yuan);
+        SGX::from_config(good_storage, None, logger, SGXConfig::default())
+    }
+
+    /// Writes the state of the market to its file, if it was created using `new_file`.
+    /// Markets without a file are not written.
+    pub fn save(&self) -> io::Result<()> {
+        match &self.file_path {
+            Some(path) => self.good_storage.write_to_file(path),
+            None => Ok(()),
+        }
+    }
+
+    /// Returns a snapshot of the current state of the market, see `SGXSnapshot`
+    pub fn snapshot(&self) -> SGXSnapshot {
+        SGXSnapshot {
+            day: self.day,
+            config: self.config.clone(),
+            goods: self.good_storage.to_snapshots(),
+            order_book: self.order_book.clone(),
+            consensus: self.consensus.clone(),
+        }
+    }
+
+    /// Rebuilds a market from the given snapshot. Open locks can be settled with their tokens,
+    /// as in the original market. The restored market does not write a log, so that it does not
+    /// overwrite the log of the original market.
+    /// Returns `None` if the snapshot contains bad values.
+    pub fn restore(snapshot: SGXSnapshot) -> Option<Self> {
+        SGX::restore_with_logger(snapshot, Logger::disabled())
+    }
+
+    /// Same as `SGX::restore`, but the restored market writes its events to the given logger
+    pub fn restore_with_logger(snapshot: SGXSnapshot, logger: Logger) -> Option<Self> {
+        let good_storage = GoodStorage::from_snapshots(snapshot.goods)?;
+        let mut market = SGX::from_config(good_storage, None, logger, snapshot.config);
+        market.day = snapshot.day;
+        market.order_book = snapshot.order_book;
+        market.consensus = snapshot.consensus;
+        Some(market)
+    }
+
+    /// Returns the consensus prices of the given kind, estimated from the events of other
+    /// markets
+    pub fn get_consensus(&self, kind: &GoodKind) -> Option<&GoodConsensus> {
+        self.consensus.get(kind)
+    }
+
+    /// Returns how far our quotes deviate from the consensus prices of every good, that was
+    /// traded by other markets
+    pub fn get_consensus_deviations(&self) -> Vec<ConsensusDeviation> {
+        let deviation = |our_price: Result<f32, MarketGetterError>, quantity: f32, fair: f32| {
+            our_price.ok().map(|price| price / quantity / fair - 1.0)
+        };
+        self.consensus
+            .iter()
+            .map(|c| ConsensusDeviation {
+                kind: c.kind,
+                buy_deviation: c.buy.and_then(|e| {
+                    deviation(
+                        self.get_buy_price(c.kind, e.quantity),
+                        e.quantity,
+                        e.unit_price,
+                    )
+                }),
+                sell_deviation: c.sell.and_then(|e| {
+                    deviation(
+                        self.get_sell_price(c.kind, e.quantity),
+                        e.quantity,
+                        e.unit_price,
+                    )
+                }),
+            })
+            .collect()
+    }
+
+    /// Returns the components of the price of `Market::get_buy_price`, see `QuoteBreakdown`.
+    /// Only the quantity that is not locked yet can be bought.
+    pub fn get_buy_quote(
+        &self,
+        kind: GoodKind,
+        quantity: f32,
+    ) -> Result<QuoteBreakdown, MarketGetterError> {
+        if is_invalid_amount(quantity) {
+            return Err(MarketGetterError::NonPositiveQuantityAsked);
+        }
+
+        let meta = match self.good_storage.get_good_for_kind(&kind) {
+            Some((_, meta)) => meta,
+            // goods that are not listed are not available
+            _ => {
+                return Err(MarketGetterError::InsufficientGoodQuantityAvailable {
+                    requested_good_kind: kind,
+                    available_good_quantity: 0.0,
+                    requested_good_quantity: quantity,
+                })
+            }
+        };
+        // goods that are locked by other traders can't be bought
+        let available_good_quantity = self.good_storage.get_free_quantity_for_kind(&kind);
+
+        if available_good_quantity < quantity {
+            return Err(MarketGetterError::InsufficientGoodQuantityAvailable {
+                requested_good_kind: kind,
+                available_good_quantity,
+                requested_good_quantity: quantity,
+            });
+        }
+
+        let quote = self.pricing_model.get_buy_quote(
+            quantity,
+            available_good_quantity,
+            meta.base_buy_price,
+        );
+        Ok(quote)
+    }
+
+    /// Returns the components of the price of `Market::get_sell_price`, see `QuoteBreakdown`
+    pub fn get_sell_quote(
+        &self,
+        kind: GoodKind,
+        quantity: f32,
+    ) -> Result<QuoteBreakdown, MarketGetterError> {
+        if is_invalid_amount(quantity) {
+            return Err(MarketGetterError::NonPositiveQuantityAsked);
+        }
+
+        let (good, meta) = match self.good_storage.get_good_for_kind(&kind) {
+            Some((good, meta)) => (good, meta),
+            // goods that are not listed can't be sold to us
+            _ => {
+                return Err(MarketGetterError::InsufficientGoodQuantityAvailable {
+                    requested_good_kind: kind,
+                    available_good_quantity: 0.0,
+                    requested_good_quantity: quantity,
+                })
+            }
+        };
+        let available_good_quantity = good.get_qty();
+
+        let quote = self.pricing_model.get_sell_quote(
+            quantity,
+            available_good_quantity,
+            meta.base_sell_price,
+        );
+        Ok(quote)
+    }
+
+    /// Replaces the pricing model of the market. The base prices of the goods are kept.
+    pub fn set_pricing_model(&mut self, pricing_model: Box<dyn PricingModel>) {
+        self.pricing_model = pricing_model;
+    }
+
+    /// Checks if the given trader can add a buy or sell lock for the given kind.
+    /// A trader can always lock a good again, that it has already locked, as long as neither its
+    /// quota nor the global cap is reached.
+    pub fn check_lock_limit(
+        &self,
+        side: LockSide,
+        kind: GoodKind,
+        trader_name: &str,
+    ) -> Result<(), LockLimitError> {
+        let (is_locked, locked_goods) = match side {
+            LockSide::Buy => (
+                self.good_storage
+                    .get_good_for_kind(&kind)
+                    .is_some_and(|(_, m)| m.is_locked_for_buy_by(trader_name)),
+                self.good_storage.get_buy_locks_len(trader_name),
+            ),
+            LockSide::Sell => (
+                self.good_storage
+                    .get_good_for_kind(&kind)
+                    .is_some_and(|(_, m)| m.is_locked_for_sell_by(trader_name)),
+                self.good_storage.get_sell_locks_len(trader_name),
+            ),
+        };
+        if !is_locked && locked_goods >= self.get_max_locked_goods() {
+            return Err(LockLimitError::MaxLockedGoodsReached {
+                trader_name: trader_name.to_string(),
+                locked_goods,
+            });
+        }
+
+        let locks = self.good_storage.get_locks();
+        if let Some(max_locks) = self.config.max_locks_per_trader {
+            let trader_locks = locks.iter().filter(|l| l.is_owned_by(trader_name)).count();
+            if trader_locks >= max_locks {
+                return Err(LockLimitError::TraderQuotaReached {
+                    trader_name: trader_name.to_string(),
+                    locks: trader_locks,
+                });
+            }
+        }
+        if let Some(max_locks) = self.config.max_locks {
+            if locks.len() >= max_locks {
+                let mut blocking_traders: Vec<(String, usize)> = Vec::new();
+                for lock in locks {
+                    match blocking_traders
+                        .iter_mut()
+                        .find(|(name, _)| *name == lock.trader_name)
+                    {
+                        Some((_, count)) => *count += 1,
+                        None => blocking_traders.push((lock.trader_name.clone(), 1)),
+                    }
+                }
+                blocking_traders.sort_by_key(|(_, locks)| Reverse(*locks));
+                return Err(LockLimitError::GlobalCapReached { blocking_traders });
+            }
+        }
+        Ok(())
+    }
+
+    /// Same as `Market::lock_buy`, but a lock that is rejected because of a lock limit returns
+    /// the reason (e.g. the traders that hold the locks) instead of `MaxAllowedLocksReached`
+    pub fn lock_buy_checked(
+        &mut self,
+        kind_to_buy: GoodKind,
+        quantity_to_buy: f32,
+        bid: f32,
+        trader_name: String,
+    ) -> Result<String, CheckedLockError<LockBuyError>> {
+        let res = self.process_lock_buy(kind_to_buy, quantity_to_buy, bid, trader_name.clone());
+        self.stats.add_lock(&kind_to_buy, res.is_ok());
+        self.sample_stats();
+        let event = match &res {
+            Ok(token) => LogEvent::LockBuy {
+                trader_name,
+                kind_to_buy,
+                quantity_to_buy,
+                bid,
+                token: token.clone(),
+            },
+            Err(e) => LogEvent::LockBuyError {
+                error: describe_lock_error(e),
+                trader_name,
+                kind_to_buy,
+                quantity_to_buy,
+                bid,
+            },
+        };
+        self.logger.log(event);
+        res
+    }
+
+    /// Same as `Market::lock_sell`, but a lock that is rejected because of a lock limit returns
+    /// the reason, see `SGX::lock_buy_checked`
+    pub fn lock_sell_checked(
+        &mut self,
+        kind_to_sell: GoodKind,
+        quantity_to_sell: f32,
+        offer: f32,
+        trader_name: String,
+    ) -> Result<String, CheckedLockError<LockSellError>> {
+        let res =
+            self.process_lock_sell(kind_to_sell, quantity_to_sell, offer, trader_name.clone());
+        self.stats.add_lock(&kind_to_sell, res.is_ok());
+        self.sample_stats();
+        let event = match &res {
+            Ok(token) => LogEvent::LockSell {
+                trader_name,
+                kind_to_sell,
+                quantity_to_sell,
+                offer,
+                token: token.clone(),
+            },
+            Err(e) => LogEvent::LockSellError {
+                error: describe_lock_error(e),
+                trader_name,
+                kind_to_sell,
+                quantity_to_sell,
+                offer,
+            },
+        };
+        self.logger.log(event);
+        res
+    }
+
+    /// Returns the name of the trader that owns the lock for the given token, if the lock exists
+    pub fn get_token_owner(&self, token: &str) -> Option<&str> {
+        self.good_storage
+            .get_lock_for_token(token)
+            .map(|lock| lock.trader_name.as_str())
+    }
+
+    /// Returns the number of days until the lock for the given token expires, if the lock
+    /// exists. A lock with 1 remaining day expires at the next `EventKind::Wait`.
+    pub fn get_remaining_lock_days(&self, token: &str) -> Option<u8> {
+        let lock_expiry_in_days = self.config.get_lock_expiry_in_days();
+        self.good_storage
+            .get_lock_for_token(token)
+            .map(|lock| lock.get_remaining_days(lock_expiry_in_days))
+    }
+
+    /// Checks if the lock for the given token can be used by the given trader.
+    /// Unknown tokens are accepted, the market operation reports them.
+    fn check_token_owner<E>(
+        &self,
+        token: &str,
+        trader_name: &str,
+    ) -> Result<(), OwnedTokenError<E>> {
+        match self.good_storage.get_lock_for_token(token) {
+            Some(lock) if !lock.is_owned_by(trader_name) => Err(OwnedTokenError::NotTokenOwner {
+                token: token.to_string(),
+                owner: lock.trader_name.clone(),
+                trader_name: trader_name.to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Same as `Market::buy`, but the token is only accepted if its lock is owned by the given
+    /// trader. The lock of a foreign token is kept untouched.
+    /// `Market::buy` of a `TraderMarket` uses it, `Market::buy` of the `SGX` itself accepts every
+    /// valid token.
+    pub fn buy_as(
+        &mut self,
+        trader_name: &str,
+        token: String,
+        cash: &mut Good,
+    ) -> Result<Good, OwnedTokenError<BuyError>> {
+        if let Err(err) = self.check_token_owner(&token, trader_name) {
+            self.logger.log(LogEvent::BuyError {
+                token,
+                error: format!("{:?}", err),
+            });
+            return Err(err);
+        }
+        self.buy(token, cash).map_err(OwnedTokenError::Market)
+    }
+
+    /// Same as `Market::sell`, but the token is only accepted if its lock is owned by the given
+    /// trader. The lock of a foreign token is kept untouched.
+    /// `Market::sell` of a `TraderMarket` uses it, `Market::sell` of the `SGX` itself accepts every
+    /// valid token.
+    pub fn sell_as(
+        &mut self,
+        trader_name: &str,
+        token: String,
+        good: &mut Good,
+    ) -> Result<Good, OwnedTokenError<SellError>> {
+        if let Err(err) = self.check_token_owner(&token, trader_name) {
+            self.logger.log(LogEvent::SellError {
+                token,
+                error: format!("{:?}", err),
+            });
+            return Err(err);
+        }
+        self.sell(token, good).map_err(OwnedTokenError::Market)
+    }
+
+    /// Cancels the buy or sell lock for the given token. The locked quantity is free again and
+    /// the token can't be used anymore. If configured, the price fluctuation of the lock is
+    /// reverted. Lock listeners are notified, since the market common has no event for it.
+    pub fn cancel_lock(&mut self, token: String) -> Result<(), CancelLockError> {
+        let res = self.process_cancel_lock(token.clone());
+        self.sample_stats();
+        let event = match &res {
+            Ok(_) => LogEvent::CancelLock { token },
+            Err(e) => LogEvent::CancelLockError {
+                token,
+                error: format!("{:?}", e),
+            },
+        };
+        self.logger.log(event);
+        res
+    }
+
+    /// Adds a listener, that is notified about cancelled and expired locks
+    pub fn add_lock_listener(&mut self, listener: Box<dyn LockListener>) {
+        self.lock_listeners.push(listener);
+    }
+
+    /// Places a limit order, that is filled as soon as our price reaches the given price per
+    /// unit. Open orders are matched after every event the market is notified of.
+    /// The escrow is split from the given good: the EUR of the limit price for a bid, or the
+    /// quantity of the good for an ask. Returns the id of the order.
+    pub fn place_order(
+        &mut self,
+        trader_name: &str,
+        side: OrderSide,
+        kind: GoodKind,
+        quantity: f32,
+        price_per_unit: f32,
+        escrow: &mut Good,
+    ) -> Result<u64, OrderError> {
+        if !self.config.order_book_enabled {
+            return Err(OrderError::OrderBookDisabled);
+        } else if kind == DEFAULT_GOOD_KIND {
+            return Err(OrderError::DefaultGoodKind);
+        } else if is_invalid_amount(quantity) {
+            return Err(OrderError::NonPositiveQuantity { quantity });
+        } else if is_invalid_amount(price_per_unit) {
+            return Err(OrderError::NonPositivePrice { price_per_unit });
+        }
+
+        let (required_kind, required_quantity) = match side {
+            OrderSide::Bid => (DEFAULT_GOOD_KIND, quantity * price_per_unit),
+            OrderSide::Ask => (kind, quantity),
+        };
+        if escrow.get_kind() != required_kind {
+            return Err(OrderError::WrongEscrowKind {
+                required_kind,
+                escrow_kind: escrow.get_kind(),
+            });
+        }
+        if escrow.split(required_quantity).is_err() {
+            return Err(OrderError::InsufficientEscrow {
+                required_quantity,
+                contained_quantity: escrow.get_qty(),
+            });
+        }
+
+        let id = self.order_book.add_order(
+            trader_name.to_string(),
+            side,
+            kind,
+            quantity,
+            price_per_unit,
+        );
+        Ok(id)
+    }
+
+    /// Returns the limit order with the given id, if it was neither cancelled nor claimed
+    pub fn get_order(&self, id: u64) -> Option<&LimitOrder> {
+        self.order_book.get_order(id)
+    }
+
+    /// Returns all limit orders of the given trader, that were neither cancelled nor claimed
+    pub fn get_orders_of(&self, trader_name: &str) -> Vec<&LimitOrder> {
+        self.order_book.get_orders_of(trader_name)
+    }
+
+    /// Returns the limit order with the given id, if it is owned by the given trader
+    fn get_owned_order(&self, trader_name: &str, id: u64) -> Result<&LimitOrder, OrderError> {
+        match self.order_book.get_order(id) {
+            Some(order) if !order.is_owned_by(trader_name) => Err(OrderError::NotOrderOwner {
+                id,
+                owner: order.trader_name.clone(),
+                trader_name: trader_name.to_string(),
+            }),
+            Some(order) => Ok(order),
+            None => Err(OrderError::UnknownOrder { id }),
+        }
+    }
+
+    /// Cancels the open limit order with the given id and returns its escrow
+    pub fn cancel_order(&mut self, trader_name: &str, id: u64) -> Result<Good, OrderError> {
+        let order = self.get_owned_order(trader_name, id)?;
+        if order.status == OrderStatus::Filled {
+            return Err(OrderError::AlreadyFilled { id });
+        }
+        let (kind, quantity) = order.get_escrow();
+        self.order_book.remove_order(id);
+        Ok(Good::new(kind, quantity))
+    }
+
+    /// Returns the proceeds of the filled limit order with the given id (the bought good for a
+    /// bid, or the EUR for an ask). The order is removed afterwards.
+    pub fn claim_order(&mut self, trader_name: &str, id: u64) -> Result<Good, OrderError> {
+        let order = self.get_owned_order(trader_name, id)?;
+        if order.status != OrderStatus::Filled {
+            return Err(OrderError::NotFilled { id });
+        }
+        let (kind, quantity) = order.get_proceeds();
+        self.order_book.remove_order(id);
+        Ok(Good::new(kind, quantity))
+    }
+
+    /// Sends the events of orders, that were filled during `on_event`, to the subscribers.
+    /// Otherwise, they are sent with the next event of this market. Must not be called from
+    /// `on_event` of a subscriber.
+    pub fn flush_pending_events(&mut self) {
+        self.notify_pending_events();
+    }
+}
+
+impl Notifiable for SGX {
+    fn add_subscriber(&mut self, subscriber: Box<dyn Notifiable>) {
+        self.subscribers.push(subscriber);
+    }
+
+    fn on_event(&mut self, event: Event) {
+        self.process_event(event);
+        self.match_orders();
+        self.sample_stats();
+    }
+}
+
+impl SGX {
+    /// Adds the given event of another market to the consensus prices. If enabled, our quote of
+    /// the same quantity is pulled towards the consensus.
+    fn follow_consensus(&mut self, event: &Event) {
+        let is_buy = match event.kind {
+            EventKind::Bought | EventKind::LockedBuy => true,
+            EventKind::Sold | EventKind::LockedSell => false,
+            EventKind::Wait => return,
+        };
+        let ConsensusConfig {
+            smoothing,
+            strength,
+        } = self.config.consensus;
+        let estimate = match self.consensus.add_event(
+            event.good_kind,
+            is_buy,
+            event.quantity,
+            event.price,
+            smoothing,
+        ) {
+            Some(estimate) => estimate,
+            None => return,
+        };
+        if strength <= 0.0 {
+            return;
+        }
+
+        let our_price = if is_buy {
+            self.get_buy_price(event.good_kind, event.quantity)
+        } else {
+            self.get_sell_price(event.good_kind, event.quantity)
+        };
+        let our_unit_price = match our_price {
+            Ok(price) if price > 0.0 => price / event.quantity,
+            _ => return, // not able get our price
+        };
+        let factor = 1.0 + strength.min(1.0) * (estimate.unit_price / our_unit_price - 1.0);
+        if let Some((_, meta)) = self.good_storage.get_mut_good_for_kind(&event.good_kind) {
+            if is_buy {
+                meta.fluctuate_buy_price_with_factor(factor);
+            } else {
+                meta.fluctuate_sell_price_with_factor(factor);
+            }
+        }
+    }
+
+    /// Updates the prices after the given event, see `Notifiable::on_event`.
+    /// No stats are sampled.
+    fn process_event(&mut self, event: Event) {
+        self.follow_consensus(&event);
+        match event.kind {
+            EventKind::Bought | EventKind::LockedBuy => {
+                let our_price = match self.get_buy_price(event.good_kind, event.quantity) {
+                    Ok(our_price) => our_price,
+                    _ => {
+                        return; // not able get our price
+                    }
+                };
+
+                let (_, bought_meta) =
+                    match self.good_storage.get_mut_good_for_kind(&event.good_kind) {
+                        Some((good, meta)) => (good, meta),
+                        _ => return, // no good found
+                    };
+                let factors = self.pricing_model.on_external_buy(our_price, event.price);
+                bought_meta.fluctuate_buy_price_with_factor(factors.buy);
+                bought_meta.fluctuate_sell_price_with_factor(factors.sell);
+            }
+            EventKind::Sold | EventKind::LockedSell => {
+                // a good was sold => demand to sell increases => lower our prices
+                let our_price = match self.get_sell_price(event.good_kind, event.quantity) {
+                    Ok(our_price) => our_price,
+                    _ => {
+                        return; // not able get our price
+                    }
+                };
+
+                let (_, bought_meta) =
+                    match self.good_storage.get_mut_good_for_kind(&event.good_kind) {
+                        Some((good, meta)) => (good, meta),
+                        _ => return, // no good found
+                    };
+                let factors = self.pricing_model.on_external_sell(our_price, event.price);
+                bought_meta.fluctuate_buy_price_with_factor(factors.buy);
+                bought_meta.fluctuate_sell_price_with_factor(factors.sell);
+            }
+            EventKind::Wait => {
+                // A day has passed, we may decrease our prices
+                self.day = self.day.saturating_add(1);
+                self.logger.log(LogEvent::Wait { day: self.day });
+                let factors = self.pricing_model.on_wait();
+                let lock_expiry_in_days = self.config.get_lock_expiry_in_days();
+                let retention_in_days = self.config.expired_token_retention_in_days;
+                let mut expired_locks = Vec::new();
+                let iter = self.good_storage.iter_mut();
+                for (good, meta) in iter {
+                    // fluctuate prices each day
+                    if good.get_kind() != DEFAULT_GOOD_KIND {
+                        meta.fluctuate_buy_price_with_factor(factors.buy);
+                        meta.fluctuate_sell_price_with_factor(factors.sell);
+                    }
+                    // unlock all locks that are too old
+                    expired_locks.extend(meta.age_locks(lock_expiry_in_days));
+                    // forget tokens that have expired long ago
+                    meta.age_expired_tokens(retention_in_days);
+                }
+                for (side, lock) in expired_locks.iter() {
+                    self.lock_listeners
+                        .iter_mut()
+                        .for_each(|l| l.on_lock_expired(*side, lock));
+                }
+            }
+        }
+    }
+}
+
+impl Market for SGX {
+    fn new_random() -> Rc<RefCell<dyn Market>> {
+        let good_storage = GoodStorage::new_random(STARTING_CAPITAL);
+        let rand_market =
+            SGX::from_config(good_storage, None, Logger::default(), SGXConfig::default());
+        Rc::new(RefCell::new(rand_market))
+    }
+
+    fn new_with_quantities(eur: f32, yen: f32, usd: f32, yuan: f32) -> Rc<RefCell<dyn Market>> {
+        let good_storage = GoodStorage::with_quantities(eur, yen, usd, yuan);
+        let market = SGX::from_config(good_storage, None, Logger::default(), SGXConfig::default());
+        Rc::new(RefCell::new(market))
+    }
+
+    /// Initializes the market from the given file. If the file does not exist or contains
+    /// bad values, the market is initialized with random goods (like `new_random`).
+    /// Either way, the state of the market is written to the file when the market is dropped.
+    fn new_file(path: &str) -> Rc<RefCell<dyn Market>> {
+        let good_storage = GoodStorage::from_file(path)
+            .unwrap_or_else(|| GoodStorage::new_random(STARTING_CAPITAL));
+        let market = SGX::from_config(
+            good_storage,
+            Some(path.to_string()),
+            Logger::default(),
+            SGXConfig::default(),
+        );
+        Rc::new(RefCell::new(market))
+    }
+
+    fn get_name(&self) -> &'static str {
+        NAME
+    }
+
+    fn get_budget(&self) -> f32 {
+        self.good_storage
+            .get_default_good()
+            .map_or(0.0, |(good, _)| good.get_qty())
+    }
+
+    fn get_buy_price(&self, kind: GoodKind, quantity: f32) -> Result<f32, MarketGetterError> {
+        self.get_buy_quote(kind, quantity).map(|quote| quote.price)
+    }
+
+    fn get_sell_price(&self, kind: GoodKind, quantity: f32) -> Result<f32, MarketGetterError> {
+        self.get_sell_quote(kind, quantity).map(|quote| quote.price)
+    }
+
+    fn get_goods(&self) -> Vec<GoodLabel> {
+        self.good_storage.get_good_labels()
+    }
+
+    /// A lock that is rejected because of a lock limit returns `MaxAllowedLocksReached`, the
+    /// reason (e.g. the traders that hold the locks) is only written to the JSON-lines log.
+    /// Use `SGX::lock_buy_checked` to get the reason.
+    fn lock_buy(
+        &mut self,
+        kind_to_buy: GoodKind,
+        quantity_to_buy: f32,
+        bid: f32,
+        trader_name: String,
+    ) -> Result<String, LockBuyError> {
+        self.lock_buy_checked(kind_to_buy, quantity_to_buy, bid, trader_name)
+            .map_err(LockBuyError::from)
+    }
+
+    /// Call when a trader **buys from this market**.
+    /// The owner of the token is not checked, use `SGX::buy_as` or a `TraderMarket` for that.
+    fn buy(&mut self, token: String, cash: &mut Good) -> Result<Good, BuyError> {
+        let lock = self.good_storage.get_lock_for_token(&token).cloned();
+        let res = self.process_buy(token.clone(), cash);
+        if let (Ok(_), Some(lock)) = (&res, lock) {
+            self.stats
+                .add_traded_volume(&lock.kind, lock.locked_original_qty);
+        }
+        self.sample_stats();
+        let event = match &res {
+            Ok(_) => LogEvent::Buy { token },
+            Err(e) => LogEvent::BuyError {
+                token,
+                error: format!("{:?}", e),
+            },
+        };
+        self.logger.log(event);
+        res
+    }
+
+    /// A lock that is rejected because of a lock limit returns `MaxAllowedLocksReached`, see
+    /// `Market::lock_buy`. Use `SGX::lock_sell_checked` to get the reason.
+    fn lock_sell(
+        &mut self,
+        kind_to_sell: GoodKind,
+        quantity_to_sell: f32,
+        offer: f32,
+        trader_name: String,
+    ) -> Result<String, LockSellError> {
+        self.lock_sell_checked(kind_to_sell, quantity_to_sell, offer, trader_name)
+            .map_err(LockSellError::from)
+    }
+
+    /// Call when a trader **sells to our market**.
+    /// The owner of the token is not checked, use `SGX::sell_as` or a `TraderMarket` for that.
+    fn sell(&mut self, token: String, good: &mut Good) -> Result<Good, SellError> {
+        let lock = self.good_storage.get_lock_for_token(&token).cloned();
+        let res = self.process_sell(token.clone(), good);
+        if let (Ok(_), Some(lock)) = (&res, lock) {
+            self.stats
+                .add_traded_volume(&lock.kind, lock.locked_original_qty);
+        }
+        self.sample_stats();
+        let event = match &res {
+            Ok(_) => LogEvent::Sell { token },
+            Err(e) => LogEvent::SellError {
+                token,
+                error: format!("{:?}", e),
+            },
+        };
+        self.logger.log(event);
+        res
+    }
+}
+
+impl Drop for SGX {
+    /// Writes the state of the market to its file, if it was created using `new_file`
+    fn drop(&mut self) {
+        if let Err(e) = self.save() {
+            let path = self.file_path.as_deref().unwrap_or_default();
+            eprintln!("Unable to write the market to the file {path}: {e}");
+        }
+    }
+}
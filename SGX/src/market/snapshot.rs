@@ -0,0 +1,42 @@
+use serde::{Deserialize, Serialize};
+use unitn_market_2022::good::good_kind::GoodKind;
+
+pub use crate::goods::expired_tokens::{ExpiredToken, ExpiredTokens};
+pub use crate::goods::good_metadata::GoodMetadata;
+pub use crate::goods::good_status::GoodLock;
+pub use crate::goods::good_storage::GoodSnapshot;
+use crate::market::config::SGXConfig;
+use crate::market::consensus::ConsensusPrices;
+use crate::market::order_book::OrderBook;
+
+/**
+ * A `SGXSnapshot` is the whole state of a market at a certain day: the quantity of every good,
+ * its base prices, its open locks (including their age) and its expired tokens.
+ * It is created with `SGX::snapshot` and a market can be rebuilt from it with `SGX::restore`,
+ * e.g. to fork a market and compare different strategies from the same day on.
+ *
+ * The open and filled limit orders are part of the snapshot, including their escrow.
+ * Subscribers, the logger and a custom pricing model are not part of a snapshot.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SGXSnapshot {
+    /// Number of days since the market initialization
+    pub day: u32,
+    /// Parameters of the market
+    pub config: SGXConfig,
+    /// All goods, including their metadata
+    pub goods: Vec<GoodSnapshot>,
+    /// All limit orders, that were neither cancelled nor claimed
+    #[serde(default)]
+    pub order_book: OrderBook,
+    /// Consensus prices of other markets, that were estimated so far
+    #[serde(default)]
+    pub consensus: ConsensusPrices,
+}
+
+impl SGXSnapshot {
+    /// Returns the snapshot of the good of the given kind
+    pub fn get_good(&self, kind: &GoodKind) -> Option<&GoodSnapshot> {
+        self.goods.iter().find(|g| g.kind == *kind)
+    }
+}
@@ -0,0 +1,166 @@
+//! Statistics of the market. For every good, the market keeps a time series of its base prices,
+//! its quantity, the traded volume and the number of accepted and rejected locks.
+//!
+//! A sample of every good is taken after each operation of the market (locks, buys and sells)
+//! and after each event it was notified of, including waiting days. Volumes and lock counts
+//! are summed up since the market initialization, so that every sample is complete on its own.
+use serde::Serialize;
+use unitn_market_2022::good::good_kind::GoodKind;
+
+use crate::goods::good_storage::GoodStorage;
+
+/// The state of a good at a certain point in time
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct StatsSample {
+    /// Number of days since the market initialization
+    pub day: u32,
+    /// Base buy price of the good, in EUR
+    pub base_buy_price: f32,
+    /// Base sell price of the good, in EUR
+    pub base_sell_price: f32,
+    /// Quantity of the good
+    pub quantity: f32,
+    /// Quantity of the good that was bought or sold by traders so far
+    pub traded_volume: f32,
+    /// Number of locks (buy and sell) that were accepted so far
+    pub accepted_locks: u32,
+    /// Number of locks (buy and sell) that were rejected so far
+    pub rejected_locks: u32,
+}
+
+/// The statistics of a single good
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GoodStats {
+    /// Kind of the good
+    #[serde(with = "crate::goods::serde_good_kind")]
+    pub kind: GoodKind,
+    /// Quantity of the good that was bought or sold by traders so far
+    pub traded_volume: f32,
+    /// Number of locks (buy and sell) that were accepted so far
+    pub accepted_locks: u32,
+    /// Number of locks (buy and sell) that were rejected so far
+    pub rejected_locks: u32,
+    /// All samples, starting with the market initialization
+    pub history: Vec<StatsSample>,
+}
+
+impl GoodStats {
+    /// Returns the last sample of the good
+    pub fn get_last_sample(&self) -> Option<&StatsSample> {
+        self.history.last()
+    }
+}
+
+/**
+ * The `MarketStats` contain the statistics of every good of a market.
+ * They can be serialized, e.g. to plot them next to the history of a trader.
+ */
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct MarketStats {
+    /// Statistics of every good, in the order of the goods of the market
+    pub goods: Vec<GoodStats>,
+}
+
+impl MarketStats {
+    /// Constructs the statistics of the given goods and takes the first sample
+    pub(crate) fn new(good_storage: &GoodStorage) -> Self {
+        let goods = good_storage
+            .iter()
+            .map(|(good, _)| GoodStats {
+                kind: good.get_kind(),
+                traded_volume: 0.0,
+                accepted_locks: 0,
+                rejected_locks: 0,
+                history: Vec::new(),
+            })
+            .collect();
+        let mut stats = Self { goods };
+        stats.sample(0, good_storage);
+        stats
+    }
+
+    /// Returns the statistics of the given kind
+    pub fn get_good_stats(&self, kind: &GoodKind) -> Option<&GoodStats> {
+        self.goods.iter().find(|s| s.kind == *kind)
+    }
+
+    /// Returns the mutable statistics of the given kind
+    fn get_mut_good_stats(&mut self, kind: &GoodKind) -> Option<&mut GoodStats> {
+        self.goods.iter_mut().find(|s| s.kind == *kind)
+    }
+
+    /// Counts a lock of the given kind
+    pub(crate) fn add_lock(&mut self, kind: &GoodKind, is_accepted: bool) {
+        if let Some(stats) = self.get_mut_good_stats(kind) {
+            if is_accepted {
+                stats.accepted_locks += 1;
+            } else {
+                stats.rejected_locks += 1;
+            }
+        }
+    }
+
+    /// Adds the given quantity to the traded volume of the given kind
+    pub(crate) fn add_traded_volume(&mut self, kind: &GoodKind, quantity: f32) {
+        if let Some(stats) = self.get_mut_good_stats(kind) {
+            stats.traded_volume += quantity;
+        }
+    }
+
+    /// Takes a sample of every good
+    pub(crate) fn sample(&mut self, day: u32, good_storage: &GoodStorage) {
+        for (good, meta) in good_storage.iter() {
+            if let Some(stats) = self.get_mut_good_stats(&good.get_kind()) {
+                let sample = StatsSample {
+                    day,
+                    base_buy_price: meta.base_buy_price,
+                    base_sell_price: meta.base_sell_price,
+                    quantity: good.get_qty(),
+                    traded_volume: stats.traded_volume,
+                    accepted_locks: stats.accepted_locks,
+                    rejected_locks: stats.rejected_locks,
+                };
+                stats.history.push(sample);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::goods::good_storage::GoodStorage;
+    use crate::market::stats::MarketStats;
+    use unitn_market_2022::good::good_kind::GoodKind;
+
+    #[test]
+    fn test_market_stats() {
+        let mut storage = GoodStorage::with_quantities(100.0, 100.0, 100.0, 100.0);
+        let mut stats = MarketStats::new(&storage);
+        assert_eq!(4, stats.goods.len());
+        assert_eq!(
+            1,
+            stats.get_good_stats(&GoodKind::USD).unwrap().history.len(),
+            "Initialization must be sampled"
+        );
+
+        stats.add_lock(&GoodKind::USD, true);
+        stats.add_lock(&GoodKind::USD, false);
+        stats.add_traded_volume(&GoodKind::USD, 10.0);
+        let (_, meta) = storage.get_mut_good_for_kind(&GoodKind::USD).unwrap();
+        meta.fluctuate_buy_price_with_factor(2.0);
+        let base_buy_price = meta.base_buy_price;
+        stats.sample(1, &storage);
+
+        let usd = stats.get_good_stats(&GoodKind::USD).unwrap();
+        assert_eq!(2, usd.history.len());
+        let sample = usd.get_last_sample().unwrap();
+        assert_eq!(1, sample.day);
+        assert_eq!(base_buy_price, sample.base_buy_price);
+        assert_eq!(10.0, sample.traded_volume);
+        assert_eq!(1, sample.accepted_locks);
+        assert_eq!(1, sample.rejected_locks);
+
+        let yen = stats.get_good_stats(&GoodKind::YEN).unwrap();
+        assert_eq!(0, yen.get_last_sample().unwrap().accepted_locks);
+    }
+}
@@ -0,0 +1,160 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use unitn_market_2022::event::event::Event;
+use unitn_market_2022::event::notifiable::Notifiable;
+use unitn_market_2022::good::good::Good;
+use unitn_market_2022::good::good_kind::GoodKind;
+use unitn_market_2022::market::good_label::GoodLabel;
+use unitn_market_2022::market::{
+    BuyError, LockBuyError, LockSellError, Market, MarketGetterError, SellError,
+};
+
+use crate::market::errors::OwnedTokenError;
+use crate::market::sgx::SGX;
+
+/**
+ * A `TraderMarket` is the `Market` of a single trader on a shared `SGX`.
+ * All locks are owned by the bound trader, and `Market::buy` and `Market::sell` only accept
+ * tokens of its own locks (see `SGX::buy_as`). A foreign token is rejected with
+ * `UnrecognizedToken`, like a token that was never issued.
+ * Every trader gets its own `TraderMarket` of the same market, e.g. with `TraderMarket::bind`.
+ */
+pub struct TraderMarket {
+    market: Rc<RefCell<SGX>>,
+    trader_name: String,
+}
+
+impl TraderMarket {
+    /// Constructs a new market of the given trader
+    pub fn new(market: Rc<RefCell<SGX>>, trader_name: &str) -> Self {
+        Self {
+            market,
+            trader_name: trader_name.to_string(),
+        }
+    }
+
+    /// Returns the market of the given trader, ready to be used as `Market`
+    pub fn bind(market: &Rc<RefCell<SGX>>, trader_name: &str) -> Rc<RefCell<dyn Market>> {
+        Rc::new(RefCell::new(TraderMarket::new(
+            Rc::clone(market),
+            trader_name,
+        )))
+    }
+
+    /// Returns the name of the bound trader
+    pub fn get_trader_name(&self) -> &str {
+        &self.trader_name
+    }
+
+    /// Returns the shared market
+    pub fn get_market(&self) -> &Rc<RefCell<SGX>> {
+        &self.market
+    }
+}
+
+impl Notifiable for TraderMarket {
+    fn add_subscriber(&mut self, subscriber: Box<dyn Notifiable>) {
+        self.market.borrow_mut().add_subscriber(subscriber);
+    }
+
+    fn on_event(&mut self, event: Event) {
+        self.market.borrow_mut().on_event(event);
+    }
+}
+
+impl Market for TraderMarket {
+    /// Returns a new `SGX`, that is not bound to a trader
+    fn new_random() -> Rc<RefCell<dyn Market>> {
+        SGX::new_random()
+    }
+
+    /// Returns a new `SGX`, that is not bound to a trader
+    fn new_with_quantities(eur: f32, yen: f32, usd: f32, yuan: f32) -> Rc<RefCell<dyn Market>> {
+        SGX::new_with_quantities(eur, yen, usd, yuan)
+    }
+
+    /// Returns a new `SGX`, that is not bound to a trader
+    fn new_file(path: &str) -> Rc<RefCell<dyn Market>> {
+        SGX::new_file(path)
+    }
+
+    fn get_name(&self) -> &'static str {
+        self.market.borrow().get_name()
+    }
+
+    fn get_budget(&self) -> f32 {
+        self.market.borrow().get_budget()
+    }
+
+    fn get_buy_price(&self, kind: GoodKind, quantity: f32) -> Result<f32, MarketGetterError> {
+        self.market.borrow().get_buy_price(kind, quantity)
+    }
+
+    fn get_sell_price(&self, kind: GoodKind, quantity: f32) -> Result<f32, MarketGetterError> {
+        self.market.borrow().get_sell_price(kind, quantity)
+    }
+
+    fn get_goods(&self) -> Vec<GoodLabel> {
+        self.market.borrow().get_goods()
+    }
+
+    /// The lock is always owned by the bound trader, the given trader name is ignored
+    fn lock_buy(
+        &mut self,
+        kind_to_buy: GoodKind,
+        quantity_to_buy: f32,
+        bid: f32,
+        _trader_name: String,
+    ) -> Result<String, LockBuyError> {
+        self.market.borrow_mut().lock_buy(
+            kind_to_buy,
+            quantity_to_buy,
+            bid,
+            self.trader_name.clone(),
+        )
+    }
+
+    /// Only tokens of locks owned by the bound trader are accepted
+    fn buy(&mut self, token: String, cash: &mut Good) -> Result<Good, BuyError> {
+        let res = self
+            .market
+            .borrow_mut()
+            .buy_as(&self.trader_name, token.clone(), cash);
+        res.map_err(|e| match e {
+            OwnedTokenError::NotTokenOwner { .. } => BuyError::UnrecognizedToken {
+                unrecognized_token: token,
+            },
+            OwnedTokenError::Market(e) => e,
+        })
+    }
+
+    /// The lock is always owned by the bound trader, the given trader name is ignored
+    fn lock_sell(
+        &mut self,
+        kind_to_sell: GoodKind,
+        quantity_to_sell: f32,
+        offer: f32,
+        _trader_name: String,
+    ) -> Result<String, LockSellError> {
+        self.market.borrow_mut().lock_sell(
+            kind_to_sell,
+            quantity_to_sell,
+            offer,
+            self.trader_name.clone(),
+        )
+    }
+
+    /// Only tokens of locks owned by the bound trader are accepted
+    fn sell(&mut self, token: String, good: &mut Good) -> Result<Good, SellError> {
+        let res = self
+            .market
+            .borrow_mut()
+            .sell_as(&self.trader_name, token.clone(), good);
+        res.map_err(|e| match e {
+            OwnedTokenError::NotTokenOwner { .. } => SellError::UnrecognizedToken {
+                unrecognized_token: token,
+            },
+            OwnedTokenError::Market(e) => e,
+        })
+    }
+}
@@ -0,0 +1,371 @@
+//! Property based tests of the SGX. Random sequences of locks, trades and days are run against
+//! a market, and the invariants of the market are checked after every step. If an invariant
+//! does not hold, proptest shrinks the sequence to a minimal one.
+#[cfg(test)]
+mod test {
+    use crate::market::config::SGXConfig;
+    use crate::market::log::Logger;
+    use crate::market::sgx::SGX;
+    use crate::tests::wait_days;
+    use proptest::prelude::*;
+    use std::collections::{HashMap, HashSet};
+    use unitn_market_2022::good::consts::DEFAULT_GOOD_KIND;
+    use unitn_market_2022::good::good::Good;
+    use unitn_market_2022::good::good_kind::GoodKind;
+    use unitn_market_2022::market::{BuyError, Market, SellError};
+
+    const KINDS: [GoodKind; 4] = [GoodKind::EUR, GoodKind::YEN, GoodKind::USD, GoodKind::YUAN];
+    const TRADERS: [&str; 2] = ["FIRST", "SECOND"];
+    const LOCK_EXPIRY_IN_DAYS: u8 = 3;
+    const MAX_LOCKS_PER_TRADER: usize = 3;
+    const MAX_LOCKS: usize = 5;
+
+    /// A single step of a sequence. Tokens are picked by index from all tokens that were
+    /// issued so far, so that used and expired tokens are tried again.
+    #[derive(Debug, Clone)]
+    enum Step {
+        LockBuy {
+            trader: usize,
+            kind: usize,
+            quantity: f32,
+            bid_factor: f32,
+        },
+        Buy {
+            token: usize,
+            cash_factor: f32,
+        },
+        LockSell {
+            trader: usize,
+            kind: usize,
+            quantity: f32,
+            offer_factor: f32,
+        },
+        Sell {
+            token: usize,
+            quantity_factor: f32,
+        },
+        Wait,
+    }
+
+    /// Returns a strategy for a single step. Bids and offers are close to the quoted price, so
+    /// that some locks are accepted and some are rejected.
+    fn step_strategy() -> impl Strategy<Value = Step> {
+        prop_oneof![
+            3 => (0..TRADERS.len(), 0..KINDS.len(), 1.0f32..2_000.0, 0.8f32..1.2).prop_map(
+                |(trader, kind, quantity, bid_factor)| Step::LockBuy {
+                    trader,
+                    kind,
+                    quantity,
+                    bid_factor,
+                }
+            ),
+            3 => (any::<usize>(), 0.9f32..1.5)
+                .prop_map(|(token, cash_factor)| Step::Buy { token, cash_factor }),
+            3 => (0..TRADERS.len(), 0..KINDS.len(), 1.0f32..2_000.0, 0.8f32..1.2).prop_map(
+                |(trader, kind, quantity, offer_factor)| Step::LockSell {
+                    trader,
+                    kind,
+                    quantity,
+                    offer_factor,
+                }
+            ),
+            3 => (any::<usize>(), 0.9f32..1.5).prop_map(|(token, quantity_factor)| Step::Sell {
+                token,
+                quantity_factor,
+            }),
+            1 => Just(Step::Wait),
+        ]
+    }
+
+    /// A market and the traders that trade with it
+    struct Harness {
+        market: SGX,
+        /// Goods of every trader, by kind
+        wallets: Vec<HashMap<GoodKind, f32>>,
+        /// Total quantity of every kind (market and traders), it must never change
+        totals: HashMap<GoodKind, f32>,
+        /// All buy tokens that were issued, with the index of their trader
+        buy_tokens: Vec<(usize, String)>,
+        /// All sell tokens that were issued, with the index of their trader
+        sell_tokens: Vec<(usize, String)>,
+        /// Tokens whose lock has expired
+        expired_tokens: HashSet<String>,
+    }
+
+    impl Harness {
+        /// Constructs a market with limits and short lock expiry, and two traders
+        fn new() -> Self {
+            let market = SGX::builder()
+                .quantities(50_000.0, 500_000.0, 5_000.0, 30_000.0)
+                .lock_expiry_in_days(LOCK_EXPIRY_IN_DAYS)
+                .max_locks_per_trader(MAX_LOCKS_PER_TRADER)
+                .max_locks(MAX_LOCKS)
+                .logger(Logger::disabled())
+                .build_sgx();
+            let wallets = TRADERS
+                .iter()
+                .map(|_| KINDS.iter().map(|kind| (*kind, 10_000.0)).collect())
+                .collect::<Vec<HashMap<GoodKind, f32>>>();
+
+            let mut harness = Self {
+                market,
+                wallets,
+                totals: HashMap::new(),
+                buy_tokens: Vec::new(),
+                sell_tokens: Vec::new(),
+                expired_tokens: HashSet::new(),
+            };
+            harness.totals = KINDS
+                .iter()
+                .map(|kind| (*kind, harness.get_total_quantity(kind)))
+                .collect();
+            harness
+        }
+
+        /// Returns the quantity of the given kind of the market and all traders
+        fn get_total_quantity(&self, kind: &GoodKind) -> f32 {
+            let market_quantity = self
+                .market
+                .get_goods()
+                .iter()
+                .find(|l| l.good_kind == *kind)
+                .map_or(0.0, |l| l.quantity);
+            let traders_quantity: f32 = self.wallets.iter().map(|w| w[kind]).sum();
+            market_quantity + traders_quantity
+        }
+
+        /// Takes up to the given quantity from the wallet of the trader
+        fn take(&mut self, trader: usize, kind: GoodKind, quantity: f32) -> Good {
+            let available = self.wallets[trader][&kind];
+            let quantity = quantity.min(available);
+            self.wallets[trader].insert(kind, available - quantity);
+            Good::new(kind, quantity)
+        }
+
+        /// Puts the given good into the wallet of the trader
+        fn put(&mut self, trader: usize, good: &Good) {
+            *self.wallets[trader].entry(good.get_kind()).or_default() += good.get_qty();
+        }
+
+        /// Returns all open tokens of the market
+        fn get_open_tokens(&self) -> HashSet<String> {
+            self.market
+                .snapshot()
+                .goods
+                .iter()
+                .flat_map(|g| {
+                    g.metadata
+                        .buy_locks
+                        .keys()
+                        .chain(g.metadata.sell_locks.keys())
+                        .cloned()
+                        .collect::<Vec<String>>()
+                })
+                .collect()
+        }
+
+        /// Runs the given step, expired tokens must be rejected
+        fn run(&mut self, step: &Step) -> Result<(), TestCaseError> {
+            match *step {
+                Step::LockBuy {
+                    trader,
+                    kind,
+                    quantity,
+                    bid_factor,
+                } => {
+                    let kind = KINDS[kind];
+                    if let Ok(price) = self.market.get_buy_price(kind, quantity) {
+                        let bid = price * bid_factor;
+                        let trader_name = TRADERS[trader].to_string();
+                        if let Ok(token) = self.market.lock_buy(kind, quantity, bid, trader_name) {
+                            self.buy_tokens.push((trader, token));
+                        }
+                    }
+                }
+                Step::Buy { token, cash_factor } => {
+                    if self.buy_tokens.is_empty() {
+                        return Ok(());
+                    }
+                    let (trader, token) = self.buy_tokens[token % self.buy_tokens.len()].clone();
+                    let price = self
+                        .market
+                        .snapshot()
+                        .goods
+                        .iter()
+                        .find_map(|g| g.metadata.buy_locks.get(&token).map(|l| l.eur_quantity))
+                        .unwrap_or(1.0);
+                    let mut cash = self.take(trader, DEFAULT_GOOD_KIND, price * cash_factor);
+                    let res = self.market.buy(token.clone(), &mut cash);
+                    self.put(trader, &cash);
+                    if self.expired_tokens.contains(&token) {
+                        prop_assert!(
+                            matches!(res, Err(BuyError::ExpiredToken { .. })),
+                            "Expired token {} must be rejected, got {:?}",
+                            token,
+                            res
+                        );
+                    }
+                    if let Ok(good) = res {
+                        self.put(trader, &good);
+                    }
+                }
+                Step::LockSell {
+                    trader,
+                    kind,
+                    quantity,
+                    offer_factor,
+                } => {
+                    let kind = KINDS[kind];
+                    if let Ok(price) = self.market.get_sell_price(kind, quantity) {
+                        let offer = price * offer_factor;
+                        let trader_name = TRADERS[trader].to_string();
+                        if let Ok(token) = self.market.lock_sell(kind, quantity, offer, trader_name)
+                        {
+                            self.sell_tokens.push((trader, token));
+                        }
+                    }
+                }
+                Step::Sell {
+                    token,
+                    quantity_factor,
+                } => {
+                    if self.sell_tokens.is_empty() {
+                        return Ok(());
+                    }
+                    let (trader, token) = self.sell_tokens[token % self.sell_tokens.len()].clone();
+                    let (kind, quantity) = self
+                        .market
+                        .snapshot()
+                        .goods
+                        .iter()
+                        .find_map(|g| {
+                            g.metadata
+                                .sell_locks
+                                .get(&token)
+                                .map(|l| (l.kind, l.locked_original_qty))
+                        })
+                        .unwrap_or((GoodKind::YEN, 1.0));
+                    let mut good = self.take(trader, kind, quantity * quantity_factor);
+                    let res = self.market.sell(token.clone(), &mut good);
+                    self.put(trader, &good);
+                    if self.expired_tokens.contains(&token) {
+                        prop_assert!(
+                            matches!(res, Err(SellError::ExpiredToken { .. })),
+                            "Expired token {} must be rejected, got {:?}",
+                            token,
+                            res
+                        );
+                    }
+                    if let Ok(eur) = res {
+                        self.put(trader, &eur);
+                    }
+                }
+                Step::Wait => {
+                    let open_tokens = self.get_open_tokens();
+                    wait_days(&mut self.market, 1);
+                    let still_open_tokens = self.get_open_tokens();
+                    self.expired_tokens
+                        .extend(open_tokens.difference(&still_open_tokens).cloned());
+                }
+            }
+            Ok(())
+        }
+
+        /// Checks the invariants of the market and the traders
+        fn check_invariants(&self) -> Result<(), TestCaseError> {
+            // value is conserved between the traders and the market
+            for kind in KINDS.iter() {
+                let total = self.totals[kind];
+                let quantity = self.get_total_quantity(kind);
+                prop_assert!(
+                    (total - quantity).abs() <= total * 1e-4,
+                    "Total quantity of {:?} changed from {} to {}",
+                    kind,
+                    total,
+                    quantity
+                );
+            }
+
+            // quantities never go negative and prices stay finite and positive
+            for label in self.market.get_goods() {
+                prop_assert!(
+                    label.quantity >= 0.0,
+                    "Negative quantity of {:?}: {}",
+                    label.good_kind,
+                    label.quantity
+                );
+                for price in [label.exchange_rate_buy, label.exchange_rate_sell] {
+                    prop_assert!(
+                        price.is_finite() && price > 0.0,
+                        "Bad price of {:?}: {}",
+                        label.good_kind,
+                        price
+                    );
+                }
+            }
+            for wallet in self.wallets.iter() {
+                prop_assert!(
+                    wallet.values().all(|q| *q >= 0.0),
+                    "Bad wallet {:?}",
+                    wallet
+                );
+            }
+
+            // locks never exceed the limits
+            let snapshot = self.market.snapshot();
+            let config: &SGXConfig = &snapshot.config;
+            let max_locked_goods = config.get_max_locked_goods(snapshot.goods.len());
+            let locks = snapshot
+                .goods
+                .iter()
+                .flat_map(|g| {
+                    g.metadata
+                        .buy_locks
+                        .values()
+                        .chain(g.metadata.sell_locks.values())
+                })
+                .collect::<Vec<_>>();
+            prop_assert!(locks.len() <= MAX_LOCKS, "Too many locks: {}", locks.len());
+            for trader_name in TRADERS {
+                let trader_locks = locks.iter().filter(|l| l.is_owned_by(trader_name)).count();
+                prop_assert!(
+                    trader_locks <= MAX_LOCKS_PER_TRADER,
+                    "Too many locks of {}: {}",
+                    trader_name,
+                    trader_locks
+                );
+                let buy_locked_goods = snapshot
+                    .goods
+                    .iter()
+                    .filter(|g| g.metadata.is_locked_for_buy_by(trader_name))
+                    .count();
+                let sell_locked_goods = snapshot
+                    .goods
+                    .iter()
+                    .filter(|g| g.metadata.is_locked_for_sell_by(trader_name))
+                    .count();
+                prop_assert!(
+                    buy_locked_goods <= max_locked_goods && sell_locked_goods <= max_locked_goods,
+                    "Too many goods locked by {}: {} (buy), {} (sell)",
+                    trader_name,
+                    buy_locked_goods,
+                    sell_locked_goods
+                );
+            }
+            Ok(())
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn test_invariants_hold_for_random_sequences(
+            steps in prop::collection::vec(step_strategy(), 1..120)
+        ) {
+            let mut harness = Harness::new();
+            for step in steps.iter() {
+                harness.run(step)?;
+                harness.check_invariants()?;
+            }
+        }
+    }
+}
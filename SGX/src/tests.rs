@@ -1,4 +1,24 @@
 #![allow(non_snake_case)]
+#[cfg(test)]
+use unitn_market_2022::event::event::{Event, EventKind};
+#[cfg(test)]
+use unitn_market_2022::event::notifiable::Notifiable;
+#[cfg(test)]
+use unitn_market_2022::good::consts::DEFAULT_GOOD_KIND;
+
+/// Lets the given number of days pass in the market, by sending it a `Wait` event per day
+#[cfg(test)]
+pub(crate) fn wait_days<M: Notifiable + ?Sized>(market: &mut M, days: u32) {
+    for _ in 0..days {
+        market.on_event(Event {
+            kind: EventKind::Wait,
+            good_kind: DEFAULT_GOOD_KIND,
+            quantity: 0.0,
+            price: 0.0,
+        });
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::market::sgx::SGX;
@@ -145,3 +165,1410 @@ mod test {
         market_test::test_get_name::<SGX>();
     }
 }
+
+/// Tests of the features the SGX adds on top of the `Market` trait
+#[cfg(test)]
+mod sgx_test {
+    use crate::goods::good_status::GoodLock;
+    use crate::goods::good_storage::GoodStorage;
+    use crate::market::config::SGXConfig;
+    use crate::market::errors::{
+        CancelLockError, CheckedLockError, LockLimitError, OrderError, OwnedTokenError,
+    };
+    use crate::market::lock_listener::{LockListener, LockSide};
+    use crate::market::log::{LogFormat, Logger};
+    use crate::market::order_book::{OrderSide, OrderStatus};
+    use crate::market::pricing::{
+        ConstantProductPricing, DemandPricing, LinearSpreadPricing, PricingModel,
+    };
+    use crate::market::sgx::SGX;
+    use crate::market::snapshot::SGXSnapshot;
+    use crate::market::trader_market::TraderMarket;
+    use crate::tests::wait_days;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::cell::RefCell;
+    use std::env::temp_dir;
+    use std::fs;
+    use std::rc::Rc;
+    use unitn_market_2022::event::event::{Event, EventKind};
+    use unitn_market_2022::event::notifiable::Notifiable;
+    use unitn_market_2022::good::consts::DEFAULT_GOOD_KIND;
+    use unitn_market_2022::good::good::Good;
+    use unitn_market_2022::good::good_kind::GoodKind;
+    use unitn_market_2022::market::{
+        BuyError, LockBuyError, LockSellError, Market, MarketGetterError, SellError,
+    };
+
+    /// Returns the quantities and exchange rates of all goods of the given market
+    fn get_goods_state(market: &Rc<RefCell<dyn Market>>) -> Vec<(GoodKind, f32, f32, f32)> {
+        market
+            .as_ref()
+            .borrow()
+            .get_goods()
+            .iter()
+            .map(|l| {
+                (
+                    l.good_kind,
+                    l.quantity,
+                    l.exchange_rate_buy,
+                    l.exchange_rate_sell,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_new_file_with_missing_file() {
+        let path = temp_dir().join("sgx_test_new_file_with_missing_file.json");
+        let path = path.to_str().unwrap();
+        let _ = fs::remove_file(path);
+
+        let market = SGX::new_file(path);
+        assert_eq!(
+            4,
+            market.as_ref().borrow().get_goods().len(),
+            "Market must be initialized with random goods"
+        );
+
+        drop(market);
+        let was_written = GoodStorage::from_file(path).is_some();
+        let _ = fs::remove_file(path);
+        assert!(was_written, "Market must be written to {} on drop", path);
+    }
+
+    #[test]
+    fn test_new_file_with_malformed_file() {
+        let path = temp_dir().join("sgx_test_new_file_with_malformed_file.json");
+        let path = path.to_str().unwrap();
+        fs::write(path, "not a market").unwrap();
+
+        let market = SGX::new_file(path);
+        let goods_len = market.as_ref().borrow().get_goods().len();
+        drop(market);
+        let _ = fs::remove_file(path);
+        assert_eq!(4, goods_len, "Market must be initialized with random goods");
+    }
+
+    #[test]
+    fn test_save() {
+        let market = SGX::new_with_logger(Logger::disabled(), 1.0, 1.0, 1.0, 1.0);
+        assert!(
+            market.save().is_ok(),
+            "A market without a file is not written"
+        );
+
+        let path = temp_dir().join("sgx_test_save.json");
+        let path = path.to_str().unwrap();
+        let storage = GoodStorage::with_quantities(10.0, 20.0, 30.0, 40.0);
+        let market = SGX::from_config(
+            storage,
+            Some(path.to_string()),
+            Logger::disabled(),
+            SGXConfig::default(),
+        );
+        assert!(market.save().is_ok());
+        let stored = GoodStorage::from_file(path);
+        let _ = fs::remove_file(path);
+        assert!(stored.is_some(), "Market must be written to {}", path);
+
+        let bad_path = temp_dir()
+            .join("sgx_test_save")
+            .join("missing_dir")
+            .join("m.json");
+        let market = SGX::from_config(
+            GoodStorage::with_quantities(1.0, 1.0, 1.0, 1.0),
+            Some(bad_path.to_str().unwrap().to_string()),
+            Logger::disabled(),
+            SGXConfig::default(),
+        );
+        assert!(market.save().is_err(), "Write errors must be returned");
+    }
+
+    #[test]
+    fn test_new_file_resumes_market() {
+        let path = temp_dir().join("sgx_test_new_file_resumes_market.json");
+        let path = path.to_str().unwrap();
+        let trader_name = "TEST_TRADER".to_string();
+        GoodStorage::with_quantities(100_000.0, 100_000.0, 100_000.0, 100_000.0)
+            .write_to_file(path)
+            .unwrap();
+
+        // trade at the first market and leave a lock open
+        let market = SGX::new_file(path);
+        let token = {
+            let mut market = market.as_ref().borrow_mut();
+            let price = market.get_buy_price(GoodKind::USD, 10.0).unwrap();
+            market
+                .lock_buy(GoodKind::USD, 10.0, price, trader_name)
+                .unwrap()
+        };
+        let stored_state = get_goods_state(&market);
+        drop(market);
+
+        // resume the market from the file
+        let market = SGX::new_file(path);
+        let resumed_state = get_goods_state(&market);
+        let mut cash = Good::new(DEFAULT_GOOD_KIND, 100_000.0);
+        let bought = market.as_ref().borrow_mut().buy(token.clone(), &mut cash);
+        drop(market);
+        let _ = fs::remove_file(path);
+
+        assert_eq!(
+            stored_state, resumed_state,
+            "Resumed market must be equal to the stored market"
+        );
+        assert!(
+            bought.is_ok(),
+            "Token '{}' must still be valid after resuming the market",
+            token
+        );
+    }
+
+    #[test]
+    fn test_multiple_buy_locks_for_same_good() {
+        let trader_name = "TEST_TRADER".to_string();
+        let market = SGX::new_with_quantities(100_000.0, 100_000.0, 1_000.0, 100_000.0);
+        let mut market = market.as_ref().borrow_mut();
+
+        // lock the same good twice
+        let price = market.get_buy_price(GoodKind::USD, 400.0).unwrap();
+        let first_token = market
+            .lock_buy(GoodKind::USD, 400.0, price, trader_name.clone())
+            .expect("First lock must succeed");
+        let price = market.get_buy_price(GoodKind::USD, 400.0).unwrap();
+        let second_token = market
+            .lock_buy(GoodKind::USD, 400.0, price, trader_name.clone())
+            .expect("Second lock for the same good must succeed");
+        assert_ne!(first_token, second_token, "Tokens must be different");
+
+        // only 200 USD are not locked anymore
+        let price = market.get_buy_price(GoodKind::USD, 400.0).unwrap();
+        let err = market.lock_buy(GoodKind::USD, 400.0, price, trader_name);
+        match err {
+            Err(LockBuyError::InsufficientGoodQuantityAvailable {
+                available_good_quantity,
+                ..
+            }) => assert_eq!(
+                200.0, available_good_quantity,
+                "Only the free quantity is available"
+            ),
+            _ => panic!("Expected insufficient quantity, got {:?}", err),
+        }
+
+        // both locks can be settled
+        let mut cash = Good::new(DEFAULT_GOOD_KIND, 100_000.0);
+        let first_good = market.buy(first_token, &mut cash);
+        let second_good = market.buy(second_token, &mut cash);
+        assert_eq!(400.0, first_good.unwrap().get_qty(), "Must buy 400 USD");
+        assert_eq!(400.0, second_good.unwrap().get_qty(), "Must buy 400 USD");
+    }
+
+    #[test]
+    fn test_multiple_sell_locks_respect_free_budget() {
+        let trader_name = "TEST_TRADER".to_string();
+        let market = SGX::new_with_quantities(100.0, 100_000.0, 100_000.0, 100_000.0);
+        let mut market = market.as_ref().borrow_mut();
+
+        let first_token = market.lock_sell(GoodKind::USD, 100.0, 60.0, trader_name.clone());
+        assert!(first_token.is_ok(), "First sell lock must succeed");
+
+        // only 40 EUR are not promised yet
+        let err = market.lock_sell(GoodKind::USD, 100.0, 60.0, trader_name.clone());
+        match err {
+            Err(LockSellError::InsufficientDefaultGoodQuantityAvailable {
+                available_good_quantity,
+                ..
+            }) => assert_eq!(
+                40.0, available_good_quantity,
+                "Only the free budget is available"
+            ),
+            _ => panic!("Expected insufficient budget, got {:?}", err),
+        }
+
+        let second_token = market.lock_sell(GoodKind::USD, 100.0, 30.0, trader_name);
+        assert!(second_token.is_ok(), "Second sell lock must succeed");
+    }
+
+    #[test]
+    fn test_buy_as_rejects_foreign_token() {
+        let owner = "OWNER";
+        let mut market = SGX::from_config(
+            GoodStorage::with_quantities(100_000.0, 100_000.0, 100_000.0, 100_000.0),
+            None,
+            Logger::disabled(),
+            SGXConfig::default(),
+        );
+        let bid = market.get_buy_price(GoodKind::USD, 100.0).unwrap();
+        let token = market
+            .lock_buy(GoodKind::USD, 100.0, bid, owner.to_string())
+            .unwrap();
+        assert_eq!(Some(owner), market.get_token_owner(&token));
+
+        let mut cash = Good::new(DEFAULT_GOOD_KIND, bid);
+        let res = market.buy_as("THIEF", token.clone(), &mut cash);
+        match res {
+            Err(OwnedTokenError::NotTokenOwner {
+                token: rejected_token,
+                owner: token_owner,
+                trader_name,
+            }) => {
+                assert_eq!(token, rejected_token);
+                assert_eq!(owner, token_owner);
+                assert_eq!("THIEF", trader_name);
+            }
+            _ => panic!("Foreign token must be rejected, got {:?}", res),
+        }
+        assert_eq!(bid, cash.get_qty(), "Cash must not be touched");
+        assert_eq!(
+            Some(owner),
+            market.get_token_owner(&token),
+            "Lock must stay open"
+        );
+
+        let bought = market.buy_as(owner, token.clone(), &mut cash);
+        assert_eq!(
+            Some(Good::new(GoodKind::USD, 100.0)),
+            bought.ok(),
+            "Owner must be able to use the token"
+        );
+        assert_eq!(None, market.get_token_owner(&token), "Lock must be closed");
+    }
+
+    #[test]
+    fn test_sell_as_rejects_foreign_token() {
+        let owner = "OWNER";
+        let mut market = SGX::from_config(
+            GoodStorage::with_quantities(100_000.0, 100_000.0, 100_000.0, 100_000.0),
+            None,
+            Logger::disabled(),
+            SGXConfig::default(),
+        );
+        let offer = market.get_sell_price(GoodKind::YEN, 100.0).unwrap();
+        let token = market
+            .lock_sell(GoodKind::YEN, 100.0, offer, owner.to_string())
+            .unwrap();
+
+        let mut good = Good::new(GoodKind::YEN, 100.0);
+        let res = market.sell_as("THIEF", token.clone(), &mut good);
+        assert!(
+            matches!(res, Err(OwnedTokenError::NotTokenOwner { .. })),
+            "Foreign token must be rejected, got {:?}",
+            res
+        );
+        assert_eq!(100.0, good.get_qty(), "Good must not be touched");
+
+        let sold = market.sell_as(owner, token, &mut good);
+        assert_eq!(
+            Some(Good::new(DEFAULT_GOOD_KIND, offer)),
+            sold.ok(),
+            "Owner must be able to use the token"
+        );
+    }
+
+    #[test]
+    fn test_trader_market_rejects_foreign_token() {
+        let sgx = Rc::new(RefCell::new(SGX::new_with_logger(
+            Logger::disabled(),
+            100_000.0,
+            100_000.0,
+            100_000.0,
+            100_000.0,
+        )));
+        let owner = TraderMarket::bind(&sgx, "OWNER");
+        let thief = TraderMarket::bind(&sgx, "THIEF");
+
+        let bid = owner.borrow().get_buy_price(GoodKind::USD, 10.0).unwrap();
+        let token = owner
+            .borrow_mut()
+            .lock_buy(GoodKind::USD, 10.0, bid, "THIEF".to_string())
+            .unwrap();
+        assert_eq!(
+            Some("OWNER"),
+            sgx.borrow().get_token_owner(&token),
+            "Lock must be owned by the bound trader"
+        );
+        let mut cash = Good::new(DEFAULT_GOOD_KIND, bid);
+        let res = thief.borrow_mut().buy(token.clone(), &mut cash);
+        assert!(
+            matches!(res, Err(BuyError::UnrecognizedToken { .. })),
+            "Foreign token must be rejected through the trait, got {:?}",
+            res
+        );
+        assert_eq!(bid, cash.get_qty(), "Cash must not be touched");
+        assert_eq!(
+            Some(Good::new(GoodKind::USD, 10.0)),
+            owner.borrow_mut().buy(token, &mut cash).ok(),
+            "Owner must be able to use the token"
+        );
+
+        let offer = owner.borrow().get_sell_price(GoodKind::USD, 10.0).unwrap();
+        let token = owner
+            .borrow_mut()
+            .lock_sell(GoodKind::USD, 10.0, offer, "OWNER".to_string())
+            .unwrap();
+        let mut good = Good::new(GoodKind::USD, 10.0);
+        let res = thief.borrow_mut().sell(token.clone(), &mut good);
+        assert!(
+            matches!(res, Err(SellError::UnrecognizedToken { .. })),
+            "Foreign token must be rejected through the trait, got {:?}",
+            res
+        );
+        assert!(owner.borrow_mut().sell(token, &mut good).is_ok());
+    }
+
+    #[test]
+    fn test_json_lines_log() {
+        let path = temp_dir().join("sgx_test_json_lines_log.jsonl");
+        let path = path.to_str().unwrap();
+        let logger = Logger::with_file(LogFormat::JsonLines, path).unwrap();
+        let mut market = SGX::new_with_logger(logger, 100_000.0, 100_000.0, 100_000.0, 100_000.0);
+
+        let bid = market.get_buy_price(GoodKind::USD, 10.0).unwrap();
+        let token = market
+            .lock_buy(GoodKind::USD, 10.0, bid, "TEST_TRADER".to_string())
+            .unwrap();
+        let _ = market.lock_buy(GoodKind::USD, -1.0, bid, "TEST_TRADER".to_string());
+        drop(market);
+
+        let content = fs::read_to_string(path).unwrap();
+        let _ = fs::remove_file(path);
+        let records = content
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .collect::<Vec<serde_json::Value>>();
+        assert_eq!(3, records.len(), "Init and both locks must be logged");
+        assert_eq!("market_init", records[0]["event"]);
+        assert_eq!("lock_buy", records[1]["event"]);
+        assert_eq!(token.as_str(), records[1]["token"]);
+        assert_eq!("lock_buy_error", records[2]["event"]);
+        assert!(
+            records[2]["error"]
+                .as_str()
+                .unwrap()
+                .starts_with("NonPositiveQuantityToBuy"),
+            "Error variant must be logged, got {}",
+            records[2]["error"]
+        );
+    }
+
+    #[test]
+    fn test_set_pricing_model() {
+        let mut market = SGX::new_with_logger(Logger::disabled(), 1000.0, 1000.0, 1000.0, 1000.0);
+        let default_price = market.get_buy_price(GoodKind::USD, 10.0).unwrap();
+        let base_buy_price = market
+            .snapshot()
+            .get_good(&GoodKind::USD)
+            .unwrap()
+            .metadata
+            .base_buy_price;
+        assert_eq!(
+            DemandPricing::default().get_buy_price(10.0, 1000.0, base_buy_price),
+            default_price,
+            "Demand pricing is the default model"
+        );
+
+        let model = ConstantProductPricing { fee: 0.0 };
+        let expected_price = model.get_buy_price(10.0, 1000.0, base_buy_price);
+        market.set_pricing_model(Box::new(model));
+        assert_eq!(
+            expected_price,
+            market.get_buy_price(GoodKind::USD, 10.0).unwrap(),
+            "Price must be calculated by the new model"
+        );
+        assert_ne!(expected_price, default_price);
+    }
+
+    #[test]
+    fn test_builder_lock_parameters() {
+        let mut market = SGX::builder()
+            .quantities(100_000.0, 100_000.0, 100_000.0, 100_000.0)
+            .max_locked_goods(1)
+            .lock_expiry_in_days(3)
+            .logger(Logger::disabled())
+            .build_sgx();
+        let trader_name = "TEST_TRADER".to_string();
+
+        let bid = market.get_buy_price(GoodKind::USD, 10.0).unwrap();
+        let token = market
+            .lock_buy(GoodKind::USD, 10.0, bid, trader_name.clone())
+            .unwrap();
+        let bid = market.get_buy_price(GoodKind::YEN, 10.0).unwrap();
+        assert!(
+            matches!(
+                market.lock_buy(GoodKind::YEN, 10.0, bid, trader_name),
+                Err(LockBuyError::MaxAllowedLocksReached)
+            ),
+            "Only one good can be locked"
+        );
+
+        wait_days(&mut market, 3);
+        let mut cash = Good::new(DEFAULT_GOOD_KIND, 100_000.0);
+        assert!(
+            matches!(
+                market.buy(token, &mut cash),
+                Err(BuyError::ExpiredToken { .. })
+            ),
+            "Lock must expire after 3 days"
+        );
+    }
+
+    #[test]
+    fn test_snapshot_and_restore() {
+        let trader_name = "TEST_TRADER".to_string();
+        let mut market = SGX::builder()
+            .quantities(100_000.0, 100_000.0, 100_000.0, 100_000.0)
+            .lock_expiry_in_days(5)
+            .logger(Logger::disabled())
+            .build_sgx();
+        let bid = market.get_buy_price(GoodKind::USD, 10.0).unwrap();
+        let token = market
+            .lock_buy(GoodKind::USD, 10.0, bid, trader_name)
+            .unwrap();
+        wait_days(&mut market, 1);
+
+        let snapshot = market.snapshot();
+        assert_eq!(1, snapshot.day);
+        assert_eq!(5, snapshot.config.lock_expiry_in_days);
+        let usd = snapshot.get_good(&GoodKind::USD).unwrap();
+        // a lock starts at age 1, the wait ages it by one day
+        assert_eq!(
+            2, usd.metadata.buy_locks[&token].age_in_days,
+            "Age of the lock must be part of the snapshot"
+        );
+
+        // the snapshot can be stored and read again
+        let json = serde_json::to_string(&snapshot).unwrap();
+        let read_snapshot: SGXSnapshot = serde_json::from_str(&json).unwrap();
+        assert_eq!(snapshot, read_snapshot);
+
+        // fork the market and settle the lock in the fork only
+        let mut fork = SGX::restore(read_snapshot).expect("Snapshot must be valid");
+        assert_eq!(
+            snapshot,
+            fork.snapshot(),
+            "Fork must be equal to the market"
+        );
+        assert_eq!(
+            market.get_remaining_lock_days(&token),
+            fork.get_remaining_lock_days(&token),
+            "Lock must expire at the same day in the fork"
+        );
+
+        let mut cash = Good::new(DEFAULT_GOOD_KIND, bid);
+        assert!(
+            fork.buy(token.clone(), &mut cash).is_ok(),
+            "Token must be valid in the fork"
+        );
+        assert_ne!(market.snapshot(), fork.snapshot());
+        assert_eq!(Some("TEST_TRADER"), market.get_token_owner(&token));
+    }
+
+    #[test]
+    fn test_restore_bad_snapshot() {
+        let mut market = SGX::new_with_logger(Logger::disabled(), 1000.0, 1000.0, 1000.0, 1000.0);
+        let bid = market.get_buy_price(GoodKind::USD, 10.0).unwrap();
+        let token = market
+            .lock_buy(GoodKind::USD, 10.0, bid, "TEST_TRADER".to_string())
+            .unwrap();
+        let snapshot = market.snapshot();
+        let usd_index = snapshot
+            .goods
+            .iter()
+            .position(|g| g.kind == GoodKind::USD)
+            .unwrap();
+        let restore_with = |corrupt: fn(&mut SGXSnapshot, usize, &str)| {
+            let mut snapshot = snapshot.clone();
+            corrupt(&mut snapshot, usd_index, &token);
+            SGX::restore(snapshot).is_some()
+        };
+
+        assert!(
+            restore_with(|_, _, _| {}),
+            "Valid snapshot must be restored"
+        );
+        assert!(
+            !restore_with(|s, usd, _| {
+                let usd = s.goods[usd].clone();
+                s.goods.push(usd);
+            }),
+            "Market can't be restored with a good listed twice"
+        );
+        assert!(
+            !restore_with(|s, usd, _| s.goods[usd].quantity = -1.0),
+            "Market can't be restored with a negative quantity"
+        );
+        assert!(
+            !restore_with(|s, usd, _| s.goods[usd].quantity = f32::NAN),
+            "Market can't be restored with a quantity of NaN"
+        );
+        assert!(
+            !restore_with(|s, usd, token| {
+                let lock = s.goods[usd].metadata.buy_locks.get_mut(token).unwrap();
+                lock.kind = GoodKind::YUAN;
+                s.goods.retain(|g| g.kind != GoodKind::YUAN);
+            }),
+            "Market can't be restored with a lock of a good that is not listed"
+        );
+        assert!(
+            !restore_with(|s, usd, token| {
+                let lock = s.goods[usd].metadata.buy_locks[token].clone();
+                s.goods[usd]
+                    .metadata
+                    .buy_locks
+                    .insert("OTHER_TOKEN".to_string(), lock);
+            }),
+            "Market can't be restored with a lock stored by another token"
+        );
+    }
+
+    #[test]
+    fn test_stats() {
+        let trader_name = "TEST_TRADER".to_string();
+        let mut market = SGX::new_with_logger(Logger::disabled(), 1000.0, 1000.0, 1000.0, 1000.0);
+        let bid = market.get_buy_price(GoodKind::USD, 10.0).unwrap();
+        let token = market
+            .lock_buy(GoodKind::USD, 10.0, bid, trader_name.clone())
+            .unwrap();
+        let _ = market.lock_buy(GoodKind::USD, 10.0, 0.0, trader_name);
+        let mut cash = Good::new(DEFAULT_GOOD_KIND, bid);
+        market.buy(token, &mut cash).unwrap();
+        wait_days(&mut market, 1);
+
+        let usd = market.get_stats().get_good_stats(&GoodKind::USD).unwrap();
+        assert_eq!(
+            5,
+            usd.history.len(),
+            "Initialization, two locks, buy and wait must be sampled"
+        );
+        assert_eq!(0, usd.history[0].day);
+        assert_eq!(1000.0, usd.history[0].quantity);
+        let sample = usd.get_last_sample().unwrap();
+        assert_eq!(1, sample.day);
+        assert_eq!(990.0, sample.quantity);
+        assert_eq!(10.0, sample.traded_volume);
+        assert_eq!(1, sample.accepted_locks);
+        assert_eq!(1, sample.rejected_locks);
+        assert!(
+            sample.base_buy_price < usd.history[3].base_buy_price,
+            "Waiting must decrease the buy price"
+        );
+    }
+
+    #[test]
+    fn test_limit_orders() {
+        let trader_name = "TEST_TRADER";
+        let mut market = SGX::builder()
+            .quantities(100_000.0, 100_000.0, 100_000.0, 100_000.0)
+            .order_book(true)
+            .logger(Logger::disabled())
+            .build_sgx();
+
+        // bid a bit below the current price, waiting decreases the price
+        let price_per_unit = market.get_buy_price(GoodKind::USD, 10.0).unwrap() / 10.0 * 0.95;
+        let mut cash = Good::new(DEFAULT_GOOD_KIND, 1_000.0);
+        let id = market
+            .place_order(
+                trader_name,
+                OrderSide::Bid,
+                GoodKind::USD,
+                10.0,
+                price_per_unit,
+                &mut cash,
+            )
+            .unwrap();
+        assert_eq!(1_000.0 - price_per_unit * 10.0, cash.get_qty());
+        assert!(
+            matches!(
+                market.claim_order(trader_name, id),
+                Err(OrderError::NotFilled { .. })
+            ),
+            "Open order can't be claimed"
+        );
+        assert!(
+            matches!(
+                market.cancel_order("THIEF", id),
+                Err(OrderError::NotOrderOwner { .. })
+            ),
+            "Foreign order can't be cancelled"
+        );
+
+        let budget = market.get_budget();
+        wait_days(&mut market, 1);
+        assert_eq!(OrderStatus::Filled, market.get_order(id).unwrap().status);
+        assert_eq!(budget + price_per_unit * 10.0, market.get_budget());
+        let bought = market.claim_order(trader_name, id);
+        assert_eq!(Some(Good::new(GoodKind::USD, 10.0)), bought.ok());
+        assert!(market.get_order(id).is_none(), "Claimed order is removed");
+
+        // an ask far above the price is never filled and can be cancelled
+        let mut yen = Good::new(GoodKind::YEN, 100.0);
+        let id = market
+            .place_order(
+                trader_name,
+                OrderSide::Ask,
+                GoodKind::YEN,
+                100.0,
+                1_000.0,
+                &mut yen,
+            )
+            .unwrap();
+        assert_eq!(0.0, yen.get_qty());
+        let escrow = market.cancel_order(trader_name, id);
+        assert_eq!(Some(Good::new(GoodKind::YEN, 100.0)), escrow.ok());
+    }
+
+    /// Collects the kinds of all events it is notified of
+    struct EventRecorder(Rc<RefCell<Vec<EventKind>>>);
+
+    impl Notifiable for EventRecorder {
+        fn add_subscriber(&mut self, _subscriber: Box<dyn Notifiable>) {}
+
+        fn on_event(&mut self, event: Event) {
+            self.0.borrow_mut().push(event.kind);
+        }
+    }
+
+    #[test]
+    fn test_filled_orders_are_notified_later() {
+        let mut market = SGX::builder()
+            .quantities(100_000.0, 100_000.0, 100_000.0, 100_000.0)
+            .order_book(true)
+            .logger(Logger::disabled())
+            .build_sgx();
+        // half of the quantity is locked by another trader
+        let bid = market.get_buy_price(GoodKind::USD, 50_000.0).unwrap();
+        market
+            .lock_buy(GoodKind::USD, 50_000.0, bid, "OTHER_TRADER".to_string())
+            .unwrap();
+        let events = Rc::new(RefCell::new(Vec::new()));
+        market.add_subscriber(Box::new(EventRecorder(Rc::clone(&events))));
+
+        // bid for the whole free quantity, far above the price
+        let price_per_unit = bid / 50_000.0 * 2.0;
+        let mut cash = Good::new(DEFAULT_GOOD_KIND, bid * 2.0);
+        let id = market
+            .place_order(
+                "TEST_TRADER",
+                OrderSide::Bid,
+                GoodKind::USD,
+                50_000.0,
+                price_per_unit,
+                &mut cash,
+            )
+            .unwrap();
+        wait_days(&mut market, 1);
+        assert_eq!(
+            OrderStatus::Filled,
+            market.get_order(id).unwrap().status,
+            "Bid for exactly the free quantity must be filled"
+        );
+        assert!(
+            events.borrow().is_empty(),
+            "Subscribers must not be notified during on_event"
+        );
+
+        market.flush_pending_events();
+        assert_eq!(1, events.borrow().len());
+        assert!(matches!(events.borrow()[0], EventKind::Bought));
+        market.flush_pending_events();
+        assert_eq!(1, events.borrow().len(), "Events are only sent once");
+    }
+
+    #[test]
+    fn test_limit_orders_disabled() {
+        let mut market = SGX::new_with_logger(Logger::disabled(), 1000.0, 1000.0, 1000.0, 1000.0);
+        let mut cash = Good::new(DEFAULT_GOOD_KIND, 1_000.0);
+        let res = market.place_order(
+            "TEST_TRADER",
+            OrderSide::Bid,
+            GoodKind::USD,
+            1.0,
+            1.0,
+            &mut cash,
+        );
+        assert_eq!(Err(OrderError::OrderBookDisabled), res);
+        assert_eq!(1_000.0, cash.get_qty(), "Escrow must not be touched");
+    }
+
+    /// Collects the tokens of all cancelled locks
+    struct CancelledTokens(Rc<RefCell<Vec<(LockSide, String)>>>);
+
+    impl LockListener for CancelledTokens {
+        fn on_lock_cancelled(&mut self, side: LockSide, lock: &GoodLock) {
+            self.0
+                .borrow_mut()
+                .push((side, lock.transaction_token.clone()));
+        }
+    }
+
+    #[test]
+    fn test_cancel_lock() {
+        let trader_name = "TEST_TRADER".to_string();
+        let mut market = SGX::builder()
+            .quantities(100_000.0, 100_000.0, 1_000.0, 100_000.0)
+            .revert_price_on_cancel(true)
+            .logger(Logger::disabled())
+            .build_sgx();
+        let cancelled = Rc::new(RefCell::new(Vec::new()));
+        market.add_lock_listener(Box::new(CancelledTokens(cancelled.clone())));
+
+        let price = market.get_buy_price(GoodKind::USD, 900.0).unwrap();
+        let token = market
+            .lock_buy(GoodKind::USD, 900.0, price, trader_name.clone())
+            .unwrap();
+        assert!(
+            market.get_buy_price(GoodKind::USD, 900.0).unwrap() > price,
+            "Lock must increase the price"
+        );
+
+        assert_eq!(Ok(()), market.cancel_lock(token.clone()));
+        assert_eq!(vec![(LockSide::Buy, token.clone())], *cancelled.borrow());
+        let reverted_price = market.get_buy_price(GoodKind::USD, 900.0).unwrap();
+        assert!(
+            (reverted_price - price).abs() < price * 1e-4,
+            "Price {} must be reverted to {}",
+            reverted_price,
+            price
+        );
+        assert!(
+            market
+                .lock_buy(GoodKind::USD, 900.0, reverted_price * 1.01, trader_name)
+                .is_ok(),
+            "Cancelled quantity must be free again"
+        );
+
+        let mut cash = Good::new(DEFAULT_GOOD_KIND, 100_000.0);
+        assert!(
+            matches!(
+                market.buy(token.clone(), &mut cash),
+                Err(BuyError::ExpiredToken { .. })
+            ),
+            "Cancelled token can't be used"
+        );
+        assert!(matches!(
+            market.cancel_lock(token),
+            Err(CancelLockError::ExpiredToken { .. })
+        ));
+        assert!(matches!(
+            market.cancel_lock("unknown".to_string()),
+            Err(CancelLockError::UnrecognizedToken { .. })
+        ));
+    }
+
+    /// Collects the owners and tokens of all expired locks
+    struct ExpiredTokens(Rc<RefCell<Vec<(String, String)>>>);
+
+    impl LockListener for ExpiredTokens {
+        fn on_lock_expired(&mut self, _side: LockSide, lock: &GoodLock) {
+            self.0
+                .borrow_mut()
+                .push((lock.trader_name.clone(), lock.transaction_token.clone()));
+        }
+    }
+
+    #[test]
+    fn test_lock_expiry() {
+        let trader_name = "TEST_TRADER".to_string();
+        let mut market = SGX::builder()
+            .quantities(100_000.0, 100_000.0, 100_000.0, 100_000.0)
+            .lock_expiry_in_days(4)
+            .logger(Logger::disabled())
+            .build_sgx();
+        let expired = Rc::new(RefCell::new(Vec::new()));
+        market.add_lock_listener(Box::new(ExpiredTokens(expired.clone())));
+
+        let bid = market.get_buy_price(GoodKind::USD, 10.0).unwrap();
+        let token = market
+            .lock_buy(GoodKind::USD, 10.0, bid, trader_name.clone())
+            .unwrap();
+        assert_eq!(Some(4), market.get_remaining_lock_days(&token));
+        assert_eq!(None, market.get_remaining_lock_days("unknown"));
+
+        for remaining_days in (1..4).rev() {
+            wait_days(&mut market, 1);
+            assert_eq!(Some(remaining_days), market.get_remaining_lock_days(&token));
+        }
+        assert!(expired.borrow().is_empty(), "Lock can't have expired yet");
+
+        wait_days(&mut market, 1);
+        assert_eq!(None, market.get_remaining_lock_days(&token));
+        assert_eq!(vec![(trader_name, token)], *expired.borrow());
+    }
+
+    #[test]
+    fn test_expired_token_retention() {
+        let mut market = SGX::builder()
+            .quantities(100_000.0, 100_000.0, 100_000.0, 100_000.0)
+            .expired_token_retention_in_days(2)
+            .logger(Logger::disabled())
+            .build_sgx();
+        let bid = market.get_buy_price(GoodKind::USD, 10.0).unwrap();
+        let token = market
+            .lock_buy(GoodKind::USD, 10.0, bid, "TEST_TRADER".to_string())
+            .unwrap();
+        market.cancel_lock(token.clone()).unwrap();
+
+        wait_days(&mut market, 1);
+        let mut cash = Good::new(DEFAULT_GOOD_KIND, bid);
+        assert!(matches!(
+            market.buy(token.clone(), &mut cash),
+            Err(BuyError::ExpiredToken { .. })
+        ));
+
+        wait_days(&mut market, 1);
+        assert!(
+            matches!(
+                market.buy(token, &mut cash),
+                Err(BuyError::UnrecognizedToken { .. })
+            ),
+            "Token must be forgotten after the retention window"
+        );
+    }
+
+    #[test]
+    fn test_consensus_prices() {
+        // the linear spread pricing does not react to events on its own
+        let build = |strength: f32| {
+            SGX::builder()
+                .quantities(100_000.0, 100_000.0, 100_000.0, 100_000.0)
+                .pricing_model(Box::new(LinearSpreadPricing::default()))
+                .consensus(0.5, strength)
+                .logger(Logger::disabled())
+                .build_sgx()
+        };
+        let mut default_market = build(0.0);
+        let mut market = build(0.5);
+        let our_price = market.get_buy_price(GoodKind::USD, 10.0).unwrap();
+        let event = Event {
+            kind: EventKind::Bought,
+            good_kind: GoodKind::USD,
+            quantity: 10.0,
+            price: our_price * 0.8,
+        };
+        for _ in 0..5 {
+            default_market.on_event(event.clone());
+            market.on_event(event.clone());
+        }
+
+        let estimate = market.get_consensus(&GoodKind::USD).unwrap().buy.unwrap();
+        assert_eq!(5, estimate.samples);
+        assert!((estimate.unit_price - our_price * 0.08).abs() < our_price * 1e-6);
+        assert_eq!(
+            our_price,
+            default_market.get_buy_price(GoodKind::USD, 10.0).unwrap(),
+            "Quotes must not follow the consensus by default"
+        );
+
+        let buy_deviation = |market: &SGX| {
+            market.get_consensus_deviations()[0]
+                .buy_deviation
+                .expect("Buy deviation must be reported")
+        };
+        assert!((buy_deviation(&default_market) - 0.25).abs() < 1e-4);
+        assert!(
+            buy_deviation(&market).abs() < 0.25 / 16.0,
+            "Quote must be pulled towards the consensus, deviation: {}",
+            buy_deviation(&market)
+        );
+        assert!(market.get_consensus_deviations()[0]
+            .sell_deviation
+            .is_none());
+    }
+
+    #[test]
+    fn test_quotes() {
+        let market = SGX::new_with_logger(
+            Logger::disabled(),
+            100_000.0,
+            100_000.0,
+            100_000.0,
+            100_000.0,
+        );
+        let quote = market.get_buy_quote(GoodKind::USD, 1_000.0).unwrap();
+        assert_eq!(
+            market.get_buy_price(GoodKind::USD, 1_000.0).unwrap(),
+            quote.price
+        );
+        assert!(quote.margin > 0.0 && quote.slippage > 0.0);
+        let quote = market.get_sell_quote(GoodKind::USD, 1_000.0).unwrap();
+        assert_eq!(
+            market.get_sell_price(GoodKind::USD, 1_000.0).unwrap(),
+            quote.price
+        );
+        assert!(matches!(
+            market.get_buy_quote(GoodKind::USD, -1.0),
+            Err(MarketGetterError::NonPositiveQuantityAsked)
+        ));
+    }
+
+    #[test]
+    fn test_buy_quote_of_locked_good() {
+        let mut market = SGX::builder()
+            .quantities(100_000.0, 100_000.0, 100_000.0, 100_000.0)
+            .logger(Logger::disabled())
+            .build_sgx();
+        let bid = market.get_buy_price(GoodKind::USD, 10_000.0).unwrap();
+        market
+            .lock_buy(GoodKind::USD, 10_000.0, bid, "FIRST".to_string())
+            .unwrap();
+
+        let res = market.get_buy_quote(GoodKind::USD, 95_000.0);
+        assert!(
+            matches!(
+                res,
+                Err(MarketGetterError::InsufficientGoodQuantityAvailable {
+                    available_good_quantity,
+                    ..
+                }) if available_good_quantity == 90_000.0
+            ),
+            "Locked quantity can't be quoted, got {:?}",
+            res
+        );
+        let base_buy_price = market
+            .snapshot()
+            .get_good(&GoodKind::USD)
+            .unwrap()
+            .metadata
+            .base_buy_price;
+        assert_eq!(
+            DemandPricing::default().get_buy_price(1_000.0, 90_000.0, base_buy_price),
+            market.get_buy_price(GoodKind::USD, 1_000.0).unwrap(),
+            "Price must be calculated on the free quantity"
+        );
+    }
+
+    #[test]
+    fn test_seeded_tokens() {
+        let gen_tokens = |market: &mut SGX| {
+            (0..3)
+                .map(|_| {
+                    let bid = market.get_buy_price(GoodKind::USD, 1.0).unwrap();
+                    let token = market
+                        .lock_buy(GoodKind::USD, 1.0, bid, "TEST_TRADER".to_string())
+                        .unwrap();
+                    market.cancel_lock(token.clone()).unwrap();
+                    token
+                })
+                .collect::<Vec<String>>()
+        };
+        let build_market = |seed: u64| {
+            SGX::builder()
+                .seed(seed)
+                .quantities(1000.0, 1000.0, 1000.0, 1000.0)
+                .logger(Logger::disabled())
+                .build_sgx()
+        };
+
+        let tokens = gen_tokens(&mut build_market(42));
+        assert_eq!(
+            tokens,
+            gen_tokens(&mut build_market(42)),
+            "The same seed must result in the same tokens"
+        );
+        assert_ne!(tokens, gen_tokens(&mut build_market(43)));
+        let mut market = SGX::new_with_logger(Logger::disabled(), 1000.0, 1000.0, 1000.0, 1000.0);
+        assert_ne!(
+            tokens,
+            gen_tokens(&mut market),
+            "Tokens without a seed are not reproducible"
+        );
+    }
+
+    #[test]
+    fn test_lock_limits_per_trader() {
+        let mut market = SGX::new_with_logger(
+            Logger::disabled(),
+            100_000.0,
+            100_000.0,
+            100_000.0,
+            100_000.0,
+        );
+        let mut lock = |kind: GoodKind, trader_name: &str| {
+            let bid = market.get_buy_price(kind, 10.0).unwrap();
+            market.lock_buy(kind, 10.0, bid, trader_name.to_string())
+        };
+
+        // by default, a trader can lock two goods
+        assert!(lock(GoodKind::USD, "FIRST").is_ok());
+        assert!(lock(GoodKind::YEN, "FIRST").is_ok());
+        assert!(lock(GoodKind::USD, "FIRST").is_ok(), "Locked good again");
+        assert!(matches!(
+            lock(GoodKind::YUAN, "FIRST"),
+            Err(LockBuyError::MaxAllowedLocksReached)
+        ));
+        assert!(
+            lock(GoodKind::YUAN, "SECOND").is_ok(),
+            "Other traders must not be blocked"
+        );
+        assert_eq!(
+            Err(LockLimitError::MaxLockedGoodsReached {
+                trader_name: "FIRST".to_string(),
+                locked_goods: 2
+            }),
+            market.check_lock_limit(LockSide::Buy, GoodKind::YUAN, "FIRST")
+        );
+        assert_eq!(
+            Ok(()),
+            market.check_lock_limit(LockSide::Sell, GoodKind::YUAN, "FIRST"),
+            "Sell locks are counted separately"
+        );
+    }
+
+    #[test]
+    fn test_lock_quota_and_global_cap() {
+        let mut market = SGX::builder()
+            .quantities(100_000.0, 100_000.0, 100_000.0, 100_000.0)
+            .max_locks_per_trader(2)
+            .max_locks(3)
+            .logger(Logger::disabled())
+            .build_sgx();
+        let mut lock = |trader_name: &str| {
+            let bid = market.get_buy_price(GoodKind::USD, 10.0).unwrap();
+            market.lock_buy(GoodKind::USD, 10.0, bid, trader_name.to_string())
+        };
+
+        assert!(lock("FIRST").is_ok());
+        assert!(lock("FIRST").is_ok());
+        assert!(matches!(
+            lock("FIRST"),
+            Err(LockBuyError::MaxAllowedLocksReached)
+        ));
+        assert!(lock("SECOND").is_ok());
+        assert!(matches!(
+            lock("SECOND"),
+            Err(LockBuyError::MaxAllowedLocksReached)
+        ));
+
+        assert_eq!(
+            Err(LockLimitError::TraderQuotaReached {
+                trader_name: "FIRST".to_string(),
+                locks: 2
+            }),
+            market.check_lock_limit(LockSide::Sell, GoodKind::YEN, "FIRST")
+        );
+        assert_eq!(
+            Err(LockLimitError::GlobalCapReached {
+                blocking_traders: vec![("FIRST".to_string(), 2), ("SECOND".to_string(), 1)]
+            }),
+            market.check_lock_limit(LockSide::Sell, GoodKind::YEN, "THIRD")
+        );
+
+        // the checked lock returns the traders that hold the locks
+        let bid = market.get_buy_price(GoodKind::USD, 10.0).unwrap();
+        let res = market.lock_buy_checked(GoodKind::USD, 10.0, bid, "THIRD".to_string());
+        match res {
+            Err(CheckedLockError::LockLimit(e)) => assert_eq!(
+                LockLimitError::GlobalCapReached {
+                    blocking_traders: vec![("FIRST".to_string(), 2), ("SECOND".to_string(), 1)]
+                },
+                e
+            ),
+            _ => panic!("Expected the global cap, got {:?}", res),
+        }
+
+        let mut market = SGX::new_with_logger(Logger::disabled(), 1000.0, 1000.0, 1000.0, 1000.0);
+        let res = market.lock_sell_checked(GoodKind::USD, 10.0, -1.0, "THIRD".to_string());
+        assert!(
+            matches!(
+                res,
+                Err(CheckedLockError::Market(
+                    LockSellError::NonPositiveOffer { .. }
+                ))
+            ),
+            "Other errors of the market are kept, got {:?}",
+            res
+        );
+    }
+
+    #[test]
+    fn test_lock_limit_is_logged() {
+        let path = temp_dir().join("sgx_test_lock_limit_is_logged.jsonl");
+        let path = path.to_str().unwrap();
+        let mut market = SGX::builder()
+            .quantities(100_000.0, 100_000.0, 100_000.0, 100_000.0)
+            .max_locks(1)
+            .logger(Logger::with_file(LogFormat::JsonLines, path).unwrap())
+            .build_sgx();
+
+        let bid = market.get_buy_price(GoodKind::USD, 10.0).unwrap();
+        market
+            .lock_buy(GoodKind::USD, 10.0, bid, "FIRST".to_string())
+            .unwrap();
+        assert!(matches!(
+            market.lock_buy(GoodKind::USD, 10.0, bid, "SECOND".to_string()),
+            Err(LockBuyError::MaxAllowedLocksReached)
+        ));
+        drop(market);
+
+        let content = fs::read_to_string(path).unwrap();
+        let _ = fs::remove_file(path);
+        let record = content
+            .lines()
+            .map(|line| serde_json::from_str::<serde_json::Value>(line).unwrap())
+            .last()
+            .unwrap();
+        assert_eq!("lock_buy_error", record["event"]);
+        assert_eq!(
+            "MaxAllowedLocksReached (All locks are held by 'FIRST' (1))", record["error"],
+            "Blocking traders must be logged"
+        );
+    }
+
+    #[test]
+    fn test_market_with_subset_of_goods() {
+        let trader_name = "TEST_TRADER".to_string();
+        let mut market = SGX::builder()
+            .goods(vec![
+                (GoodKind::EUR, 10_000.0, 1.0),
+                (GoodKind::YEN, 10_000.0, 150.0),
+            ])
+            .logger(Logger::disabled())
+            .build_sgx();
+        let kinds = market
+            .get_goods()
+            .iter()
+            .map(|l| l.good_kind)
+            .collect::<Vec<GoodKind>>();
+        assert_eq!(vec![GoodKind::EUR, GoodKind::YEN], kinds);
+
+        match market.get_buy_price(GoodKind::USD, 10.0) {
+            Err(MarketGetterError::InsufficientGoodQuantityAvailable {
+                available_good_quantity,
+                ..
+            }) => assert_eq!(0.0, available_good_quantity),
+            res => panic!("USD is not listed, got {:?}", res),
+        }
+        assert!(market.get_sell_price(GoodKind::USD, 10.0).is_err());
+        assert!(matches!(
+            market.lock_buy(GoodKind::USD, 10.0, 100.0, trader_name.clone()),
+            Err(LockBuyError::InsufficientGoodQuantityAvailable { .. })
+        ));
+        assert!(matches!(
+            market.lock_sell(GoodKind::USD, 10.0, 1.0, trader_name.clone()),
+            Err(LockSellError::OfferTooHigh { .. })
+        ));
+
+        // the listed good can be traded
+        let bid = market.get_buy_price(GoodKind::YEN, 10.0).unwrap();
+        let token = market
+            .lock_buy(GoodKind::YEN, 10.0, bid, trader_name)
+            .unwrap();
+        let mut cash = Good::new(DEFAULT_GOOD_KIND, bid);
+        assert_eq!(
+            Some(Good::new(GoodKind::YEN, 10.0)),
+            market.buy(token, &mut cash).ok()
+        );
+        assert_eq!(10_000.0 + bid, market.get_budget());
+    }
+
+    /// Returns a random kind, not every kind is listed by every market
+    fn random_kind(rng: &mut StdRng) -> GoodKind {
+        match rng.gen_range(0..4) {
+            0 => GoodKind::EUR,
+            1 => GoodKind::YEN,
+            2 => GoodKind::USD,
+            _ => GoodKind::YUAN,
+        }
+    }
+
+    /// Returns a random quantity or price up to the given max. Some of them can't be traded
+    /// (zero, negative, NaN or infinite).
+    fn random_amount(rng: &mut StdRng, max: f32) -> f32 {
+        match rng.gen_range(0..10) {
+            0 => 0.0,
+            1 => -rng.gen_range(0.0..max),
+            2 => f32::NAN,
+            3 => f32::INFINITY,
+            _ => rng.gen_range(0.0..max),
+        }
+    }
+
+    /// Returns one of the given tokens (they may be used or expired already) or an unknown one
+    fn random_token(rng: &mut StdRng, tokens: &[String]) -> String {
+        if tokens.is_empty() || rng.gen_bool(0.2) {
+            "UNKNOWN_TOKEN".to_string()
+        } else {
+            tokens[rng.gen_range(0..tokens.len())].clone()
+        }
+    }
+
+    /// Checks that the goods of the market can still be traded
+    fn assert_market_invariants(market: &SGX) {
+        for good in market.snapshot().goods {
+            assert!(
+                good.quantity.is_finite() && good.quantity >= 0.0,
+                "Bad quantity of {:?}: {}",
+                good.kind,
+                good.quantity
+            );
+            let meta = good.metadata;
+            assert!(
+                meta.base_buy_price.is_finite() && meta.base_buy_price > 0.0,
+                "Bad buy price of {:?}: {}",
+                good.kind,
+                meta.base_buy_price
+            );
+            assert!(
+                meta.base_sell_price.is_finite() && meta.base_sell_price > 0.0,
+                "Bad sell price of {:?}: {}",
+                good.kind,
+                meta.base_sell_price
+            );
+        }
+        let budget = market.get_budget();
+        assert!(budget.is_finite() && budget >= 0.0, "Bad budget: {budget}");
+    }
+
+    /// Calls the market with random arguments, the market must neither panic nor end up in a
+    /// state that can't be traded anymore
+    fn drive_random_calls(market: &mut SGX, rng: &mut StdRng, calls: usize) {
+        let traders = ["FIRST", "SECOND", "THIRD"];
+        let mut buy_tokens = Vec::new();
+        let mut sell_tokens = Vec::new();
+        let mut order_ids = Vec::new();
+
+        for _ in 0..calls {
+            let kind = random_kind(rng);
+            let quantity = random_amount(rng, 2_000.0);
+            let price = random_amount(rng, 5_000.0);
+            let trader_name = traders[rng.gen_range(0..traders.len())].to_string();
+            match rng.gen_range(0..11) {
+                0 => {
+                    let _ = market.get_buy_price(kind, quantity);
+                }
+                1 => {
+                    let _ = market.get_sell_price(kind, quantity);
+                }
+                2 => {
+                    // bid the quoted price most of the time, so that locks are accepted
+                    let bid = match market.get_buy_price(kind, quantity) {
+                        Ok(quote) if rng.gen_bool(0.7) => quote,
+                        _ => price,
+                    };
+                    if let Ok(token) = market.lock_buy(kind, quantity, bid, trader_name) {
+                        buy_tokens.push(token);
+                    }
+                }
+                3 => {
+                    let offer = match market.get_sell_price(kind, quantity) {
+                        Ok(quote) if rng.gen_bool(0.7) => quote,
+                        _ => price,
+                    };
+                    if let Ok(token) = market.lock_sell(kind, quantity, offer, trader_name) {
+                        sell_tokens.push(token);
+                    }
+                }
+                4 => {
+                    let token = random_token(rng, &buy_tokens);
+                    let cash_kind = if rng.gen_bool(0.8) {
+                        DEFAULT_GOOD_KIND
+                    } else {
+                        kind
+                    };
+                    let mut cash = Good::new(cash_kind, random_amount(rng, 100_000.0));
+                    let _ = market.buy(token, &mut cash);
+                }
+                5 => {
+                    let token = random_token(rng, &sell_tokens);
+                    let mut good = Good::new(kind, quantity);
+                    let _ = market.sell(token, &mut good);
+                }
+                6 => {
+                    let tokens = if rng.gen_bool(0.5) {
+                        &buy_tokens
+                    } else {
+                        &sell_tokens
+                    };
+                    let token = random_token(rng, tokens);
+                    let _ = market.cancel_lock(token);
+                }
+                7 => {
+                    let event_kind = match rng.gen_range(0..5) {
+                        0 => EventKind::Bought,
+                        1 => EventKind::Sold,
+                        2 => EventKind::LockedBuy,
+                        3 => EventKind::LockedSell,
+                        _ => EventKind::Wait,
+                    };
+                    market.on_event(Event {
+                        kind: event_kind,
+                        good_kind: kind,
+                        quantity,
+                        price,
+                    });
+                }
+                8 => {
+                    let side = if rng.gen_bool(0.5) {
+                        OrderSide::Bid
+                    } else {
+                        OrderSide::Ask
+                    };
+                    let escrow_kind = match side {
+                        OrderSide::Bid => DEFAULT_GOOD_KIND,
+                        OrderSide::Ask => kind,
+                    };
+                    let mut escrow = Good::new(escrow_kind, random_amount(rng, 100_000.0));
+                    let price_per_unit = price / 1_000.0;
+                    if let Ok(id) = market.place_order(
+                        &trader_name,
+                        side,
+                        kind,
+                        quantity,
+                        price_per_unit,
+                        &mut escrow,
+                    ) {
+                        order_ids.push(id);
+                    }
+                }
+                9 => {
+                    let id = order_ids.get(rng.gen_range(0..=order_ids.len())).copied();
+                    let _ = market.cancel_order(&trader_name, id.unwrap_or(u64::MAX));
+                }
+                _ => {
+                    let id = order_ids.get(rng.gen_range(0..=order_ids.len())).copied();
+                    let _ = market.claim_order(&trader_name, id.unwrap_or(u64::MAX));
+                }
+            }
+            assert_market_invariants(market);
+        }
+    }
+
+    #[test]
+    fn test_random_calls_do_not_panic() {
+        for seed in 0..20 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let mut markets = [
+                SGX::builder()
+                    .seed(seed)
+                    .logger(Logger::disabled())
+                    .build_sgx(),
+                // not every good is listed
+                SGX::builder()
+                    .goods(vec![(GoodKind::YEN, 10_000.0, 150.0)])
+                    .order_book(true)
+                    .max_locks_per_trader(3)
+                    .max_locks(5)
+                    .revert_price_on_cancel(true)
+                    .logger(Logger::disabled())
+                    .build_sgx(),
+                // nothing to trade at all
+                SGX::builder()
+                    .quantities(0.0, 0.0, 0.0, 0.0)
+                    .order_book(true)
+                    .logger(Logger::disabled())
+                    .build_sgx(),
+                SGX::builder()
+                    .quantities(5_000.0, 500.0, 500.0, 500.0)
+                    .pricing_model(Box::new(ConstantProductPricing::default()))
+                    .lock_expiry_in_days(3)
+                    .order_book(true)
+                    .logger(Logger::disabled())
+                    .build_sgx(),
+            ];
+            for market in markets.iter_mut() {
+                drive_random_calls(market, &mut rng, 300);
+            }
+        }
+    }
+}
@@ -26,6 +26,29 @@
 //! ```shell
 //! $ trader-cli average-seller sgx smse tase -d 7 -m 10 -c 3000000
 //! ```
+//!
+//! *Run `Stingy` for 7 days on SGX and TASE and print the history and the report (EUR value per
+//! day and PnL metrics) as JSON*
+//! ```shell
+//! $ trader-cli stingy sgx tase -d 7 --report --as-json
+//! ```
+//!
+//! *Run `AverageSeller` for 7 days on SGX with the seed 42. Every run with the same seed has the
+//! same history*
+//! ```shell
+//! $ trader-cli average-seller sgx -d 7 --seed 42
+//! ```
+//!
+//! *Run `Stingy` for 7 days on SGX with the parameters of `stingy.toml` (see the
+//! `trader::strategies::config` module for the format)*
+//! ```shell
+//! $ trader-cli stingy sgx -d 7 --config stingy.toml
+//! ```
+//!
+//! *List all strategies of the registry*
+//! ```shell
+//! $ trader-cli --list-strategies
+//! ```
 
 use chrono::Local;
 use clap::Parser;
@@ -37,9 +60,12 @@ use std::io;
 use std::io::prelude::Write;
 use std::path::PathBuf;
 use std::rc::Rc;
-use trader::trader::{StrategyIdentifier, Trader};
+use trader::strategies::config::StrategyConfig;
+use trader::strategies::registry::StrategyRegistry;
+use trader::trader::Trader;
 use unitn_market_2022::market::Market;
 use SGX::market::sgx::SGX;
+use SGX::market::trader_market::TraderMarket;
 use TASE::TASE;
 use ZSE::market::ZSE;
 
@@ -51,8 +77,9 @@ type MarketRef = Rc<RefCell<dyn Market>>;
 #[clap(about, author, version)]
 pub struct Args {
     /// Name of the strategy the trader is supposed to use.
-    /// Available strategy names: average-seller, stingy.
-    pub strategy: String,
+    /// Available strategy names: average-seller, stingy (see --list-strategies).
+    #[arg(required_unless_present = "list_strategies")]
+    pub strategy: Option<String>,
     /// List of markets the trader should work with.
     /// Available market names: sgx, smse, tase, zse.
     pub markets: Vec<String>,
@@ -79,15 +106,41 @@ pub struct Args {
     /// STRATEGY_NAME-TIMESTAMP.json.
     #[arg(short, long)]
     pub output: Option<PathBuf>,
+    /// Seed for the random decisions of the trader and the random quantities of SGX.
+    /// The same seed reproduces the same run on SGX. Other markets are not seeded.
+    #[arg(short, long)]
+    pub seed: Option<u64>,
+    /// Indicates if the report (EUR value per day, PnL, return, drawdown and volatility)
+    /// should be printed or written next to the history. As JSON, both are exported as
+    /// an object with the keys "history" and "report".
+    #[arg(short, long, default_value_t = false)]
+    pub report: bool,
+    /// Path to a TOML or JSON file with the parameters of the strategies.
+    /// Missing parameters are set to their default.
+    #[arg(long)]
+    pub config: Option<PathBuf>,
+    /// Lists the names of all strategies in the registry and exits.
+    #[arg(long, default_value_t = false)]
+    pub list_strategies: bool,
 }
 
 /// Generates a [`MarketRef`] instance if the given is valid, otherwise
 /// it returns `None`. The market contains random quantities.
+/// If a seed is given, the quantities of SGX are generated with that seed.
+/// SGX is bound to the given trader, so that it only accepts the tokens
+/// of the trader's own locks.
 /// Valid names for markets are: `sgx`, `smse`, `tase`, and `zse`.
-fn gen_market(market_name: &str) -> Option<MarketRef> {
+fn gen_market(market_name: &str, seed: Option<u64>, trader_name: &str) -> Option<MarketRef> {
     let market_name = market_name.to_ascii_lowercase();
     match market_name.as_str() {
-        "sgx" => Some(SGX::new_random()),
+        "sgx" => {
+            let builder = match seed {
+                Some(seed) => SGX::builder().seed(seed),
+                None => SGX::builder(),
+            };
+            let sgx = Rc::new(RefCell::new(builder.build_sgx()));
+            Some(TraderMarket::bind(&sgx, trader_name))
+        }
         "smse" => Some(Smse::new_random()),
         "tase" => Some(TASE::new_random()),
         "zse" => Some(ZSE::new_random()),
@@ -98,7 +151,7 @@ fn gen_market(market_name: &str) -> Option<MarketRef> {
 /// Parses the given market names and returns a [`MarketRef`] if
 /// available. it uses the [`gen_market`] method to
 /// generate a market.
-fn parse_markets(markets: &[String]) -> Vec<MarketRef> {
+fn parse_markets(markets: &[String], seed: Option<u64>, trader_name: &str) -> Vec<MarketRef> {
     let mut market_refs = Vec::new();
     let mut markets = markets
         .iter()
@@ -107,25 +160,15 @@ fn parse_markets(markets: &[String]) -> Vec<MarketRef> {
     // remove duplicates
     markets.dedup();
     for market_name in markets.iter() {
-        if let Some(market) = gen_market(market_name.as_str()) {
+        if let Some(market) = gen_market(market_name.as_str(), seed, trader_name) {
             market_refs.push(market);
         } else {
-            println!("Market '{market_name}' is not available. Try sgx, smse, tase, or zse.");
+            eprintln!("Market '{market_name}' is not available. Try sgx, smse, tase, or zse.");
         }
     }
     market_refs
 }
 
-/// Tries to map the given strategy name to an optional [`StrategyIdentifier`].
-/// Valid strategy names: `average-seller`.
-fn map_strategy_to_id(strategy: &str) -> Option<StrategyIdentifier> {
-    match strategy {
-        "stingy" => Some(StrategyIdentifier::Stingy),
-        "average-seller" => Some(StrategyIdentifier::AverageSeller),
-        _ => None,
-    }
-}
-
 /// Writes the history to the visualizer input path.
 fn write_history(file_path: &PathBuf, history: &String) -> Result<(), io::Error> {
     match File::create(file_path) {
@@ -145,41 +188,88 @@ fn main() {
     let env = Env::default().filter_or("MY_LOG_LEVEL", args.log_level);
     let _ = env_logger::try_init_from_env(env);
 
-    let strategy_id = map_strategy_to_id(args.strategy.as_str());
-    if let Some(strategy_id) = strategy_id {
-        let markets = parse_markets(&args.markets);
+    let registry = StrategyRegistry::new();
+    if args.list_strategies {
+        for name in registry.get_names() {
+            println!("{name}");
+        }
+        return;
+    }
+
+    let strategy = args.strategy.unwrap_or_default();
+    if registry.contains(&strategy) {
+        let trader_name = registry.get_trader_name(&strategy).unwrap_or(&strategy);
+        let markets = parse_markets(&args.markets, args.seed, trader_name);
         if markets.is_empty() {
-            println!("At least one market is required");
+            eprintln!("At least one market is required");
             std::process::exit(1);
         }
 
-        let trader = Trader::from(strategy_id, args.capital, markets);
-        trader.apply_strategy(args.days, args.minute_interval);
+        let config = match args.config {
+            Some(path) => match StrategyConfig::from_file(path) {
+                Ok(config) => config,
+                Err(e) => {
+                    eprintln!("Could not load the config: {e}");
+                    std::process::exit(1);
+                }
+            },
+            None => StrategyConfig::default(),
+        };
+
+        let trader = Trader::try_from_registry(
+            &registry,
+            &strategy,
+            args.capital,
+            markets,
+            args.seed,
+            &config,
+        );
+        let trader = match trader {
+            Ok(trader) => trader,
+            Err(e) => {
+                eprintln!("Could not create the trader: {e}");
+                std::process::exit(1);
+            }
+        };
+        if let Err(e) = trader.try_apply_strategy(args.days, args.minute_interval) {
+            eprintln!("Could not run the trader: {e}");
+            std::process::exit(1);
+        }
 
         if let Some(mut output_path) = args.output {
             if output_path.is_dir() {
-                let filename = format!("{}-{}.json", args.strategy, Local::now().timestamp());
+                let filename = format!("{}-{}.json", strategy, Local::now().timestamp());
                 let filename = PathBuf::from(filename);
                 output_path = output_path.join(filename);
             }
 
-            let history = trader.get_history_as_json();
-            match write_history(&output_path, &history) {
+            let (name, json) = if args.report {
+                ("history and report", trader.get_export_as_json())
+            } else {
+                ("history", trader.get_history_as_json())
+            };
+            match write_history(&output_path, &json) {
                 Ok(_) => {
                     let output = output_path.as_os_str().to_str().unwrap_or_default();
-                    println!("Successfully wrote history to {output}");
+                    println!("Successfully wrote {name} to {output}");
                 }
-                Err(e) => println!("Error while writing history as JSON: {}", e),
+                Err(e) => eprintln!("Error while writing {name} as JSON: {}", e),
             }
+        } else if args.report && args.as_json {
+            println!("{}", trader.get_export_as_json());
+        } else if args.report {
+            println!("{:?}", trader.get_history());
+            println!("{:?}", trader.get_report());
         } else if args.as_json {
             println!("{}", trader.get_history_as_json());
         } else {
             println!("{:?}", trader.get_history());
         }
     } else {
-        println!(
-            "No strategy called '{}' available. Try: average-seller, stingy.",
-            args.strategy
+        eprintln!(
+            "No strategy called '{}' available. Try: {}.",
+            strategy,
+            registry.get_names().join(", ")
         );
         std::process::exit(1);
     }
@@ -187,13 +277,15 @@ fn main() {
 
 #[cfg(test)]
 mod tests {
-    use crate::{gen_market, map_strategy_to_id, parse_markets};
-    use trader::trader::StrategyIdentifier;
+    use crate::{gen_market, parse_markets};
+    use trader::strategies::registry::StrategyRegistry;
+
+    const TRADER_NAME: &str = "TEST_TRADER";
 
     #[test]
     fn test_parse_markets() {
         // Test with empty slice
-        let markets = parse_markets(&[]);
+        let markets = parse_markets(&[], None, TRADER_NAME);
         assert_eq!(
             0,
             markets.len(),
@@ -202,7 +294,7 @@ mod tests {
 
         // Test with no existing markets
         let names: Vec<String> = Vec::from(["a".to_string(), "b".to_string()]);
-        let markets = parse_markets(&names);
+        let markets = parse_markets(&names, None, TRADER_NAME);
         assert_eq!(
             0,
             markets.len(),
@@ -212,7 +304,7 @@ mod tests {
         // Test with multiple existing markets
         let names: Vec<String> =
             Vec::from(["sgx".to_string(), "sgx".to_string(), "smse".to_string()]);
-        let markets = parse_markets(&names);
+        let markets = parse_markets(&names, None, TRADER_NAME);
         assert_eq!(2, markets.len(), "There shouldn't be any duplicates");
 
         // Test with all available markets
@@ -222,45 +314,34 @@ mod tests {
             "tase".to_string(),
             "zse".to_string(),
         ]);
-        let markets = parse_markets(&names);
+        let markets = parse_markets(&names, None, TRADER_NAME);
         assert_eq!(4, markets.len(), "There must be {} markets", names.len());
     }
 
     #[test]
-    fn test_map_strategy_to_id() {
-        // test with empty str
-        let id = map_strategy_to_id("");
-        assert_eq!(
-            None, id,
-            "No identifier should be returned for an empty str"
-        );
+    fn test_registered_strategies() {
+        let registry = StrategyRegistry::new();
 
-        // test non existing strategy
-        let strategy = "NON-EXISTING-STRATEGY";
-        let id = map_strategy_to_id(strategy);
-        assert_eq!(
-            None, id,
-            "No identifier should be returned for the strategy {}",
-            strategy
+        // test with empty str
+        assert!(
+            !registry.contains(""),
+            "No strategy should be registered for an empty str"
         );
 
-        // test existing strategy
-        let strategy = "average-seller";
-        let expected = StrategyIdentifier::AverageSeller;
-        let id = map_strategy_to_id(strategy);
-        assert_eq!(
-            Some(expected.clone()),
-            id,
-            "The id for the strategy '{}' must be '{:?}'",
-            strategy,
-            expected
-        );
+        // test the documented strategies
+        for strategy in ["average-seller", "stingy"] {
+            assert!(
+                registry.contains(strategy),
+                "The strategy '{}' must be registered",
+                strategy
+            );
+        }
     }
 
     #[test]
     fn test_market_factory_gen_market() {
         // test with empty str
-        let market = gen_market("");
+        let market = gen_market("", None, TRADER_NAME);
         assert!(
             market.is_none(),
             "There should be no market for an empty name"
@@ -268,7 +349,7 @@ mod tests {
 
         // test with non known name
         let market_name = "NON-EXISTING";
-        let market = gen_market(market_name);
+        let market = gen_market(market_name, None, TRADER_NAME);
         assert!(
             market.is_none(),
             "There should be no market generated for unknown name '{}'",
@@ -278,7 +359,7 @@ mod tests {
         // test all known market names
         let known_names = vec!["sgx", "smse", "tase", "zse"];
         for market_name in known_names {
-            let market = gen_market(market_name);
+            let market = gen_market(market_name, None, TRADER_NAME);
             assert!(
                 market.is_some(),
                 "There must be a market generated for name '{}'",
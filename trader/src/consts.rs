@@ -1,2 +1,5 @@
 pub const TRADER_NAME_AVERAGE_SELLER: &str = "TheAverageSellerTrader";
 pub const TRADER_NAME_STINGY: &str = "TheStingy";
+/// Names of the strategies in the [`crate::strategies::registry::StrategyRegistry`]
+pub const STRATEGY_NAME_AVERAGE_SELLER: &str = "average-seller";
+pub const STRATEGY_NAME_STINGY: &str = "stingy";
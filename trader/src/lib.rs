@@ -23,6 +23,8 @@
 //!
 //! let history = trader.get_history(); // get the history for further computations
 //! let json = trader.get_history_as_json(); // or get the history as JSON string
+//! let ledger = trader.get_ledger_as_json(); // every lock, buy and sell as JSON string
+//! let report = trader.get_report(); // EUR value per day and PnL metrics
 //! ```
 //!
 //! # Available Stratagies
@@ -69,46 +71,45 @@
 //!
 //! ### Step 2
 //!
-//! Create a new identifier for your trader by extending the enum
-//! `StrategyIdentifier` at `/src/trader/mod.rs`:
-//!
-//! ```rust
-//! enum StrategyIdentifier {
-//!     Most_Simple,
-//!     YOUR_NEW_TRADER, // Add your new trader
-//! }
-//! ```
-//!
-//! ### Step 3
-//!
-//! Define the name of your strategy at `/src/consts.rs`. The const name should
-//! start with *TRADER_NAME_* to keep consistency. For example:
+//! Define the name of your strategy and the name of its trader at
+//! `/src/consts.rs`. The const names should start with *STRATEGY_NAME_*
+//! and *TRADER_NAME_* to keep consistency. For example:
 //!
 //! ```rust
+//! pub const STRATEGY_NAME_MOST_SIMPLE: &str = "most-simple";
 //! pub const TRADER_NAME_MOST_SIMPLE: &str = "TheMostSimpleTrader";
 //! ```
 //!
-//! Then, add the name to `get_name_for_strategy` at `/src/trader/mod.rs`.
-//! For example:
+//! Then, register your strategy in `StrategyRegistry::new` at
+//! `/src/strategies/registry.rs`. For example:
 //!
-//! ```rust
-//! fn get_name_for_strategy(id: StrategyIdentifier) -> String {
-//!     match id {
-//!         StrategyIdentifier::Most_Simple => TRADER_NAME_MOST_SIMPLE.to_string(),
-//!         StrategyIdentifier::YOUR_NEW_TRADER => TRADER_NAME_YOUR_NEW_TRADER.to_string(), // Add your new trader
-//!     }
-//! }
+//! ```rust,ignore
+//! registry.register(
+//!     STRATEGY_NAME_MOST_SIMPLE,
+//!     TRADER_NAME_MOST_SIMPLE,
+//!     |markets, trader_name| Box::new(MostSimpleStrategy::new(markets, trader_name)),
+//! );
 //! ```
 //!
-//! Lastly, at `/src/trader/mod.rs` extend the `test_get_name_for_strategy` test
-//! case with your name:
-//!
-//! ```rust
-//! let possible_strategies = [
-//!     (StrategyIdentifier::Most_Simple, TRADER_NAME_MOST_SIMPLE),
-//!     (StrategyIdentifier::YOUR_NEW_TRADER, TRADER_NAME_YOUR_NEW_TRADER), // Add your new trader
-//! ];
-//! // ...
+//! Now, the strategy can be used by its name, e.g. with
+//! `trader-cli most-simple sgx`. Optionally, add an identifier to the enum
+//! `StrategyIdentifier` at `/src/trader/mod.rs` and return the name in
+//! `StrategyIdentifier::get_strategy_name`.
+//!
+//! ### Strategies of other crates
+//!
+//! A strategy doesn't have to be part of this crate. Other crates can
+//! register their own strategies at a `StrategyRegistry` and create a
+//! trader with it:
+//!
+//! ```rust,ignore
+//! let mut registry = StrategyRegistry::new();
+//! registry.register("in-house", "TheInHouseTrader", |markets, trader_name| {
+//!     Box::new(InHouseStrategy::new(markets, trader_name))
+//! });
+//! let config = StrategyConfig::default();
+//! let trader =
+//!     Trader::try_from_registry(&registry, "in-house", 1_000_000.0, markets, None, &config)?;
 //! ```
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -21,7 +21,7 @@
 //!
 //! To solve the second problem, the strategy follows a more static approach.
 //! It is allowed to pay at max. 30% of the owned EUR quantity. 30% because there are 3 different
-//! goods to buy.
+//! goods to buy. The share can be changed with [`AverageSellerConfig::eur_budget`].
 //!
 //! ## 3. How much do we buy?
 //!
@@ -52,11 +52,15 @@
 //!
 //! The problem that arise with this strategy is, how does the strategy find the highest quantity
 //! to sell, for the highest profit.
+use crate::strategies::config::StrategyConfig;
 use crate::strategies::strategy::Strategy;
+use crate::trader::ledger::{Ledger, LedgerRef, Operation, Transaction, TransactionResult};
 use crate::MarketRef;
 use log::{info, warn};
+use rand::rngs::StdRng;
 use rand::seq::SliceRandom;
-use rand::thread_rng;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
 use std::cell::{RefCell, RefMut};
 use std::collections::HashMap;
@@ -99,6 +103,44 @@ impl Payment {
     }
 }
 
+/// Parameters of the `AverageSellerStrategy`. Every parameter that is missing in a config file
+/// is set to its default.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AverageSellerConfig {
+    /// Maximum allowed difference between sell and buy operations (default: 5)
+    pub max_diff_count_operations: u32,
+    /// Share of the owned EUR that can be spent on a single buy (default: 0.3, because there are
+    /// 3 different goods to buy)
+    pub eur_budget: f32,
+    /// Quantities above this threshold are tried in steps of `large_quantity_step`
+    /// (default: 1.000.000)
+    pub large_quantity_threshold: i32,
+    /// Step size for large quantities (default: 1000)
+    pub large_quantity_step: usize,
+    /// Quantities above this threshold are tried in steps of `medium_quantity_step`
+    /// (default: 100.000)
+    pub medium_quantity_threshold: i32,
+    /// Step size for medium quantities (default: 100)
+    pub medium_quantity_step: usize,
+    /// Step size for all other quantities (default: 10)
+    pub small_quantity_step: usize,
+}
+
+impl Default for AverageSellerConfig {
+    fn default() -> Self {
+        Self {
+            max_diff_count_operations: 5,
+            eur_budget: 0.3,
+            large_quantity_threshold: 1_000_000,
+            large_quantity_step: 1000,
+            medium_quantity_threshold: 100_000,
+            medium_quantity_step: 100,
+            small_quantity_step: 10,
+        }
+    }
+}
+
 /// The implementation of the `AverageSellerStrategy`.
 pub struct AverageSellerStrategy {
     /// Name of the trader using this strategy
@@ -119,8 +161,12 @@ pub struct AverageSellerStrategy {
     buy_count: RefCell<u32>,
     /// Number of sell operations
     sell_count: RefCell<u32>,
-    /// Maximum allowed difference between sell and buy operations
-    max_diff_count_operations: u32,
+    /// Parameters of this strategy
+    config: AverageSellerConfig,
+    /// Random number generator for all random decisions
+    rng: RefCell<StdRng>,
+    /// Ledger all operations are recorded to
+    ledger: LedgerRef,
 }
 
 /// Buying methods
@@ -139,7 +185,7 @@ impl AverageSellerStrategy {
         let sell_count = *self.sell_count.borrow();
         let buy_count = *self.buy_count.borrow();
         let diff = sell_count.abs_diff(buy_count);
-        diff <= self.max_diff_count_operations
+        diff <= self.config.max_diff_count_operations
     }
 
     /// Returns an adequate bid for the wanted good and the max. available EUR quantity.
@@ -261,7 +307,7 @@ impl AverageSellerStrategy {
     fn find_good_to_lock_buy(&self, inventory: &[Good]) -> GoodKind {
         // shuffle the inventory first, maybe all good are empty
         let mut shuffled_inventory = inventory.to_owned();
-        shuffled_inventory.shuffle(&mut thread_rng());
+        shuffled_inventory.shuffle(&mut *self.rng.borrow_mut());
 
         shuffled_inventory
             .iter()
@@ -287,6 +333,12 @@ impl AverageSellerStrategy {
                 bid.price,
                 self.trader_name.clone(),
             );
+            self.record(
+                Operation::LockBuy,
+                bid,
+                token.as_ref().ok().cloned(),
+                TransactionResult::from(&token),
+            );
             if let Ok(token) = token {
                 info!(
                     "Locked for buy: good {} {} for {} EUR at market {}",
@@ -309,10 +361,11 @@ impl AverageSellerStrategy {
             .get_good_for_kind(&GoodKind::EUR, inventory)
             .unwrap()
             .get_qty();
-        let adequate_bids =
-            self.find_adequate_bids(&kind_to_buy, eur_qty * 0.3, |market, max_eur, kind| {
-                self.find_adequate_bid(market, max_eur, kind)
-            });
+        let adequate_bids = self.find_adequate_bids(
+            &kind_to_buy,
+            eur_qty * self.config.eur_budget,
+            |market, max_eur, kind| self.find_adequate_bid(market, max_eur, kind),
+        );
         // 3. Find cheapest bid among adequate bids for kind
         let cheapest_bids = self.filter_cheapest_bid(&adequate_bids);
         // 4. Lock cheapest bid
@@ -348,6 +401,12 @@ impl AverageSellerStrategy {
                 .get_mut_good_for_kind(&GoodKind::EUR, inventory)
                 .unwrap();
             let bought_good = market.buy(token.clone(), eur);
+            self.record(
+                Operation::Buy,
+                bid,
+                Some(token.clone()),
+                TransactionResult::from(&bought_good),
+            );
             if let Ok(bought_good) = bought_good {
                 info!(
                     "Bought good {} {} for {} EUR at market {}",
@@ -538,6 +597,12 @@ impl AverageSellerStrategy {
             self.trader_name.clone(),
         );
         let market_name = market.get_name().to_string();
+        self.record(
+            Operation::LockSell,
+            &offer,
+            token.as_ref().ok().cloned(),
+            TransactionResult::from(&token),
+        );
 
         match token {
             Ok(token) => {
@@ -618,6 +683,12 @@ impl AverageSellerStrategy {
                 .unwrap();
             let old_quantity = good.get_qty();
             let cash = market.sell(token.clone(), good);
+            self.record(
+                Operation::Sell,
+                offer,
+                Some(token.clone()),
+                TransactionResult::from(&cash),
+            );
             if let Ok(cash) = cash {
                 let new_quantity = old_quantity - good.get_qty();
                 info!(
@@ -666,15 +737,37 @@ impl AverageSellerStrategy {
 
 /// Helper methods
 impl AverageSellerStrategy {
+    /// Records an operation for the given bid or offer to the ledger.
+    fn record(
+        &self,
+        operation: Operation,
+        payment: &Payment,
+        token: Option<String>,
+        result: TransactionResult,
+    ) {
+        self.ledger.borrow_mut().record(Transaction {
+            operation,
+            market: payment.market_name.clone(),
+            kind: payment.good_kind,
+            quantity: payment.quantity,
+            price: payment.price,
+            token,
+            result,
+        });
+    }
+
     /// This method returns an adequate step size to decrease a [`Good`] quantity.
+    /// The step size is at least 1.
     fn get_quantity_steps(&self, quantity: i32) -> usize {
-        if quantity > 1_000_000 {
-            1000
-        } else if quantity > 100_000 {
-            100
+        let config = &self.config;
+        let steps = if quantity > config.large_quantity_threshold {
+            config.large_quantity_step
+        } else if quantity > config.medium_quantity_threshold {
+            config.medium_quantity_step
         } else {
-            10
-        }
+            config.small_quantity_step
+        };
+        steps.max(1)
     }
 
     /// Builds a default buy history that contains all tradable goods.
@@ -748,10 +841,24 @@ impl Strategy for AverageSellerStrategy {
             sold_tokens: RefCell::new(Vec::new()),
             sell_count: RefCell::new(0),
             buy_count: RefCell::new(0),
-            max_diff_count_operations: 5,
+            config: AverageSellerConfig::default(),
+            rng: RefCell::new(StdRng::from_entropy()),
+            ledger: Rc::new(RefCell::new(Ledger::default())),
         }
     }
 
+    fn set_seed(&mut self, seed: u64) {
+        self.rng = RefCell::new(StdRng::seed_from_u64(seed));
+    }
+
+    fn set_ledger(&mut self, ledger: LedgerRef) {
+        self.ledger = ledger;
+    }
+
+    fn set_config(&mut self, config: &StrategyConfig) {
+        self.config = config.average_seller.clone();
+    }
+
     fn get_markets(&self) -> &Vec<MarketRef> {
         self.markets.borrow()
     }
@@ -796,7 +903,10 @@ impl Strategy for AverageSellerStrategy {
 
 #[cfg(test)]
 mod tests {
-    use crate::strategies::average_seller_strategy::{AverageSellerStrategy, Payment};
+    use crate::strategies::average_seller_strategy::{
+        AverageSellerConfig, AverageSellerStrategy, Payment,
+    };
+    use crate::strategies::config::StrategyConfig;
     use crate::strategies::strategy::Strategy;
     use crate::MarketRef;
     use smse::Smse;
@@ -1186,6 +1296,31 @@ mod tests {
         assert_eq!(GoodKind::YUAN, kind, "Kind must be YUAN");
     }
 
+    #[test]
+    fn test_get_quantity_steps() {
+        let mut strategy = AverageSellerStrategy::new(vec![], "TRADER_NAME");
+        assert_eq!(1000, strategy.get_quantity_steps(2_000_000));
+        assert_eq!(100, strategy.get_quantity_steps(200_000));
+        assert_eq!(10, strategy.get_quantity_steps(2_000));
+
+        let config = StrategyConfig {
+            average_seller: AverageSellerConfig {
+                medium_quantity_threshold: 1_000,
+                medium_quantity_step: 50,
+                small_quantity_step: 0,
+                ..AverageSellerConfig::default()
+            },
+            ..StrategyConfig::default()
+        };
+        strategy.set_config(&config);
+        assert_eq!(50, strategy.get_quantity_steps(2_000));
+        assert_eq!(
+            1,
+            strategy.get_quantity_steps(200),
+            "The step size must be at least 1"
+        );
+    }
+
     #[test]
     fn test_allowed_to_buy() {
         let trader_name = "TRADER_NAME";
@@ -0,0 +1,206 @@
+//! The config contains the parameters of all strategies of this crate, one section per strategy.
+//! It can be loaded from a TOML or JSON file, so strategies can be tuned without recompiling.
+//! Every parameter (or section) that is missing is set to its default:
+//!
+//! ```toml
+//! [average_seller]
+//! max_diff_count_operations = 10
+//! eur_budget = 0.2
+//!
+//! [stingy]
+//! percentage_buy = 0.02
+//! rate_window = 20
+//! ```
+use crate::strategies::average_seller_strategy::AverageSellerConfig;
+use crate::strategies::stingy_strategy::StingyConfig;
+use serde::{Deserialize, Serialize};
+use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::Path;
+
+/// A `ConfigError` is returned, if a config file can't be loaded
+#[derive(Debug, PartialEq)]
+pub enum ConfigError {
+    /// The file can't be read
+    Io(String),
+    /// The content is not a valid config
+    Parse(String),
+    /// A parameter is out of its range, see `StrategyConfig::validate`
+    Invalid(String),
+}
+
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ConfigError::Io(e) => write!(f, "Unable to read the config: {}", e),
+            ConfigError::Parse(e) => write!(f, "Invalid config: {}", e),
+            ConfigError::Invalid(e) => write!(f, "Invalid parameter: {}", e),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Parameters of all strategies
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StrategyConfig {
+    /// Parameters of the `AverageSellerStrategy`
+    pub average_seller: AverageSellerConfig,
+    /// Parameters of the `StingyStrategy`
+    pub stingy: StingyConfig,
+}
+
+impl StrategyConfig {
+    /// Parses a config in the TOML format and validates it
+    pub fn from_toml(toml: &str) -> Result<Self, ConfigError> {
+        let config: Self = toml::from_str(toml).map_err(|e| ConfigError::Parse(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Parses a config in the JSON format and validates it
+    pub fn from_json(json: &str) -> Result<Self, ConfigError> {
+        let config: Self =
+            serde_json::from_str(json).map_err(|e| ConfigError::Parse(e.to_string()))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    /// Loads the config from the given file. Files with the extension `.json` are parsed as
+    /// JSON, all other files as TOML.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self, ConfigError> {
+        let path = path.as_ref();
+        let content = fs::read_to_string(path).map_err(|e| ConfigError::Io(e.to_string()))?;
+        match path.extension().and_then(|e| e.to_str()) {
+            Some("json") => Self::from_json(&content),
+            _ => Self::from_toml(&content),
+        }
+    }
+
+    /// Checks that every parameter is in its range: shares of EUR or goods must be greater than
+    /// 0 (the percentages of the `StingyStrategy` at most 0.05, the EUR budget of the
+    /// `AverageSellerStrategy` at most 1), thresholds must not be negative and the rate window
+    /// must not be empty.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        let average_seller = &self.average_seller;
+        check_share("average_seller.eur_budget", average_seller.eur_budget, 1.0)?;
+        check(
+            average_seller.medium_quantity_threshold >= 0,
+            "average_seller.medium_quantity_threshold must not be negative",
+        )?;
+        check(
+            average_seller.large_quantity_threshold >= average_seller.medium_quantity_threshold,
+            "average_seller.large_quantity_threshold must not be lower than \
+             average_seller.medium_quantity_threshold",
+        )?;
+
+        let stingy = &self.stingy;
+        check_share("stingy.percentage_buy", stingy.percentage_buy, 0.05)?;
+        check_share("stingy.percentage_sell", stingy.percentage_sell, 0.05)?;
+        check(
+            stingy.rate_window > 0,
+            "stingy.rate_window must be at least 1",
+        )
+    }
+}
+
+/// Returns `ConfigError::Invalid` with the given message, if the condition does not hold
+fn check(condition: bool, message: &str) -> Result<(), ConfigError> {
+    if condition {
+        Ok(())
+    } else {
+        Err(ConfigError::Invalid(message.to_string()))
+    }
+}
+
+/// Checks that the given share is greater than 0 and at most the given max.
+fn check_share(name: &str, share: f32, max: f32) -> Result<(), ConfigError> {
+    check(
+        share > 0.0 && share <= max,
+        &format!(
+            "{} must be greater than 0 and at most {}, got {}",
+            name, max, share
+        ),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::strategies::average_seller_strategy::AverageSellerConfig;
+    use crate::strategies::config::{ConfigError, StrategyConfig};
+    use crate::strategies::stingy_strategy::StingyConfig;
+
+    #[test]
+    fn test_read_config() {
+        let config = StrategyConfig::from_toml(
+            r#"
+            [average_seller]
+            max_diff_count_operations = 10
+
+            [stingy]
+            percentage_buy = 0.02
+            "#,
+        )
+        .unwrap();
+        assert_eq!(10, config.average_seller.max_diff_count_operations);
+        assert_eq!(
+            AverageSellerConfig::default().eur_budget,
+            config.average_seller.eur_budget,
+            "Missing parameters must be set to their default"
+        );
+        assert_eq!(0.02, config.stingy.percentage_buy);
+        assert_eq!(
+            StingyConfig::default().rate_window,
+            config.stingy.rate_window
+        );
+
+        let config = StrategyConfig::from_json(r#"{"stingy": {"rate_window": 20}}"#).unwrap();
+        assert_eq!(20, config.stingy.rate_window);
+        assert_eq!(AverageSellerConfig::default(), config.average_seller);
+
+        assert_eq!(
+            StrategyConfig::default(),
+            StrategyConfig::from_toml("").unwrap()
+        );
+        assert!(matches!(
+            StrategyConfig::from_json("{"),
+            Err(ConfigError::Parse(_))
+        ));
+        assert!(matches!(
+            StrategyConfig::from_file("does/not/exist.toml"),
+            Err(ConfigError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_validate_config() {
+        assert_eq!(Ok(()), StrategyConfig::default().validate());
+
+        for toml in [
+            "[average_seller]\neur_budget = 1.5",
+            "[average_seller]\neur_budget = 0.0",
+            "[average_seller]\nmedium_quantity_threshold = -1",
+            "[average_seller]\nlarge_quantity_threshold = 10\nmedium_quantity_threshold = 100",
+            "[stingy]\npercentage_buy = 0.1",
+            "[stingy]\npercentage_sell = -0.01",
+            "[stingy]\nrate_window = 0",
+        ] {
+            assert!(
+                matches!(
+                    StrategyConfig::from_toml(toml),
+                    Err(ConfigError::Invalid(_))
+                ),
+                "Config '{}' must be rejected",
+                toml
+            );
+        }
+        assert!(matches!(
+            StrategyConfig::from_json(r#"{"stingy": {"percentage_buy": 0.5}}"#),
+            Err(ConfigError::Invalid(_))
+        ));
+
+        let config = StrategyConfig::from_toml("[stingy]\npercentage_buy = 0.05").unwrap();
+        assert_eq!(0.05, config.stingy.percentage_buy, "The max. is allowed");
+    }
+}
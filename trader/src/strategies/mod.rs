@@ -1,4 +1,7 @@
 //! The strategy module contains the [`strategy`] trait, as well as all available custom strategies.
+//! The [`registry`] creates a strategy by its name, the [`config`] contains the parameters of the strategies.
 pub mod average_seller_strategy;
+pub mod config;
+pub mod registry;
 pub mod stingy_strategy;
 pub mod strategy;
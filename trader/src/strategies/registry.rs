@@ -0,0 +1,156 @@
+//! The registry maps the name of a strategy (e.g. `average-seller`) to a factory that creates
+//! the strategy. All strategies of this crate are registered by default. Other crates can
+//! register their own strategies, without changing this crate:
+//!
+//! ```rust,ignore
+//! let mut registry = StrategyRegistry::new();
+//! registry.register("my-strategy", "MyTrader", |markets, trader_name| {
+//!     Box::new(MyStrategy::new(markets, trader_name))
+//! });
+//! let config = StrategyConfig::default();
+//! let trader =
+//!     Trader::try_from_registry(&registry, "my-strategy", 1_000.0, markets, None, &config)?;
+//! ```
+use crate::consts::{
+    STRATEGY_NAME_AVERAGE_SELLER, STRATEGY_NAME_STINGY, TRADER_NAME_AVERAGE_SELLER,
+    TRADER_NAME_STINGY,
+};
+use crate::strategies::average_seller_strategy::AverageSellerStrategy;
+use crate::strategies::stingy_strategy::StingyStrategy;
+use crate::strategies::strategy::Strategy;
+use crate::MarketRef;
+use std::collections::BTreeMap;
+
+/// A factory that creates a strategy for the given markets and trader name
+pub type StrategyFactory = Box<dyn Fn(Vec<MarketRef>, &str) -> Box<dyn Strategy>>;
+
+/// A registered strategy
+struct RegisteredStrategy {
+    /// Name of the trader using this strategy
+    trader_name: String,
+    /// Creates the strategy
+    factory: StrategyFactory,
+}
+
+/// All strategies a trader can be created with, by their name
+pub struct StrategyRegistry {
+    strategies: BTreeMap<String, RegisteredStrategy>,
+}
+
+impl Default for StrategyRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StrategyRegistry {
+    /// Creates a registry that contains all strategies of this crate
+    pub fn new() -> Self {
+        let mut registry = Self::empty();
+        registry.register(
+            STRATEGY_NAME_AVERAGE_SELLER,
+            TRADER_NAME_AVERAGE_SELLER,
+            |markets, trader_name| Box::new(AverageSellerStrategy::new(markets, trader_name)),
+        );
+        registry.register(
+            STRATEGY_NAME_STINGY,
+            TRADER_NAME_STINGY,
+            |markets, trader_name| Box::new(StingyStrategy::new(markets, trader_name)),
+        );
+        registry
+    }
+
+    /// Creates a registry without any strategies
+    pub fn empty() -> Self {
+        Self {
+            strategies: BTreeMap::new(),
+        }
+    }
+
+    /// Registers a strategy with the given name. The trader using the strategy is named
+    /// `trader_name`. A strategy that is already registered with the same name is replaced.
+    pub fn register<F>(&mut self, name: &str, trader_name: &str, factory: F)
+    where
+        F: Fn(Vec<MarketRef>, &str) -> Box<dyn Strategy> + 'static,
+    {
+        self.strategies.insert(
+            name.to_string(),
+            RegisteredStrategy {
+                trader_name: trader_name.to_string(),
+                factory: Box::new(factory),
+            },
+        );
+    }
+
+    /// Checks if a strategy with the given name is registered
+    pub fn contains(&self, name: &str) -> bool {
+        self.strategies.contains_key(name)
+    }
+
+    /// Returns the names of all registered strategies, in alphabetical order
+    pub fn get_names(&self) -> Vec<&str> {
+        self.strategies.keys().map(|name| name.as_str()).collect()
+    }
+
+    /// Returns the name of the trader using the given strategy
+    pub fn get_trader_name(&self, name: &str) -> Option<&str> {
+        self.strategies
+            .get(name)
+            .map(|strategy| strategy.trader_name.as_str())
+    }
+
+    /// Creates the strategy with the given name for the given markets.
+    /// Returns `None` if no strategy is registered with that name.
+    pub fn create(&self, name: &str, markets: Vec<MarketRef>) -> Option<Box<dyn Strategy>> {
+        self.strategies
+            .get(name)
+            .map(|strategy| (strategy.factory)(markets, &strategy.trader_name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::consts::{STRATEGY_NAME_AVERAGE_SELLER, STRATEGY_NAME_STINGY, TRADER_NAME_STINGY};
+    use crate::strategies::registry::StrategyRegistry;
+    use crate::strategies::stingy_strategy::StingyStrategy;
+    use crate::strategies::strategy::Strategy;
+    use unitn_market_2022::market::Market;
+    use SGX::market::sgx::SGX;
+
+    #[test]
+    fn test_built_in_strategies() {
+        let registry = StrategyRegistry::new();
+        assert_eq!(
+            vec![STRATEGY_NAME_AVERAGE_SELLER, STRATEGY_NAME_STINGY],
+            registry.get_names()
+        );
+        assert_eq!(
+            Some(TRADER_NAME_STINGY),
+            registry.get_trader_name(STRATEGY_NAME_STINGY)
+        );
+
+        let strategy = registry.create(STRATEGY_NAME_STINGY, vec![SGX::new_random()]);
+        assert_eq!(1, strategy.unwrap().get_markets().len());
+        assert!(registry
+            .create("unknown", vec![SGX::new_random()])
+            .is_none());
+        assert!(StrategyRegistry::empty().get_names().is_empty());
+    }
+
+    #[test]
+    fn test_register_strategy() {
+        let mut registry = StrategyRegistry::new();
+        registry.register("in-house", "TheInHouseTrader", |markets, trader_name| {
+            Box::new(StingyStrategy::new(markets, trader_name))
+        });
+        assert!(registry.contains("in-house"));
+        assert_eq!(
+            Some("TheInHouseTrader"),
+            registry.get_trader_name("in-house")
+        );
+        assert!(registry
+            .create("in-house", vec![SGX::new_random()])
+            .is_some());
+        assert_eq!(3, registry.get_names().len());
+    }
+}
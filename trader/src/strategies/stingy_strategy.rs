@@ -23,10 +23,17 @@
 //! (USD, YEN or YUAN) and collects a set of possible deals. Afterward the strategy search for the deal with the higher exchange rate to EUR: higher the exchange rate, more the quantity of EUR.
 //! The selling strategy also look at the average exchange rate during the last 10 operations and if it can't find a good deal, it will try to sell the one with the higher price, to let the prices fluctuate.
 //! The strategy will always try to sell after every buy operation.
+//!
+//! ## Parameters
+//! The percentages of EUR and goods (1%) and the number of operations the average exchange rate is computed of (10)
+//! are the defaults of the [`StingyConfig`].
 
+use crate::strategies::config::StrategyConfig;
 use crate::strategies::strategy::Strategy;
+use crate::trader::ledger::{Ledger, LedgerRef, Operation, Transaction, TransactionResult};
 use crate::MarketRef;
 use log::{info, warn};
+use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
 use std::cell::RefCell;
 use std::collections::VecDeque;
@@ -34,10 +41,6 @@ use std::rc::Rc;
 use unitn_market_2022::good::good::Good;
 use unitn_market_2022::good::good_kind::GoodKind;
 
-/// This consts define the percentage that the trader is willing to buy or sell.
-/// In order to be coherent with the strategy, it has not to be greater than 0.05.
-const PERCENTAGE_BUY: f32 = 0.01;
-const PERCENTAGE_SELL: f32 = 0.01;
 /// This const define the percentage that the trader is willing to sell while closing the strategy.
 const PERCENTAGE_SELL_ALL_GOODS: f32 = 1.0;
 
@@ -95,6 +98,32 @@ impl Deal {
     }
 }
 
+/// Parameters of the `StingyStrategy`. Every parameter that is missing in a config file is set
+/// to its default.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StingyConfig {
+    /// The percentage of EUR the trader is willing to spend on a buy (default: 0.01).
+    /// In order to be coherent with the strategy, it has not to be greater than 0.05.
+    pub percentage_buy: f32,
+    /// The percentage of a good the trader is willing to sell (default: 0.01).
+    /// In order to be coherent with the strategy, it has not to be greater than 0.05.
+    pub percentage_sell: f32,
+    /// Number of exchange rates per good and market, the average exchange rate is computed of
+    /// (default: 10)
+    pub rate_window: usize,
+}
+
+impl Default for StingyConfig {
+    fn default() -> Self {
+        Self {
+            percentage_buy: 0.01,
+            percentage_sell: 0.01,
+            rate_window: 10,
+        }
+    }
+}
+
 /// Implementation of the `StingyStrategy`.
 pub struct StingyStrategy {
     /// name of the trader that use this strategy
@@ -113,6 +142,10 @@ pub struct StingyStrategy {
     deals_buy_history: RefCell<Vec<Deal>>,
     /// History of all the deal done by the trader for **selling** goods to the markets
     deals_sell_history: RefCell<Vec<Deal>>,
+    /// Ledger that records every operation of the trader with the markets
+    ledger: LedgerRef,
+    /// Parameters of the strategy
+    config: StingyConfig,
 }
 
 /// Methods for **buy**.
@@ -220,6 +253,12 @@ impl StingyStrategy {
                 deal.price,
                 self.trader_name.clone(),
             );
+            self.record(
+                Operation::LockBuy,
+                deal,
+                token.as_ref().ok().cloned(),
+                TransactionResult::from(&token),
+            );
 
             if let Ok(token) = token {
                 info!("Lock buy done with token: {}", token);
@@ -261,7 +300,13 @@ impl StingyStrategy {
             let mut market = market.as_ref().borrow_mut();
 
             if let Some(token) = token {
-                let buy_good = market.buy(token, trader_eur);
+                let buy_good = market.buy(token.clone(), trader_eur);
+                self.record(
+                    Operation::Buy,
+                    &deal,
+                    Some(token),
+                    TransactionResult::from(&buy_good),
+                );
 
                 if let Ok(buy_good) = buy_good {
                     info!(
@@ -397,6 +442,12 @@ impl StingyStrategy {
             deal.price,
             self.trader_name.clone(),
         );
+        self.record(
+            Operation::LockSell,
+            deal,
+            token.as_ref().ok().cloned(),
+            TransactionResult::from(&token),
+        );
 
         if let Ok(token) = token {
             info!(
@@ -438,7 +489,13 @@ impl StingyStrategy {
                     .find(|good| good.get_kind() == deal.good_kind)
                     .unwrap();
 
-                let sell_good = market.sell(token, good_to_sell);
+                let sell_good = market.sell(token.clone(), good_to_sell);
+                self.record(
+                    Operation::Sell,
+                    &deal,
+                    Some(token),
+                    TransactionResult::from(&sell_good),
+                );
 
                 if let Ok(sell_good) = sell_good {
                     info!(
@@ -472,17 +529,42 @@ impl StingyStrategy {
     fn get_market_qty(&self) -> usize {
         self.markets.len()
     }
+
+    /// Get the max. number of exchange rates in a history, `rate_window` rates for each of the
+    /// 3 goods of every market.
+    fn get_history_len(&self) -> usize {
+        self.get_market_qty() * 3 * self.config.rate_window
+    }
+
+    /// Records an operation of the given deal to the ledger.
+    fn record(
+        &self,
+        operation: Operation,
+        deal: &Deal,
+        token: Option<String>,
+        result: TransactionResult,
+    ) {
+        self.ledger.borrow_mut().record(Transaction {
+            operation,
+            market: deal.market_name.clone(),
+            kind: deal.good_kind,
+            quantity: deal.quantity,
+            price: deal.price,
+            token,
+            result,
+        });
+    }
 }
 
 /// Helper methods for **buying**.
 impl StingyStrategy {
     /// This methods dda a new exchange rate item, passed as a parameter, in the [`ex_rate_buy_history`]
-    /// only if there are no more than 10 exchange rates for every kind of good (total: 30).
-    /// If there are more than 10 ex rate for every kind of good, it removes the first 3 items of
+    /// only if there are no more than `rate_window` exchange rates for every kind of good (default total: 30).
+    /// If there are more than `rate_window` ex rate for every kind of good, it removes the first 3 items of
     /// the [`ex_rate_buy_history`] deque vector.
     fn add_ex_rate_buy_to_history(&self, e: ExchangeRate) {
         let mut history = self.ex_rate_buy_history.borrow_mut();
-        if history.len() >= self.get_market_qty() * 3 * 10 {
+        if history.len() >= self.get_history_len() {
             history.pop_front();
             history.pop_front();
             history.pop_front();
@@ -513,7 +595,7 @@ impl StingyStrategy {
         }
     }
 
-    /// Return as `f32` the average exchange rate for **buying** a certain good kind during the last 10 operations.
+    /// Return as `f32` the average exchange rate for **buying** a certain good kind during the last `rate_window` operations.
     fn get_avg_buy_ex_rate(&self, good_kind: GoodKind) -> f32 {
         let mut counter = 0;
         let mut total: f32 = 0.0;
@@ -542,12 +624,12 @@ impl StingyStrategy {
 /// Helper methods for **selling**.
 impl StingyStrategy {
     /// This methods dda a new exchange rate item, passed as a parameter, in the [`ex_rate_sell_history`]
-    /// only if there are no more than 10 exchange rates for every kind of good (total: 30).
-    /// If there are more than 10 ex rate for every kind of good, it removes the first 3 items of
+    /// only if there are no more than `rate_window` exchange rates for every kind of good (default total: 30).
+    /// If there are more than `rate_window` ex rate for every kind of good, it removes the first 3 items of
     /// the [`ex_rate_sell_history`] deque vector.
     fn add_ex_rate_sell_to_history(&self, e: ExchangeRate) {
         let mut history = self.ex_rate_sell_history.borrow_mut();
-        if history.len() >= self.get_market_qty() * 3 * 10 {
+        if history.len() >= self.get_history_len() {
             history.pop_front();
             history.pop_front();
             history.pop_front();
@@ -578,7 +660,7 @@ impl StingyStrategy {
         }
     }
 
-    /// Return as `f32` the average exchange rate for **selling** a certain good kind during the last 10 operations.
+    /// Return as `f32` the average exchange rate for **selling** a certain good kind during the last `rate_window` operations.
     fn get_avg_sell_ex_rate(&self, good_kind: GoodKind) -> f32 {
         let mut counter = 0;
         let mut total: f32 = 0.0;
@@ -614,9 +696,21 @@ impl Strategy for StingyStrategy {
             ex_rate_sell_history: RefCell::new(VecDeque::new()),
             deals_buy_history: RefCell::new(Vec::new()),
             deals_sell_history: RefCell::new(Vec::new()),
+            ledger: Rc::new(RefCell::new(Ledger::default())),
+            config: StingyConfig::default(),
         }
     }
 
+    /// Record all operations to the given ledger of the trader.
+    fn set_ledger(&mut self, ledger: LedgerRef) {
+        self.ledger = ledger;
+    }
+
+    /// Use the `stingy` parameters of the given config.
+    fn set_config(&mut self, config: &StrategyConfig) {
+        self.config = config.stingy.clone();
+    }
+
     /// Return a vector of `MarketRef`.
     /// This methods return references to the markets involved in the strategy.
     fn get_markets(&self) -> &Vec<MarketRef> {
@@ -634,9 +728,9 @@ impl Strategy for StingyStrategy {
 
     /// This method defines how to apply the strategy.
     fn apply(&self, goods: &mut Vec<Good>) {
-        self.buy_deal(goods, PERCENTAGE_BUY);
+        self.buy_deal(goods, self.config.percentage_buy);
         self.update_ex_rates_buy();
-        self.sell_deal(goods, PERCENTAGE_SELL);
+        self.sell_deal(goods, self.config.percentage_sell);
         self.update_ex_rates_sell();
     }
 }
@@ -644,7 +738,8 @@ impl Strategy for StingyStrategy {
 #[cfg(test)]
 mod tests {
     use crate::consts::TRADER_NAME_STINGY;
-    use crate::strategies::stingy_strategy::{Deal, ExchangeRate, StingyStrategy};
+    use crate::strategies::config::StrategyConfig;
+    use crate::strategies::stingy_strategy::{Deal, ExchangeRate, StingyConfig, StingyStrategy};
     use crate::strategies::strategy::Strategy;
     use crate::MarketRef;
     use smse::Smse;
@@ -995,6 +1090,26 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_rate_window() {
+        let sgx = init_sgx(1_000.0, 1_000.0, 1_000.0, 1_000.0);
+        let mut strategy = StingyStrategy::new(vec![Rc::clone(&sgx)], TRADER_NAME_STINGY);
+        let config = StrategyConfig {
+            stingy: StingyConfig {
+                rate_window: 2,
+                ..StingyConfig::default()
+            },
+            ..StrategyConfig::default()
+        };
+        strategy.set_config(&config);
+
+        for _ in 0..5 {
+            strategy.update_ex_rates_buy();
+        }
+        // 2 rates for each of the 3 goods
+        assert_eq!(6, strategy.ex_rate_buy_history.borrow().len());
+    }
+
     #[test]
     fn test_update_ex_rates_sell() {
         let trader_name = TRADER_NAME_STINGY;
@@ -2,6 +2,8 @@
 //!
 //! The goal of this implementation is to give an author of a strategy every possible freedom
 //! to define what a strategy is suppose to do.
+use crate::strategies::config::StrategyConfig;
+use crate::trader::ledger::LedgerRef;
 use crate::MarketRef;
 
 use std::rc::Rc;
@@ -16,6 +18,15 @@ pub trait Strategy {
         Self: Sized;
     /// Returns a reference to the markets used by this strategy.
     fn get_markets(&self) -> &Vec<MarketRef>;
+    /// Seeds the random decisions of the strategy, so that a run can be reproduced.
+    /// Strategies without random decisions can ignore the seed.
+    fn set_seed(&mut self, _seed: u64) {}
+    /// Gives the strategy the ledger of the trader, to record every lock, buy and sell.
+    /// Strategies that don't record their operations can ignore the ledger.
+    fn set_ledger(&mut self, _ledger: LedgerRef) {}
+    /// Sets the parameters of the strategy, a strategy uses its own section of the config.
+    /// Strategies without parameters can ignore the config.
+    fn set_config(&mut self, _config: &StrategyConfig) {}
     /// Increases the day of all given markets by one day.
     /// Call this method after a day has passed.
     fn increase_day_by_one(&self) {
@@ -0,0 +1,54 @@
+use std::fmt::{Display, Formatter};
+
+/// A `TraderError` is returned by [`crate::trader::Trader::try_from`],
+/// [`crate::trader::Trader::try_from_registry`] and
+/// [`crate::trader::Trader::try_apply_strategy`], if the given arguments are not valid
+#[derive(Debug, PartialEq)]
+pub enum TraderError {
+    /// The start capital is not a positive number
+    NonPositiveStartCapital { start_capital: f32 },
+    /// The trader needs at least one market
+    NoMarkets,
+    /// No strategy is registered with the given name
+    UnknownStrategy { name: String },
+    /// The trader has to run at least one day
+    ZeroDays,
+    /// The strategy has to be applied at least every minute
+    ZeroMinuteInterval,
+    /// The strategy has to be applied at least once a day
+    IntervalLongerThanDay {
+        apply_every_minutes: u32,
+        minutes_per_day: u32,
+    },
+}
+
+impl Display for TraderError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TraderError::NonPositiveStartCapital { start_capital } => write!(
+                f,
+                "The start capital must be greater than 0.0 ({} given)",
+                start_capital
+            ),
+            TraderError::NoMarkets => write!(f, "At least one market is required"),
+            TraderError::UnknownStrategy { name } => {
+                write!(f, "No strategy called '{}' is registered", name)
+            }
+            TraderError::ZeroDays => write!(f, "The trader has to run at least 1 day"),
+            TraderError::ZeroMinuteInterval => write!(
+                f,
+                "The strategy has to be applied at least every 1 minute instead of every 0 minutes"
+            ),
+            TraderError::IntervalLongerThanDay {
+                apply_every_minutes,
+                minutes_per_day,
+            } => write!(
+                f,
+                "The strategy can't be applied every {} minutes, a day only has {} minutes",
+                apply_every_minutes, minutes_per_day
+            ),
+        }
+    }
+}
+
+impl std::error::Error for TraderError {}
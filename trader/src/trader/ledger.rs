@@ -0,0 +1,148 @@
+//! The ledger records every single operation a strategy performs on a market (lock buy, buy,
+//! lock sell and sell), whether it was successful or not. In contrast to the history, that only
+//! contains the goods at the end of each day, the ledger explains how a trader ended up there.
+//!
+//! The trader shares the ledger with its strategy and updates the time of the ledger, before it
+//! applies the strategy. A strategy only has to record its operations.
+use serde::{Serialize, Serializer};
+use std::cell::RefCell;
+use std::rc::Rc;
+use unitn_market_2022::good::good_kind::GoodKind;
+
+/// Reference to a ledger that is shared between a trader and its strategy
+pub type LedgerRef = Rc<RefCell<Ledger>>;
+
+/// The operations on a market that are recorded
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+pub enum Operation {
+    LockBuy,
+    Buy,
+    LockSell,
+    Sell,
+}
+
+/// The result of an operation
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub enum TransactionResult {
+    Success,
+    /// The operation failed with the given error of the market
+    Failure(String),
+}
+
+impl<T, E> From<&Result<T, E>> for TransactionResult
+where
+    E: std::fmt::Debug,
+{
+    fn from(result: &Result<T, E>) -> Self {
+        match result {
+            Ok(_) => TransactionResult::Success,
+            Err(err) => TransactionResult::Failure(format!("{:?}", err)),
+        }
+    }
+}
+
+/// A single operation a strategy has performed on a market
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct Transaction {
+    /// The operation
+    pub operation: Operation,
+    /// Name of the market
+    pub market: String,
+    /// Kind of the good that is bought or sold
+    #[serde(serialize_with = "serialize_good_kind")]
+    pub kind: GoodKind,
+    /// Quantity of the good
+    pub quantity: f32,
+    /// The price in EUR (the bid or offer of a lock, or the EUR paid or received)
+    pub price: f32,
+    /// The token of the lock, if there is one
+    pub token: Option<String>,
+    /// Whether the operation was successful
+    pub result: TransactionResult,
+}
+
+/// A transaction with the time it was recorded at
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct LedgerEntry {
+    /// The day of the run, starting at 0
+    pub day: u32,
+    /// The minute of the day the strategy was applied at
+    pub minute: u32,
+    #[serde(flatten)]
+    pub transaction: Transaction,
+}
+
+/// All transactions of a trader, in the order they were recorded
+#[derive(Clone, Debug, Default)]
+pub struct Ledger {
+    /// The current day
+    day: u32,
+    /// The current minute of the day
+    minute: u32,
+    entries: Vec<LedgerEntry>,
+}
+
+impl Ledger {
+    /// Sets the time, all following transactions are recorded at
+    pub fn set_time(&mut self, day: u32, minute: u32) {
+        self.day = day;
+        self.minute = minute;
+    }
+
+    /// Records the given transaction at the current time
+    pub fn record(&mut self, transaction: Transaction) {
+        self.entries.push(LedgerEntry {
+            day: self.day,
+            minute: self.minute,
+            transaction,
+        });
+    }
+
+    /// Returns all entries
+    pub fn get_entries(&self) -> &Vec<LedgerEntry> {
+        &self.entries
+    }
+}
+
+/// Serializes a [`GoodKind`] as its name
+fn serialize_good_kind<S>(kind: &GoodKind, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_str(&kind.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::trader::ledger::{Ledger, Operation, Transaction, TransactionResult};
+    use unitn_market_2022::good::good_kind::GoodKind;
+
+    #[test]
+    fn test_record_transaction() {
+        let mut ledger = Ledger::default();
+        ledger.set_time(2, 120);
+        let lock: Result<String, String> = Err("MaxAllowedLocksReached".to_string());
+        ledger.record(Transaction {
+            operation: Operation::LockBuy,
+            market: "SGX".to_string(),
+            kind: GoodKind::USD,
+            quantity: 10.0,
+            price: 9.5,
+            token: None,
+            result: TransactionResult::from(&lock),
+        });
+
+        let entries = ledger.get_entries();
+        assert_eq!(1, entries.len());
+        assert_eq!(2, entries[0].day);
+        assert_eq!(120, entries[0].minute);
+        assert_eq!(
+            TransactionResult::Failure("\"MaxAllowedLocksReached\"".to_string()),
+            entries[0].transaction.result
+        );
+
+        let json = serde_json::to_string(entries).unwrap();
+        assert!(json.contains("\"day\":2"), "Transaction must be flattened");
+        assert!(json.contains("\"kind\":\"USD\""), "Kind must be its name");
+    }
+}
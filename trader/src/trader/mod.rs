@@ -5,25 +5,47 @@
 //! day, the trader pushes a transformed copy of the goods to the history.
 //! Another goal of this implementation is to give a strategy every possible freedom.
 //!
-//! Furthermore, the trader is able export its history in JSON format.
-use crate::consts::{TRADER_NAME_AVERAGE_SELLER, TRADER_NAME_STINGY};
-use crate::strategies::average_seller_strategy::AverageSellerStrategy;
+//! Furthermore, the trader is able export its history in JSON format. Next to the history, the
+//! trader keeps a [`ledger`] of every single operation its strategy has performed. The EUR value
+//! of the goods is computed every day, to evaluate the run with a [`report`].
+pub mod errors;
+pub mod ledger;
+pub mod report;
+
+use crate::consts::{STRATEGY_NAME_AVERAGE_SELLER, STRATEGY_NAME_STINGY};
+use crate::strategies::config::StrategyConfig;
+use crate::strategies::registry::StrategyRegistry;
 use crate::strategies::strategy::Strategy;
+use crate::trader::errors::TraderError;
+use crate::trader::ledger::{Ledger, LedgerEntry, LedgerRef};
+use crate::trader::report::{DayValuation, TraderReport};
 use crate::MarketRef;
 use env_logger::Env;
 use serde::Serialize;
 use std::cell::RefCell;
+use std::rc::Rc;
 
-use crate::strategies::stingy_strategy::StingyStrategy;
 use unitn_market_2022::good::good::Good;
 use unitn_market_2022::good::good_kind::GoodKind;
 
+/// Identifiers of the strategies of this crate. Every strategy (including the strategies of
+/// other crates) can also be created by its name with a [`StrategyRegistry`].
 #[derive(Clone, Debug, Eq, Ord, PartialOrd, PartialEq)]
 pub enum StrategyIdentifier {
     AverageSeller,
     Stingy,
 }
 
+impl StrategyIdentifier {
+    /// Returns the name of the strategy in the [`StrategyRegistry`]
+    pub fn get_strategy_name(&self) -> &'static str {
+        match self {
+            StrategyIdentifier::AverageSeller => STRATEGY_NAME_AVERAGE_SELLER,
+            StrategyIdentifier::Stingy => STRATEGY_NAME_STINGY,
+        }
+    }
+}
+
 #[derive(Clone, Debug, Serialize)]
 pub struct HistoryDay {
     day: u32,
@@ -47,6 +69,13 @@ impl HistoryDay {
 
 pub type TraderHistory = Vec<HistoryDay>;
 
+/// The history and the report of a trader, as they are exported together
+#[derive(Serialize)]
+struct TraderExport<'a> {
+    history: &'a TraderHistory,
+    report: TraderReport,
+}
+
 pub struct Trader {
     /// Name of the trader
     name: String,
@@ -58,6 +87,12 @@ pub struct Trader {
     /// Each row is a day (starting at day 0) and it represents the effects of the
     /// buy and sell actions, the strategy as applied.
     history: RefCell<TraderHistory>,
+    /// Every lock, buy and sell the strategy has performed
+    ledger: LedgerRef,
+    /// The EUR the trader has started with
+    start_capital: f32,
+    /// EUR value of the goods per day, starting at day 0
+    valuations: RefCell<Vec<DayValuation>>,
     /// Number of days the trader is running
     days: RefCell<u32>,
 }
@@ -75,26 +110,19 @@ impl Trader {
         Vec::from([eur, usd, yen, yuan])
     }
 
-    /// Inits the strategy for the given identifier.
+    /// Prepares the created strategy. If a seed is given, the strategy is seeded with it.
+    /// The strategy uses the given config and records its operations to the given ledger.
     fn init_strategy(
-        id: StrategyIdentifier,
-        markets: Vec<MarketRef>,
-        trader_name: &str,
-    ) -> Box<dyn Strategy> {
-        match id {
-            StrategyIdentifier::AverageSeller => {
-                Box::new(AverageSellerStrategy::new(markets, trader_name))
-            }
-            StrategyIdentifier::Stingy => Box::new(StingyStrategy::new(markets, trader_name)),
-        }
-    }
-
-    /// Returns the name of the trader for the given strategy identifier.
-    fn get_name_for_strategy(id: StrategyIdentifier) -> &'static str {
-        match id {
-            StrategyIdentifier::AverageSeller => TRADER_NAME_AVERAGE_SELLER,
-            StrategyIdentifier::Stingy => TRADER_NAME_STINGY,
+        strategy: &mut dyn Strategy,
+        seed: Option<u64>,
+        config: &StrategyConfig,
+        ledger: LedgerRef,
+    ) {
+        if let Some(seed) = seed {
+            strategy.set_seed(seed);
         }
+        strategy.set_config(config);
+        strategy.set_ledger(ledger);
     }
 
     /// Transforms a slice of goods to a [`HistoryDay`] struct.
@@ -111,17 +139,141 @@ impl Trader {
         day
     }
 
-    /// Instantiates a trader
+    /// Returns the value of the given goods in EUR. Every good other than EUR is valued at the
+    /// best sell price of all markets for its quantity. If no market makes an offer, the good is
+    /// valued with the best sell exchange rate of the markets.
+    fn value_goods_in_eur(markets: &[MarketRef], goods: &[Good]) -> f32 {
+        let is_valid = |value: &f32| value.is_finite() && *value > 0.0;
+        goods
+            .iter()
+            .map(|good| {
+                let kind = good.get_kind();
+                let quantity = good.get_qty();
+                if kind == GoodKind::EUR || quantity <= 0.0 {
+                    return quantity;
+                }
+                let best_price = markets
+                    .iter()
+                    .filter_map(|m| m.as_ref().borrow().get_sell_price(kind, quantity).ok())
+                    .filter(is_valid)
+                    .reduce(f32::max);
+                best_price.unwrap_or_else(|| {
+                    markets
+                        .iter()
+                        .flat_map(|m| m.as_ref().borrow().get_goods())
+                        .filter(|label| label.good_kind == kind)
+                        .map(|label| label.exchange_rate_sell * quantity)
+                        .filter(is_valid)
+                        .fold(0.0, f32::max)
+                })
+            })
+            .sum()
+    }
+
+    /// Instantiates a trader.
+    /// Panics if the start capital is not positive or no markets are given,
+    /// use [`Trader::try_from`] to handle these errors.
     pub fn from(
         strategy_id: StrategyIdentifier,
         start_capital: f32,
         markets: Vec<MarketRef>,
     ) -> Self {
-        if start_capital <= 0.0 {
-            panic!("start_capital must be greater than 0.0")
+        Self::try_from(strategy_id, start_capital, markets).unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Instantiates a trader, whose strategy is seeded with the given seed.
+    /// Together with seeded markets, a run of the trader can be reproduced exactly.
+    /// Panics for the same reasons as [`Trader::from`].
+    pub fn with_seed(
+        strategy_id: StrategyIdentifier,
+        start_capital: f32,
+        markets: Vec<MarketRef>,
+        seed: u64,
+    ) -> Self {
+        Self::try_with_seed(strategy_id, start_capital, markets, seed)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Instantiates a trader, or returns a [`TraderError`] if the start capital is not
+    /// positive or no markets are given.
+    pub fn try_from(
+        strategy_id: StrategyIdentifier,
+        start_capital: f32,
+        markets: Vec<MarketRef>,
+    ) -> Result<Self, TraderError> {
+        Self::init(
+            strategy_id,
+            start_capital,
+            markets,
+            None,
+            &StrategyConfig::default(),
+        )
+    }
+
+    /// Instantiates a trader, whose strategy is seeded with the given seed, or returns a
+    /// [`TraderError`] for the same reasons as [`Trader::try_from`].
+    pub fn try_with_seed(
+        strategy_id: StrategyIdentifier,
+        start_capital: f32,
+        markets: Vec<MarketRef>,
+        seed: u64,
+    ) -> Result<Self, TraderError> {
+        Self::init(
+            strategy_id,
+            start_capital,
+            markets,
+            Some(seed),
+            &StrategyConfig::default(),
+        )
+    }
+
+    /// Instantiates a trader, whose strategy uses the parameters of the given config (e.g.
+    /// loaded with [`StrategyConfig::from_file`]), or returns a [`TraderError`] for the same
+    /// reasons as [`Trader::try_from`].
+    pub fn try_with_config(
+        strategy_id: StrategyIdentifier,
+        start_capital: f32,
+        markets: Vec<MarketRef>,
+        config: &StrategyConfig,
+    ) -> Result<Self, TraderError> {
+        Self::init(strategy_id, start_capital, markets, None, config)
+    }
+
+    /// Instantiates a trader with a built-in strategy, the strategy is seeded if a seed is given
+    fn init(
+        strategy_id: StrategyIdentifier,
+        start_capital: f32,
+        markets: Vec<MarketRef>,
+        seed: Option<u64>,
+        config: &StrategyConfig,
+    ) -> Result<Self, TraderError> {
+        Self::try_from_registry(
+            &StrategyRegistry::new(),
+            strategy_id.get_strategy_name(),
+            start_capital,
+            markets,
+            seed,
+            config,
+        )
+    }
+
+    /// Instantiates a trader with the strategy of the given name in the registry. The strategy
+    /// is seeded if a seed is given and uses the parameters of the given config. Returns a
+    /// [`TraderError`] if no strategy is registered with that name, or for the same reasons as
+    /// [`Trader::try_from`].
+    pub fn try_from_registry(
+        registry: &StrategyRegistry,
+        strategy_name: &str,
+        start_capital: f32,
+        markets: Vec<MarketRef>,
+        seed: Option<u64>,
+        config: &StrategyConfig,
+    ) -> Result<Self, TraderError> {
+        if !start_capital.is_finite() || start_capital <= 0.0 {
+            return Err(TraderError::NonPositiveStartCapital { start_capital });
         }
         if markets.is_empty() {
-            panic!("markets can't be empty");
+            return Err(TraderError::NoMarkets);
         }
 
         // Init logger
@@ -130,22 +282,38 @@ impl Trader {
             .write_style_or("MY_LOG_STYLE", "always");
         let _ = env_logger::try_init_from_env(env);
 
+        let unknown_strategy = || TraderError::UnknownStrategy {
+            name: strategy_name.to_string(),
+        };
+        let name = registry
+            .get_trader_name(strategy_name)
+            .ok_or_else(unknown_strategy)?;
+        let mut strategy = registry
+            .create(strategy_name, markets)
+            .ok_or_else(unknown_strategy)?;
+        let ledger = Rc::new(RefCell::new(Ledger::default()));
+        Self::init_strategy(strategy.as_mut(), seed, config, Rc::clone(&ledger));
+
         // init default goods
-        let name = Self::get_name_for_strategy(strategy_id.clone());
-        let strategy = Self::init_strategy(strategy_id, markets, name);
         let goods = Self::create_goods(start_capital);
         let history = Vec::from([Self::transform_good_to_history_day(0, &goods)]);
 
         // Make all markets subscribe
         strategy.subscribe_all_markets();
 
-        Self {
+        Ok(Self {
             name: name.to_string(),
             strategy: RefCell::new(strategy),
             goods: RefCell::new(goods),
             history: RefCell::new(history),
+            ledger,
+            start_capital,
+            valuations: RefCell::new(Vec::from([DayValuation {
+                day: 0,
+                value: start_capital,
+            }])),
             days: RefCell::new(0),
-        }
+        })
     }
 }
 
@@ -154,26 +322,34 @@ impl Trader {
     /// It simulates minutes by calculating how many times the strategy has to be
     /// applied for a using *t = 24 * 60 / n* where *n* is defined as mentioned above.
     /// Then, it applies the strategy exactly *t* times.
+    /// Panics if the arguments are not valid, use [`Trader::try_apply_strategy`] to handle
+    /// these errors.
     pub fn apply_strategy(&self, max_days: u32, apply_every_minutes: u32) {
+        self.try_apply_strategy(max_days, apply_every_minutes)
+            .unwrap_or_else(|e| panic!("{}", e))
+    }
+
+    /// Applies the selected strategy every *n* minutes, like [`Trader::apply_strategy`].
+    /// Returns a [`TraderError`] if the trader should run for 0 days, or the interval is 0
+    /// minutes or longer than a day. Then, the strategy is not applied at all.
+    pub fn try_apply_strategy(
+        &self,
+        max_days: u32,
+        apply_every_minutes: u32,
+    ) -> Result<(), TraderError> {
         if max_days < 1 {
-            panic!(
-                "The trader has to run at least 1 day ({} max. days given)",
-                max_days
-            );
+            return Err(TraderError::ZeroDays);
         }
         if apply_every_minutes < 1 {
-            panic!(
-                "The trader has to be applied at least ever 1 minute instead of every {} minute/s",
-                apply_every_minutes
-            );
+            return Err(TraderError::ZeroMinuteInterval);
         }
 
         let minutes_per_day: u32 = 24 * 60;
         if apply_every_minutes > minutes_per_day {
-            panic!(
-                "Can't apply strategy more than {} times a day (number of minutes per day)",
-                minutes_per_day
-            )
+            return Err(TraderError::IntervalLongerThanDay {
+                apply_every_minutes,
+                minutes_per_day,
+            });
         }
         // how many times to apply the strategy per day?
         let interval_times = minutes_per_day / apply_every_minutes;
@@ -185,7 +361,10 @@ impl Trader {
             let mut goods = self.goods.borrow_mut();
 
             // apply strategy every n minutes
-            for _ in 0..interval_times {
+            for tick in 0..interval_times {
+                self.ledger
+                    .borrow_mut()
+                    .set_time(*days, tick * apply_every_minutes);
                 self.strategy.borrow_mut().apply(&mut goods);
             }
 
@@ -195,13 +374,21 @@ impl Trader {
 
             // if its the last day, sell all remaining goods
             if *days >= max_days {
+                self.ledger.borrow_mut().set_time(*days, 0);
                 self.strategy.borrow().sell_remaining_goods(&mut goods);
             }
 
             // add updated goods to history after strategy has been applied
             let day = Trader::transform_good_to_history_day(*days, &goods);
             self.history.borrow_mut().push(day);
+
+            // value the goods at the prices of the end of the day
+            let value = Trader::value_goods_in_eur(self.strategy.borrow().get_markets(), &goods);
+            self.valuations
+                .borrow_mut()
+                .push(DayValuation { day: *days, value });
         }
+        Ok(())
     }
 
     /// Returns the number of days the agent is running
@@ -220,6 +407,42 @@ impl Trader {
         serde_json::to_string(&history.clone()).unwrap_or_default()
     }
 
+    /// Returns all operations the strategy has performed, in the order they were performed
+    pub fn get_ledger(&self) -> Vec<LedgerEntry> {
+        self.ledger.borrow().get_entries().clone()
+    }
+
+    /// Returns the ledger as a json string
+    pub fn get_ledger_as_json(&self) -> String {
+        let ledger = self.ledger.borrow();
+        serde_json::to_string(ledger.get_entries()).unwrap_or_default()
+    }
+
+    /// Returns the valuation and PnL metrics of the run so far
+    pub fn get_report(&self) -> TraderReport {
+        TraderReport::new(
+            self.start_capital,
+            self.valuations.borrow().clone(),
+            self.ledger.borrow().get_entries(),
+        )
+    }
+
+    /// Returns the report as a json string
+    pub fn get_report_as_json(&self) -> String {
+        serde_json::to_string(&self.get_report()).unwrap_or_default()
+    }
+
+    /// Returns the history and the report as a json string, as an object with the keys
+    /// `history` and `report`
+    pub fn get_export_as_json(&self) -> String {
+        let history = self.history.borrow();
+        let export = TraderExport {
+            history: &history,
+            report: self.get_report(),
+        };
+        serde_json::to_string(&export).unwrap_or_default()
+    }
+
     /// Returns the name of this trader
     pub fn get_name(&self) -> &String {
         &self.name
@@ -229,6 +452,12 @@ impl Trader {
 #[cfg(test)]
 mod tests {
     use crate::consts::{TRADER_NAME_AVERAGE_SELLER, TRADER_NAME_STINGY};
+    use crate::strategies::average_seller_strategy::AverageSellerConfig;
+    use crate::strategies::config::StrategyConfig;
+    use crate::strategies::registry::StrategyRegistry;
+    use crate::strategies::stingy_strategy::StingyStrategy;
+    use crate::strategies::strategy::Strategy;
+    use crate::trader::errors::TraderError;
     use crate::trader::{StrategyIdentifier, Trader};
     use crate::MarketRef;
     use smse::Smse;
@@ -262,7 +491,7 @@ mod tests {
 
         // test if it works
         let trader = Trader::from(StrategyIdentifier::AverageSeller, 300_000.0, markets);
-        let trader_name = Trader::get_name_for_strategy(StrategyIdentifier::AverageSeller);
+        let trader_name = TRADER_NAME_AVERAGE_SELLER;
         assert_eq!(
             trader_name,
             trader.get_name(),
@@ -282,6 +511,107 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_trader_with_seed() {
+        let run_trader = || {
+            let markets = vec![SGX::new_random_with_seed(42)];
+            let trader =
+                Trader::with_seed(StrategyIdentifier::AverageSeller, 1_000_000.0, markets, 7);
+            trader.apply_strategy(3, 240);
+            trader.get_history_as_json()
+        };
+        assert_eq!(
+            run_trader(),
+            run_trader(),
+            "Runs with the same seed must have the same history"
+        );
+    }
+
+    #[test]
+    fn test_ledger() {
+        let markets = vec![SGX::new_random_with_seed(42)];
+        let trader = Trader::with_seed(StrategyIdentifier::Stingy, 1_000_000.0, markets, 7);
+        assert!(
+            trader.get_ledger().is_empty(),
+            "Nothing has been traded yet"
+        );
+
+        trader.apply_strategy(2, 360);
+        let ledger = trader.get_ledger();
+        assert!(!ledger.is_empty(), "The operations must be recorded");
+        assert!(
+            ledger
+                .iter()
+                .all(|entry| entry.day <= 2 && entry.minute < 24 * 60),
+            "Every operation must be recorded within the run"
+        );
+        assert!(
+            ledger
+                .windows(2)
+                .all(|w| (w[0].day, w[0].minute) <= (w[1].day, w[1].minute)),
+            "Operations must be recorded in order"
+        );
+        assert!(trader.get_ledger_as_json().starts_with('['));
+    }
+
+    #[test]
+    fn test_report() {
+        let days = 3;
+        let markets = vec![SGX::new_random_with_seed(42)];
+        let trader = Trader::with_seed(StrategyIdentifier::AverageSeller, 1_000_000.0, markets, 7);
+        trader.apply_strategy(days, 240);
+
+        let report = trader.get_report();
+        assert_eq!(days + 1, report.valuations.len() as u32);
+        assert_eq!(1_000_000.0, report.valuations[0].value);
+        assert!(
+            report
+                .valuations
+                .iter()
+                .all(|v| v.value.is_finite() && v.value >= 0.0),
+            "Every valuation must be a valid EUR value"
+        );
+        assert_eq!(
+            report.valuations.last().unwrap().value,
+            report.final_value,
+            "The final value is the valuation of the last day"
+        );
+        assert!((0.0..=1.0).contains(&report.max_drawdown));
+        assert!(trader.get_report_as_json().contains("\"valuations\""));
+
+        let export: serde_json::Value = serde_json::from_str(&trader.get_export_as_json()).unwrap();
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&trader.get_history_as_json()).unwrap(),
+            export["history"],
+            "The export must contain the history"
+        );
+        assert_eq!(
+            days + 1,
+            export["report"]["valuations"].as_array().unwrap().len() as u32,
+            "The export must contain the report"
+        );
+    }
+
+    #[test]
+    fn test_value_goods_in_eur() {
+        let sgx = SGX::new_with_quantities(10_000.0, 10_000.0, 10_000.0, 10_000.0);
+        let markets = vec![Rc::clone(&sgx)];
+
+        let goods = vec![Good::new(GoodKind::EUR, 500.0)];
+        assert_eq!(500.0, Trader::value_goods_in_eur(&markets, &goods));
+
+        let goods = vec![
+            Good::new(GoodKind::EUR, 500.0),
+            Good::new(GoodKind::USD, 100.0),
+        ];
+        let usd = sgx
+            .as_ref()
+            .borrow()
+            .get_sell_price(GoodKind::USD, 100.0)
+            .unwrap();
+        assert_eq!(500.0 + usd, Trader::value_goods_in_eur(&markets, &goods));
+    }
+
     #[test]
     #[should_panic]
     fn test_new_trader_with_no_capital() {
@@ -365,6 +695,50 @@ mod tests {
         assert_eq!(15_000.0, history.yuan, "YUAN must be {}", 15_000.0);
     }
 
+    #[test]
+    fn test_try_from() {
+        let markets = vec![SGX::new_random_with_seed(42)];
+        let trader = Trader::try_from(StrategyIdentifier::Stingy, -1.0, markets.clone());
+        assert_eq!(
+            Some(TraderError::NonPositiveStartCapital {
+                start_capital: -1.0
+            }),
+            trader.err()
+        );
+        let trader = Trader::try_from(StrategyIdentifier::Stingy, f32::NAN, markets.clone());
+        assert!(matches!(
+            trader,
+            Err(TraderError::NonPositiveStartCapital { .. })
+        ));
+        let trader = Trader::try_from(StrategyIdentifier::Stingy, 1_000.0, vec![]);
+        assert_eq!(Some(TraderError::NoMarkets), trader.err());
+
+        assert!(Trader::try_from(StrategyIdentifier::Stingy, 1_000.0, markets).is_ok());
+    }
+
+    #[test]
+    fn test_try_apply_strategy() {
+        let markets = vec![SGX::new_random_with_seed(42)];
+        let trader = Trader::try_from(StrategyIdentifier::Stingy, 1_000.0, markets).unwrap();
+
+        assert_eq!(Err(TraderError::ZeroDays), trader.try_apply_strategy(0, 60));
+        assert_eq!(
+            Err(TraderError::ZeroMinuteInterval),
+            trader.try_apply_strategy(1, 0)
+        );
+        assert_eq!(
+            Err(TraderError::IntervalLongerThanDay {
+                apply_every_minutes: 24 * 60 + 1,
+                minutes_per_day: 24 * 60
+            }),
+            trader.try_apply_strategy(1, 24 * 60 + 1)
+        );
+        assert_eq!(0, trader.get_days(), "The trader must not run on errors");
+
+        assert_eq!(Ok(()), trader.try_apply_strategy(1, 24 * 60));
+        assert_eq!(1, trader.get_days());
+    }
+
     #[test]
     #[should_panic]
     fn test_apply_strategy_for_zero_days() {
@@ -407,15 +781,72 @@ mod tests {
         ]);
 
         for (expected_name, id) in expected {
-            let name = Trader::get_name_for_strategy(id.clone());
+            let trader = Trader::from(id.clone(), 1_000.0, vec![SGX::new_random()]);
             assert_eq!(
-                expected_name, name,
+                expected_name,
+                trader.get_name(),
                 "The name for id {:?} must be {}",
-                id, expected_name
+                id,
+                expected_name
             );
         }
     }
 
+    #[test]
+    fn test_try_with_config() {
+        let markets = vec![SGX::new_random_with_seed(42)];
+        // never allowed to buy
+        let config = StrategyConfig {
+            average_seller: AverageSellerConfig {
+                eur_budget: 0.0,
+                ..AverageSellerConfig::default()
+            },
+            ..StrategyConfig::default()
+        };
+        let trader =
+            Trader::try_with_config(StrategyIdentifier::AverageSeller, 1_000.0, markets, &config)
+                .unwrap();
+        trader.apply_strategy(1, 240);
+
+        let history = trader.get_history();
+        assert_eq!(
+            1_000.0,
+            history.last().unwrap().eur,
+            "The trader must not spend any EUR"
+        );
+    }
+
+    #[test]
+    fn test_try_from_registry() {
+        let mut registry = StrategyRegistry::new();
+        registry.register("in-house", "TheInHouseTrader", |markets, trader_name| {
+            Box::new(StingyStrategy::new(markets, trader_name))
+        });
+
+        let markets = vec![SGX::new_random_with_seed(42)];
+        let config = StrategyConfig::default();
+        let trader = Trader::try_from_registry(
+            &registry,
+            "in-house",
+            1_000.0,
+            markets.clone(),
+            Some(7),
+            &config,
+        )
+        .unwrap();
+        assert_eq!("TheInHouseTrader", trader.get_name());
+        assert_eq!(Ok(()), trader.try_apply_strategy(1, 240));
+
+        let trader =
+            Trader::try_from_registry(&registry, "unknown", 1_000.0, markets, None, &config);
+        assert_eq!(
+            Some(TraderError::UnknownStrategy {
+                name: "unknown".to_string()
+            }),
+            trader.err()
+        );
+    }
+
     #[test]
     fn test_apply_average_seller_strategy_for_one_week() {
         let days = 7;
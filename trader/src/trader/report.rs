@@ -0,0 +1,206 @@
+//! The report evaluates a run of a trader. The inventory of each day is valued in EUR at the
+//! prices of the markets (mark-to-market). On top of the valuations, the report contains the
+//! realised and unrealised profit and loss (PnL), the return relative to the start capital, the
+//! max. drawdown and the volatility of the daily returns.
+use crate::trader::ledger::{LedgerEntry, Operation, TransactionResult};
+use serde::Serialize;
+use std::collections::HashMap;
+use unitn_market_2022::good::good_kind::GoodKind;
+
+/// The EUR value of the whole inventory at the end of a day
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DayValuation {
+    /// The day, day 0 is the start of the run
+    pub day: u32,
+    /// Value of all goods in EUR
+    pub value: f32,
+}
+
+/// Valuation and PnL metrics of a trader
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct TraderReport {
+    /// The EUR the trader has started with
+    pub start_capital: f32,
+    /// Value of the inventory at the last day
+    pub final_value: f32,
+    /// Value of the inventory per day
+    pub valuations: Vec<DayValuation>,
+    /// Profit of all sells, compared to the average price the sold goods were bought for
+    pub realised_pnl: f32,
+    /// Profit of the goods that are still owned, compared to the price they were bought for
+    pub unrealised_pnl: f32,
+    /// Return relative to the start capital (e.g. 0.1 for a profit of 10%)
+    pub total_return: f32,
+    /// Largest relative loss from a previous peak of the valuation (e.g. 0.2 for 20%)
+    pub max_drawdown: f32,
+    /// Standard deviation of the daily returns
+    pub volatility: f32,
+    /// Average daily return divided by the volatility, 0 if there is no volatility
+    pub sharpe_ratio: f32,
+}
+
+impl TraderReport {
+    /// Builds the report from the valuations of each day and the ledger of the trader
+    pub fn new(start_capital: f32, valuations: Vec<DayValuation>, ledger: &[LedgerEntry]) -> Self {
+        let values = valuations.iter().map(|v| v.value).collect::<Vec<f32>>();
+        let final_value = values.last().copied().unwrap_or(start_capital);
+        let realised_pnl = get_realised_pnl(ledger);
+        let returns = get_daily_returns(&values);
+        let volatility = get_std_deviation(&returns);
+        let sharpe_ratio = if volatility > f32::EPSILON {
+            get_mean(&returns) / volatility
+        } else {
+            0.0
+        };
+
+        Self {
+            start_capital,
+            final_value,
+            valuations,
+            realised_pnl,
+            // everything that is not realised yet
+            unrealised_pnl: final_value - start_capital - realised_pnl,
+            total_return: (final_value - start_capital) / start_capital,
+            max_drawdown: get_max_drawdown(&values),
+            volatility,
+            sharpe_ratio,
+        }
+    }
+}
+
+/// Returns the realised PnL of all successful buys and sells of the ledger. A sold good is
+/// compared with the average price per piece of the goods of the same kind that are owned.
+fn get_realised_pnl(ledger: &[LedgerEntry]) -> f32 {
+    // { kind: (owned quantity, price paid for it) }
+    let mut positions: HashMap<GoodKind, (f32, f32)> = HashMap::new();
+    let mut realised_pnl = 0.0;
+
+    let transactions = ledger
+        .iter()
+        .map(|entry| &entry.transaction)
+        .filter(|t| t.result == TransactionResult::Success);
+    for transaction in transactions {
+        let (quantity, cost) = positions.entry(transaction.kind).or_insert((0.0, 0.0));
+        match transaction.operation {
+            Operation::Buy => {
+                *quantity += transaction.quantity;
+                *cost += transaction.price;
+            }
+            Operation::Sell if *quantity > 0.0 => {
+                let sold_quantity = transaction.quantity.min(*quantity);
+                let sold_cost = *cost * sold_quantity / *quantity;
+                realised_pnl += transaction.price - sold_cost;
+                *quantity -= sold_quantity;
+                *cost -= sold_cost;
+            }
+            _ => {}
+        }
+    }
+    realised_pnl
+}
+
+/// Returns the relative change between each two following values
+fn get_daily_returns(values: &[f32]) -> Vec<f32> {
+    values
+        .windows(2)
+        .filter(|w| w[0] > 0.0)
+        .map(|w| w[1] / w[0] - 1.0)
+        .collect()
+}
+
+/// Returns the mean of the given values, 0 if there are none
+fn get_mean(values: &[f32]) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    values.iter().sum::<f32>() / values.len() as f32
+}
+
+/// Returns the standard deviation of the given values, 0 if there are none
+fn get_std_deviation(values: &[f32]) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+    let mean = get_mean(values);
+    let variance = values.iter().map(|v| (v - mean).powi(2)).sum::<f32>() / values.len() as f32;
+    variance.sqrt()
+}
+
+/// Returns the largest relative loss from a previous peak
+fn get_max_drawdown(values: &[f32]) -> f32 {
+    let mut peak = f32::MIN;
+    let mut max_drawdown = 0.0;
+    for value in values.iter() {
+        peak = peak.max(*value);
+        if peak > 0.0 {
+            max_drawdown = f32::max(max_drawdown, (peak - value) / peak);
+        }
+    }
+    max_drawdown
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::trader::ledger::{Ledger, Operation, Transaction, TransactionResult};
+    use crate::trader::report::{get_max_drawdown, get_realised_pnl, DayValuation, TraderReport};
+    use unitn_market_2022::good::good_kind::GoodKind;
+
+    fn transaction(operation: Operation, quantity: f32, price: f32) -> Transaction {
+        Transaction {
+            operation,
+            market: "SGX".to_string(),
+            kind: GoodKind::USD,
+            quantity,
+            price,
+            token: Some("token".to_string()),
+            result: TransactionResult::Success,
+        }
+    }
+
+    #[test]
+    fn test_realised_pnl() {
+        let mut ledger = Ledger::default();
+        ledger.record(transaction(Operation::LockBuy, 10.0, 100.0));
+        ledger.record(transaction(Operation::Buy, 10.0, 100.0));
+        ledger.record(transaction(Operation::Buy, 10.0, 140.0));
+        // sold 5 pieces for 15 EUR each, bought for 12 EUR each
+        ledger.record(transaction(Operation::Sell, 5.0, 75.0));
+        let mut failed = transaction(Operation::Sell, 5.0, 1000.0);
+        failed.result = TransactionResult::Failure("ExpiredToken".to_string());
+        ledger.record(failed);
+
+        assert_eq!(15.0, get_realised_pnl(ledger.get_entries()));
+    }
+
+    #[test]
+    fn test_max_drawdown() {
+        assert_eq!(0.0, get_max_drawdown(&[]));
+        assert_eq!(0.0, get_max_drawdown(&[100.0, 110.0, 120.0]));
+        assert_eq!(0.5, get_max_drawdown(&[100.0, 200.0, 150.0, 100.0, 180.0]));
+    }
+
+    #[test]
+    fn test_trader_report() {
+        let valuations = vec![
+            DayValuation {
+                day: 0,
+                value: 100.0,
+            },
+            DayValuation {
+                day: 1,
+                value: 110.0,
+            },
+            DayValuation {
+                day: 2,
+                value: 121.0,
+            },
+        ];
+        let report = TraderReport::new(100.0, valuations, &[]);
+        assert_eq!(121.0, report.final_value);
+        assert!((report.total_return - 0.21).abs() < 1e-5);
+        assert_eq!(0.0, report.max_drawdown);
+        assert!(report.volatility < 1e-5, "Returns are constant");
+        assert_eq!(0.0, report.realised_pnl);
+        assert_eq!(21.0, report.unrealised_pnl);
+    }
+}